@@ -0,0 +1,51 @@
+//! Fuzzing support.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use OmgWtf8;
+
+/// Generates a well-formed OMG-WTF-8 string.
+///
+/// The code units are biased towards ASCII characters and surrogates, and a
+/// supplementary character at either end may be cut in half to produce the
+/// split representation.
+impl<'a> Arbitrary<'a> for Box<OmgWtf8> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let (split_start, split_end) = u.arbitrary::<(bool, bool)>()?;
+        let mut wide = Vec::new();
+        for unit in u.arbitrary_iter::<u16>()? {
+            let unit = unit?;
+            wide.push(match unit & 3 {
+                0 => unit >> 9,
+                1 => 0xd800 | unit >> 6 & 0x3ff,
+                2 => 0xdc00 | unit >> 6 & 0x3ff,
+                _ => unit,
+            });
+        }
+
+        Ok(OmgWtf8::from_wide(&wide).split_ends(split_start, split_end))
+    }
+}
+
+#[test]
+fn test_arbitrary() {
+    let mut state = 1u64;
+    let mut split_start = false;
+    let mut split_end = false;
+    for _ in 0..256 {
+        let data: Vec<u8> = (0..64)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                (state >> 56) as u8
+            })
+            .collect();
+        let s = Box::<OmgWtf8>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let bytes = s.as_bytes();
+        assert!(OmgWtf8::validate(bytes).is_well_formed(), "{:?}", s);
+        split_start |= bytes.len() >= 3 && bytes[0] & 0xc0 == 0x80;
+        split_end |= bytes.len() >= 3 && bytes[bytes.len() - 3] >= 0xf0;
+    }
+    assert!(split_start);
+    assert!(split_end);
+}
@@ -0,0 +1,81 @@
+//! Interoperability with `bstr`.
+//!
+//! An OMG-WTF-8 string compares equal to a byte string if the bytes are
+//! well-formed OMG-WTF-8 and represent the same code points, so the split
+//! and canonical representations of a surrogate are considered equal.
+
+use bstr::{BStr, BString, ByteSlice};
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8 {
+    /// Views the underlying bytes of this string as a byte string.
+    ///
+    /// Surrogates at the edges may be in split representation.
+    pub fn as_bstr(&self) -> &BStr {
+        BStr::new(&self.0)
+    }
+}
+
+impl<'a> From<&'a OmgWtf8> for &'a BStr {
+    fn from(s: &'a OmgWtf8) -> &'a BStr {
+        s.as_bstr()
+    }
+}
+
+impl<'a> From<&'a OmgWtf8> for BString {
+    /// Converts the string to a byte string in canonical representation.
+    fn from(s: &'a OmgWtf8) -> BString {
+        BString::from(s.to_wtf8().into_owned())
+    }
+}
+
+impl From<OmgWtf8Buf> for BString {
+    /// Converts the buffer to a byte string without copying.
+    fn from(s: OmgWtf8Buf) -> BString {
+        BString::from(s.0)
+    }
+}
+
+impl PartialEq<BStr> for OmgWtf8 {
+    fn eq(&self, other: &BStr) -> bool {
+        OmgWtf8::from_bytes(other).is_ok_and(|other| *self == *other)
+    }
+}
+
+impl PartialEq<OmgWtf8> for BStr {
+    fn eq(&self, other: &OmgWtf8) -> bool {
+        *other == *self
+    }
+}
+
+impl PartialEq<BString> for OmgWtf8 {
+    fn eq(&self, other: &BString) -> bool {
+        *self == *other.as_bstr()
+    }
+}
+
+impl PartialEq<OmgWtf8> for BString {
+    fn eq(&self, other: &OmgWtf8) -> bool {
+        *other == *self.as_bstr()
+    }
+}
+
+#[test]
+fn test_bstr() {
+    let s = OmgWtf8::from_str("😀a😑");
+    assert_eq!(s[2..7].as_bstr(), BStr::new(b"\x9f\x98\x80a\xf0\x9f\x98"));
+    assert_eq!(<&BStr>::from(s), BStr::new(s.as_bytes()));
+    assert_eq!(
+        BString::from(&s[2..7]),
+        BString::from(&b"\xed\xb8\x80a\xed\xa0\xbd"[..])
+    );
+
+    assert!(s[2..7] == *BStr::new(b"\xed\xb8\x80a\xed\xa0\xbd"));
+    assert!(*BStr::new(b"\x9f\x98\x80a\xf0\x9f\x98") == s[2..7]);
+    let owned = BString::from(&s[2..7]);
+    assert!(s[2..7] == owned);
+    let owned = BString::from(s.to_owned());
+    assert!(owned == *s);
+    assert!(*s != *BStr::new(b"\xf0\x9f\x98\x80a"));
+    assert!(*OmgWtf8::EMPTY != *BStr::new(b"\xff"));
+}
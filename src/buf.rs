@@ -0,0 +1,980 @@
+use code_point::CodePoint;
+use conv::{canonicalize_in_place, code_unit_from_canonical, encode_unit};
+use std::borrow::{Borrow, Cow, ToOwned};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::{Add, AddAssign, Deref, DerefMut};
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8Buf {
+    /// Creates a new empty OMG-WTF-8 buffer.
+    pub fn new() -> Self {
+        OmgWtf8Buf(Vec::new())
+    }
+
+    /// Creates a new empty OMG-WTF-8 buffer with at least the given capacity
+    /// in bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        OmgWtf8Buf(Vec::with_capacity(capacity))
+    }
+
+    /// Returns the capacity of this buffer in bytes.
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.0.reserve(additional)
+    }
+
+    /// Reserves capacity for exactly `additional` more bytes.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.0.reserve_exact(additional)
+    }
+
+    /// Shrinks the capacity of this buffer to match its length.
+    pub fn shrink_to_fit(&mut self) {
+        self.0.shrink_to_fit()
+    }
+
+    /// Borrows the content as an OMG-WTF-8 string.
+    pub fn as_omgwtf8(&self) -> &OmgWtf8 {
+        unsafe { OmgWtf8::from_bytes_unchecked(&self.0) }
+    }
+
+    /// Mutably borrows the content as an OMG-WTF-8 string.
+    pub fn as_mut_omgwtf8(&mut self) -> &mut OmgWtf8 {
+        unsafe { OmgWtf8::from_bytes_unchecked_mut(&mut self.0) }
+    }
+
+    /// Converts this buffer into a boxed OMG-WTF-8 string.
+    pub fn into_boxed_omgwtf8(self) -> Box<OmgWtf8> {
+        unsafe { OmgWtf8::from_boxed_bytes_unchecked(self.0.into_boxed_slice()) }
+    }
+
+    /// Converts this buffer into a `String` without copying, or returns the
+    /// original buffer if it contains unpaired surrogates.
+    pub fn into_string(self) -> Result<String, OmgWtf8Buf> {
+        String::from_utf8(self.0).map_err(|e| OmgWtf8Buf(e.into_bytes()))
+    }
+
+    /// Converts this buffer into standard WTF-8 bytes without copying.
+    ///
+    /// This is always possible since the buffer is kept canonical.
+    pub fn into_wtf8(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Appends a string to the end of this buffer.
+    ///
+    /// If the string starts with a low surrogate and this buffer ends with a
+    /// high surrogate, the two are merged into a single 4-byte sequence, so
+    /// the buffer stays canonical.
+    pub fn push_omgwtf8(&mut self, s: &OmgWtf8) {
+        let (begin, middle, end) = s.canonicalize();
+        if begin != 0 {
+            self.push_code_unit(code_unit_from_canonical(begin));
+        }
+        self.0.extend_from_slice(middle);
+        if end != 0 {
+            encode_unit(&mut self.0, code_unit_from_canonical(end));
+        }
+    }
+
+    /// Appends a UTF-16 code unit to the end of this buffer.
+    ///
+    /// If the code unit is a low surrogate and this buffer ends with a high
+    /// surrogate, the two are merged into a single 4-byte sequence, so the
+    /// buffer stays canonical.
+    pub fn push_code_unit(&mut self, c: u16) {
+        if let 0xdc00..=0xdfff = c {
+            if let Some(high) = trailing_high_surrogate(&self.0) {
+                let len = self.0.len();
+                self.0.truncate(len - 3);
+                let pair = surrogate_pair(high, c);
+                self.0
+                    .extend_from_slice(pair.encode(&mut [0; 4]).as_bytes());
+                return;
+            }
+        }
+        encode_unit(&mut self.0, c);
+    }
+
+    /// Appends UTF-16 code units to the end of this buffer.
+    ///
+    /// Surrogate pairs are merged as in `from_wide()`, including a high
+    /// surrogate at the end of this buffer followed by a low surrogate at the
+    /// start of `ucs2`. This allows accumulating UTF-16 data read in chunks
+    /// without converting each chunk separately.
+    ///
+    /// ```
+    /// use omgwtf8::{OmgWtf8, OmgWtf8Buf};
+    ///
+    /// let mut buf = OmgWtf8Buf::new();
+    /// buf.push_wide(&[0x61, 0xd83d]);
+    /// buf.push_wide(&[0xde00, 0x62]);
+    /// assert_eq!(buf, "a😀b");
+    /// ```
+    #[doc(alias = "extend_from_wide")]
+    pub fn push_wide(&mut self, ucs2: &[u16]) {
+        self.0.reserve(ucs2.len());
+        for &c in ucs2 {
+            self.push_code_unit(c);
+        }
+    }
+
+    /// Creates a buffer from the numeric values of code points, returning
+    /// `None` if any value is above U+10FFFF.
+    ///
+    /// Surrogates are allowed as individual values. A high surrogate followed
+    /// by a low surrogate is merged into a supplementary character, so this
+    /// reverses `to_code_points()`.
+    pub fn from_code_points<I: IntoIterator<Item = u32>>(iter: I) -> Option<Self> {
+        iter.into_iter().map(CodePoint::from_u32).collect()
+    }
+
+    /// Shortens this buffer to the given byte length.
+    ///
+    /// If the length points to the middle of a 4-byte sequence, the high
+    /// surrogate half is kept in canonical representation. If the length is
+    /// not less than the current length, this has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length cannot be used for slicing this buffer.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len >= self.0.len() {
+            return;
+        }
+        let len = self[..new_len].len();
+        self.0.truncate(len);
+        canonicalize_in_place(&mut self.0);
+    }
+
+    /// Retains only the code points for which the predicate returns true,
+    /// compacting the buffer in place.
+    ///
+    /// Unpaired surrogates are passed to the predicate as code points too. If
+    /// removing code points brings a high and a low surrogate together, they
+    /// are merged into a supplementary character.
+    pub fn retain<F: FnMut(CodePoint) -> bool>(&mut self, mut f: F) {
+        let len = self.0.len();
+        let mut read = 0;
+        let mut write = 0;
+        while read < len {
+            let (c, n) = unsafe { OmgWtf8::from_bytes_unchecked(&self.0[read..]) }
+                .first_code_point()
+                .unwrap();
+            if f(c) {
+                let high = match c.to_u32() {
+                    0xdc00..=0xdfff => trailing_high_surrogate(&self.0[..write]),
+                    _ => None,
+                };
+                if let Some(high) = high {
+                    let pair = surrogate_pair(high, c.to_u32() as u16);
+                    let mut buf = [0; 4];
+                    let pair = pair.encode(&mut buf).as_bytes();
+                    self.0[write - 3..write + 1].copy_from_slice(pair);
+                    write += 1;
+                } else {
+                    self.0.copy_within(read..read + n, write);
+                    write += n;
+                }
+            }
+            read += n;
+        }
+        self.0.truncate(write);
+    }
+
+    /// Removes the last UTF-16 code unit from this buffer and returns it, or
+    /// `None` if the buffer is empty.
+    ///
+    /// If the buffer ends with a supplementary character, only its low
+    /// surrogate is removed, and the high surrogate is kept in canonical
+    /// representation.
+    pub fn pop_code_unit(&mut self) -> Option<u16> {
+        let start = self.0.iter().rposition(|&b| b & 0xc0 != 0x80)?;
+        let len = self.0.len() - start;
+        let lead_mask = match len {
+            1 => 0x7f,
+            2 => 0x1f,
+            3 => 0x0f,
+            _ => 0x07,
+        };
+        let c = self.0[start + 1..]
+            .iter()
+            .fold(self.0[start] as u32 & lead_mask, |c, &b| {
+                c << 6 | b as u32 & 0x3f
+            });
+        self.0.truncate(start);
+        if len < 4 {
+            return Some(c as u16);
+        }
+        let c = c - 0x1_0000;
+        encode_unit(&mut self.0, 0xd800 | (c >> 10) as u16);
+        Some(0xdc00 | (c & 0x3ff) as u16)
+    }
+
+    /// Replaces the content between two slicing indices with a string,
+    /// re-canonicalizing the surrogates at both seams.
+    fn splice(&mut self, start: usize, end: usize, s: &OmgWtf8) {
+        let tail = self[end..].to_owned();
+        let head_len = self[..start].len();
+        self.0.truncate(head_len);
+        canonicalize_in_place(&mut self.0);
+        self.push_omgwtf8(s);
+        self.push_omgwtf8(&tail);
+    }
+
+    /// Inserts a code point at a byte index.
+    ///
+    /// The index may point to the middle of a 4-byte sequence, in which case
+    /// the code point is placed between the two surrogate halves. A
+    /// surrogate inserted next to its counterpart is merged with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index cannot be used for slicing this buffer.
+    pub fn insert(&mut self, index: usize, c: CodePoint) {
+        self.splice(index, index, c.encode(&mut [0; 4]));
+    }
+
+    /// Inserts a string at a byte index.
+    ///
+    /// The index may point to the middle of a 4-byte sequence, in which case
+    /// the string is placed between the two surrogate halves. Surrogates
+    /// meeting at both ends of the string are merged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index cannot be used for slicing this buffer.
+    pub fn insert_omgwtf8(&mut self, index: usize, s: &OmgWtf8) {
+        self.splice(index, index, s);
+    }
+
+    /// Removes the code point starting at a byte index and returns it.
+    ///
+    /// If the index points to the middle of a 4-byte sequence, only the low
+    /// surrogate is removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index cannot be used for slicing this buffer, or if it
+    /// is at the end of the buffer.
+    pub fn remove(&mut self, index: usize) -> CodePoint {
+        let (c, end) = {
+            let tail = &self[index..];
+            match tail.first_code_point() {
+                Some((c, len)) => (c, self.len() - tail.len() + len),
+                None => panic!("cannot remove a code point from the end of a string"),
+            }
+        };
+        self.splice(index, end, OmgWtf8::EMPTY);
+        c
+    }
+
+    /// Inserts a UTF-16 code unit at an offset counted in UTF-16 code units.
+    ///
+    /// A surrogate inserted next to its counterpart is merged with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is larger than the number of code units.
+    pub fn insert_wide(&mut self, wide_index: usize, c: u16) {
+        let index = self.byte_index_of_wide_or_panic(wide_index);
+        self.insert(index, CodePoint::from_u32(c as u32).unwrap());
+    }
+
+    /// Removes the UTF-16 code unit at an offset counted in UTF-16 code
+    /// units and returns it.
+    ///
+    /// If the code unit is half of a supplementary character, the other
+    /// half is kept as an unpaired surrogate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is not less than the number of code units.
+    pub fn remove_wide(&mut self, wide_index: usize) -> u16 {
+        let index = self.byte_index_of_wide_or_panic(wide_index);
+        let c = match self[index..].first_code_point() {
+            Some((c, _)) => c.to_u32(),
+            None => panic!("cannot remove a code unit from the end of a string"),
+        };
+        if c < 0x1_0000 {
+            self.remove(index);
+            c as u16
+        } else {
+            // remove the high surrogate only.
+            self.splice(index, index + 2, OmgWtf8::EMPTY);
+            (0xd800 | (c - 0x1_0000) >> 10) as u16
+        }
+    }
+}
+
+/// Returns the high surrogate at the end of canonical WTF-8 bytes.
+fn trailing_high_surrogate(bytes: &[u8]) -> Option<u16> {
+    match *bytes {
+        [.., 0xed, b2 @ 0xa0..=0xaf, b3] => {
+            Some(code_unit_from_canonical((b2 as u16) << 8 | b3 as u16))
+        }
+        _ => None,
+    }
+}
+
+/// Combines a high and a low surrogate into a supplementary character.
+fn surrogate_pair(high: u16, low: u16) -> CodePoint {
+    let c = ((high as u32 & 0x3ff) << 10 | (low as u32 & 0x3ff)) + 0x1_0000;
+    CodePoint::from_u32(c).unwrap()
+}
+
+/// Concatenates the strings into a new buffer.
+///
+/// Surrogate halves meeting at the seams are merged, so the result is
+/// canonical.
+pub fn concat<S: AsRef<OmgWtf8>>(pieces: &[S]) -> OmgWtf8Buf {
+    let mut buf = OmgWtf8Buf::with_capacity(pieces.iter().map(|s| s.as_ref().len()).sum());
+    for s in pieces {
+        buf.push_omgwtf8(s.as_ref());
+    }
+    buf
+}
+
+/// Joins the strings into a new buffer, placing the separator between each
+/// pair of them.
+///
+/// Surrogate halves meeting at the seams are merged, so the result is
+/// canonical.
+pub fn join<S: AsRef<OmgWtf8>>(pieces: &[S], sep: &OmgWtf8) -> OmgWtf8Buf {
+    let mut buf = OmgWtf8Buf::new();
+    for (i, s) in pieces.iter().enumerate() {
+        if i != 0 {
+            buf.push_omgwtf8(sep);
+        }
+        buf.push_omgwtf8(s.as_ref());
+    }
+    buf
+}
+
+impl Deref for OmgWtf8Buf {
+    type Target = OmgWtf8;
+    fn deref(&self) -> &OmgWtf8 {
+        self.as_omgwtf8()
+    }
+}
+
+impl DerefMut for OmgWtf8Buf {
+    fn deref_mut(&mut self) -> &mut OmgWtf8 {
+        self.as_mut_omgwtf8()
+    }
+}
+
+/// The buffer is canonicalized when copying from a borrowed string.
+impl<'a> From<&'a OmgWtf8> for OmgWtf8Buf {
+    fn from(s: &'a OmgWtf8) -> Self {
+        let mut bytes = s.0.to_vec();
+        canonicalize_in_place(&mut bytes);
+        OmgWtf8Buf(bytes)
+    }
+}
+
+impl<'a> From<&'a str> for OmgWtf8Buf {
+    fn from(s: &'a str) -> Self {
+        OmgWtf8Buf(s.as_bytes().to_vec())
+    }
+}
+
+/// Reuses the allocation of the `String`.
+impl From<String> for OmgWtf8Buf {
+    fn from(s: String) -> Self {
+        OmgWtf8Buf(s.into_bytes())
+    }
+}
+
+/// Reuses the allocation of the buffer if it is valid UTF-8, otherwise
+/// returns the original buffer.
+impl TryFrom<OmgWtf8Buf> for String {
+    type Error = OmgWtf8Buf;
+    fn try_from(s: OmgWtf8Buf) -> Result<String, OmgWtf8Buf> {
+        s.into_string()
+    }
+}
+
+impl From<Box<OmgWtf8>> for OmgWtf8Buf {
+    fn from(s: Box<OmgWtf8>) -> Self {
+        let s = s.canonicalize_owned();
+        let bytes = unsafe { Box::from_raw(Box::into_raw(s) as *mut [u8]) };
+        OmgWtf8Buf(bytes.into_vec())
+    }
+}
+
+impl From<OmgWtf8Buf> for Box<OmgWtf8> {
+    fn from(s: OmgWtf8Buf) -> Self {
+        s.into_boxed_omgwtf8()
+    }
+}
+
+/// Allows an `OmgWtf8Buf`-keyed map be queried using `&OmgWtf8`.
+///
+/// This is sound because both types hash and compare through the canonical
+/// representation.
+impl Borrow<OmgWtf8> for OmgWtf8Buf {
+    fn borrow(&self) -> &OmgWtf8 {
+        self
+    }
+}
+
+impl ToOwned for OmgWtf8 {
+    type Owned = OmgWtf8Buf;
+    fn to_owned(&self) -> OmgWtf8Buf {
+        OmgWtf8Buf::from(self)
+    }
+}
+
+impl<'a> From<&'a OmgWtf8> for Cow<'a, OmgWtf8> {
+    fn from(s: &'a OmgWtf8) -> Self {
+        Cow::Borrowed(s)
+    }
+}
+
+impl<'a> From<&'a OmgWtf8Buf> for Cow<'a, OmgWtf8> {
+    fn from(s: &'a OmgWtf8Buf) -> Self {
+        Cow::Borrowed(s)
+    }
+}
+
+impl<'a> From<OmgWtf8Buf> for Cow<'a, OmgWtf8> {
+    fn from(s: OmgWtf8Buf) -> Self {
+        Cow::Owned(s)
+    }
+}
+
+/// Reuses the buffer if owned, otherwise copies the borrowed string.
+impl<'a> From<Cow<'a, OmgWtf8>> for OmgWtf8Buf {
+    fn from(s: Cow<'a, OmgWtf8>) -> Self {
+        s.into_owned()
+    }
+}
+
+impl OmgWtf8 {
+    /// Returns this string with both ends in canonical representation.
+    ///
+    /// The string is borrowed if it is already canonical, so the allocation
+    /// only happens when a surrogate pair is split at either end.
+    pub fn to_canonical(&self) -> Cow<'_, OmgWtf8> {
+        if self.is_canonical() {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.to_owned())
+        }
+    }
+}
+
+/// Appends a string to the buffer, merging the surrogate halves at the
+/// junction.
+impl<'a> Add<&'a OmgWtf8> for OmgWtf8Buf {
+    type Output = OmgWtf8Buf;
+    fn add(mut self, other: &'a OmgWtf8) -> OmgWtf8Buf {
+        self.push_omgwtf8(other);
+        self
+    }
+}
+
+/// Appends a string to the buffer, merging the surrogate halves at the
+/// junction.
+impl<'a> AddAssign<&'a OmgWtf8> for OmgWtf8Buf {
+    fn add_assign(&mut self, other: &'a OmgWtf8) {
+        self.push_omgwtf8(other);
+    }
+}
+
+impl Extend<char> for OmgWtf8Buf {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        for c in iter {
+            self.0
+                .extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+    }
+}
+
+/// A high surrogate followed by a low surrogate is merged into a single
+/// 4-byte sequence, even if they come from separate iterator items.
+impl Extend<CodePoint> for OmgWtf8Buf {
+    fn extend<I: IntoIterator<Item = CodePoint>>(&mut self, iter: I) {
+        for c in iter {
+            self.push_omgwtf8(c.encode(&mut [0; 4]));
+        }
+    }
+}
+
+/// A high surrogate followed by a low surrogate is merged into a single
+/// 4-byte sequence, even if they come from separate iterator items.
+impl Extend<u16> for OmgWtf8Buf {
+    fn extend<I: IntoIterator<Item = u16>>(&mut self, iter: I) {
+        for c in iter {
+            self.push_code_unit(c);
+        }
+    }
+}
+
+impl<'a> Extend<&'a OmgWtf8> for OmgWtf8Buf {
+    fn extend<I: IntoIterator<Item = &'a OmgWtf8>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_omgwtf8(s);
+        }
+    }
+}
+
+impl FromIterator<char> for OmgWtf8Buf {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut buf = OmgWtf8Buf::new();
+        buf.extend(iter);
+        buf
+    }
+}
+
+impl FromIterator<CodePoint> for OmgWtf8Buf {
+    fn from_iter<I: IntoIterator<Item = CodePoint>>(iter: I) -> Self {
+        let mut buf = OmgWtf8Buf::new();
+        buf.extend(iter);
+        buf
+    }
+}
+
+impl FromIterator<u16> for OmgWtf8Buf {
+    fn from_iter<I: IntoIterator<Item = u16>>(iter: I) -> Self {
+        let mut buf = OmgWtf8Buf::new();
+        buf.extend(iter);
+        buf
+    }
+}
+
+impl<'a> FromIterator<&'a OmgWtf8> for OmgWtf8Buf {
+    fn from_iter<I: IntoIterator<Item = &'a OmgWtf8>>(iter: I) -> Self {
+        let mut buf = OmgWtf8Buf::new();
+        buf.extend(iter);
+        buf
+    }
+}
+
+impl AsRef<OmgWtf8> for OmgWtf8Buf {
+    fn as_ref(&self) -> &OmgWtf8 {
+        self
+    }
+}
+
+impl AsMut<OmgWtf8> for OmgWtf8Buf {
+    fn as_mut(&mut self) -> &mut OmgWtf8 {
+        self
+    }
+}
+
+/// Allows formatting into the buffer with `write!`.
+impl fmt::Write for OmgWtf8Buf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl fmt::Debug for OmgWtf8Buf {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, fmt)
+    }
+}
+
+impl Eq for OmgWtf8Buf {}
+
+impl PartialEq for OmgWtf8Buf {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Ord for OmgWtf8Buf {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl PartialOrd for OmgWtf8Buf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hashes the same way as the borrowed `OmgWtf8`.
+impl Hash for OmgWtf8Buf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+#[test]
+fn test_to_owned_canonicalizes() {
+    let s = OmgWtf8::from_str("😀A😑");
+    assert_eq!(s.to_owned().as_bytes(), s.as_bytes());
+    assert_eq!(s[2..7].to_owned().as_bytes(), b"\xed\xb8\x80A\xed\xa0\xbd");
+    assert!(s[2..7].to_owned().is_canonical());
+    assert_eq!(&*s[2..7].to_owned(), &s[2..7]);
+}
+
+#[test]
+fn test_borrow_as_map_key() {
+    use std::collections::{BTreeMap, HashMap};
+
+    let s = OmgWtf8::from_str("😀A😑");
+    let mut hash_map = HashMap::new();
+    let mut btree_map = BTreeMap::new();
+    hash_map.insert(OmgWtf8Buf::from(&*OmgWtf8::from_wide(&[0xde00, 0x41])), 1);
+    btree_map.insert(OmgWtf8Buf::from(&*OmgWtf8::from_wide(&[0xde00, 0x41])), 1);
+    hash_map.insert(OmgWtf8Buf::from("A😑"), 2);
+    btree_map.insert(OmgWtf8Buf::from("A😑"), 2);
+
+    assert_eq!(hash_map.get(&s[2..5]), Some(&1));
+    assert_eq!(btree_map.get(&s[2..5]), Some(&1));
+    assert_eq!(hash_map.get(&s[4..]), Some(&2));
+    assert_eq!(btree_map.get(&s[4..]), Some(&2));
+    assert_eq!(hash_map.get(&s[4..7]), None);
+    assert_eq!(btree_map.get(&s[4..7]), None);
+}
+
+#[test]
+fn test_concat() {
+    let s = OmgWtf8::from_str("😀A😑");
+    assert_eq!(*concat(&[&s[..2], &s[2..]]), *s);
+    assert_eq!(
+        concat(&[&s[..2], &s[2..7], &s[7..]]).as_bytes(),
+        s.as_bytes()
+    );
+    assert_eq!(
+        concat(&[&s[..7], &s[..2]]).as_bytes(),
+        b"\xf0\x9f\x98\x80A\xed\xa0\xbd\xed\xa0\xbd"
+    );
+    assert_eq!(
+        concat(&[&s[7..], &s[2..]]).as_bytes(),
+        b"\xed\xb8\x91\xed\xb8\x80A\xf0\x9f\x98\x91"
+    );
+    assert_eq!(concat::<&OmgWtf8>(&[]), "");
+    assert_eq!(concat(&["a", "b"]), "ab");
+
+    let high = OmgWtf8::from_wide(&[0xd83d]);
+    let low = OmgWtf8::from_wide(&[0xde00]);
+    assert_eq!(concat(&[&*high, &*low]).as_bytes(), "😀".as_bytes());
+    assert_eq!(
+        concat(&[&*high, OmgWtf8::EMPTY, &s[2..]]).as_bytes(),
+        s.as_bytes()
+    );
+}
+
+#[test]
+fn test_push_after_non_surrogate() {
+    let mut buf = OmgWtf8Buf::from("\u{d7ff}");
+    buf.push_code_unit(0xdc00);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd7ff, 0xdc00]));
+}
+
+#[test]
+fn test_join() {
+    let s = OmgWtf8::from_str("😀A😑");
+    assert_eq!(*join(&[&s[..2], &s[2..]], OmgWtf8::EMPTY), *s);
+    assert_eq!(*join(&[&s[..2], &s[7..]], &s[2..7]), *s);
+    assert_eq!(join(&["a", "b", "c"], OmgWtf8::from_str(", ")), "a, b, c");
+    assert_eq!(join::<&str>(&[], OmgWtf8::from_str(", ")), "");
+    assert_eq!(join(&["a"], &s[2..7]), "a");
+}
+
+#[test]
+fn test_add() {
+    let s = OmgWtf8::from_str("😀A😑");
+    let buf = s[..2].to_owned() + &s[2..];
+    assert_eq!(buf.as_bytes(), s.as_bytes());
+    let buf = OmgWtf8Buf::new() + &s[7..] + OmgWtf8::from_str("b");
+    assert_eq!(buf.as_bytes(), b"\xed\xb8\x91b");
+
+    let mut buf = OmgWtf8Buf::from("x");
+    buf += &s[..7];
+    assert_eq!(buf.as_bytes(), b"x\xf0\x9f\x98\x80A\xed\xa0\xbd");
+    buf += &s[7..];
+    assert_eq!(buf.as_bytes(), "x😀A😑".as_bytes());
+}
+
+#[test]
+fn test_from_iter() {
+    let s = OmgWtf8::from_str("😀A😑");
+    let buf: OmgWtf8Buf = "😀A😑".chars().collect();
+    assert_eq!(buf.as_bytes(), s.as_bytes());
+    let buf: OmgWtf8Buf = s.encode_wide().collect();
+    assert_eq!(buf.as_bytes(), s.as_bytes());
+    let buf: OmgWtf8Buf = vec![&s[..2], &s[2..7], &s[7..]].into_iter().collect();
+    assert_eq!(buf.as_bytes(), s.as_bytes());
+    let buf: OmgWtf8Buf = vec![0xde00, 0xd83d, 0xd83d].into_iter().collect();
+    assert_eq!(buf.as_bytes(), b"\xed\xb8\x80\xed\xa0\xbd\xed\xa0\xbd");
+
+    let mut buf = OmgWtf8Buf::from("a");
+    buf.extend(vec![0xd83d]);
+    buf.extend(vec![0xde00, 0x62]);
+    assert_eq!(buf, "a😀b");
+    buf.extend("😀".chars());
+    buf.extend(vec![&s[7..]]);
+    assert_eq!(
+        buf.as_bytes(),
+        b"a\xf0\x9f\x98\x80b\xf0\x9f\x98\x80\xed\xb8\x91"
+    );
+}
+
+#[test]
+fn test_code_points() {
+    let s = OmgWtf8::from_str("a😀\u{e9}");
+    assert_eq!(s.to_code_points(), vec![0x61, 0x1f600, 0xe9]);
+    assert_eq!(s[3..].to_code_points(), vec![0xde00, 0xe9]);
+    assert_eq!(s[..3].to_code_points(), vec![0x61, 0xd83d]);
+    for s in &[s, &s[3..], &s[..3]] {
+        let buf = OmgWtf8Buf::from_code_points(s.to_code_points()).unwrap();
+        assert_eq!(*buf, **s);
+    }
+
+    let buf = OmgWtf8Buf::from_code_points(vec![0xd83d, 0xde00, 0xdc00, 0xd800]).unwrap();
+    assert_eq!(buf.as_bytes(), b"\xf0\x9f\x98\x80\xed\xb0\x80\xed\xa0\x80");
+    assert_eq!(buf.to_code_points(), vec![0x1f600, 0xdc00, 0xd800]);
+    assert_eq!(OmgWtf8Buf::from_code_points(vec![0x61, 0x110000]), None);
+    assert_eq!(
+        OmgWtf8Buf::from_code_points(vec![]),
+        Some(OmgWtf8Buf::new())
+    );
+}
+
+#[test]
+fn test_cow() {
+    let s = OmgWtf8::from_str("😀a");
+    let borrowed = Cow::from(s);
+    assert!(matches!(borrowed, Cow::Borrowed(_)));
+    let buf = s.to_owned();
+    assert!(matches!(Cow::from(&buf), Cow::Borrowed(_)));
+    let owned: Cow<OmgWtf8> = Cow::from(buf.clone());
+    assert!(matches!(owned, Cow::Owned(_)));
+    assert_eq!(borrowed, owned);
+    assert_eq!(OmgWtf8Buf::from(owned), buf);
+
+    assert!(matches!(s.to_canonical(), Cow::Borrowed(_)));
+    let canonical = s[2..].to_canonical();
+    assert!(matches!(canonical, Cow::Owned(_)));
+    assert_eq!(canonical.as_bytes(), b"\xed\xb8\x80a");
+    assert_eq!(canonical, &s[2..]);
+    assert_eq!(&s[2..], canonical);
+    assert_eq!(canonical, *OmgWtf8::from_wide(&[0xde00, 0x61]));
+    assert_eq!(OmgWtf8Buf::from(&s[2..]), canonical);
+    assert_ne!(canonical, buf);
+}
+
+#[test]
+fn test_fmt_write() {
+    use std::fmt::Write;
+
+    let mut buf: OmgWtf8Buf = vec![0xd83d].into_iter().collect();
+    let (c, n) = ('x', 7);
+    write!(buf, "{}-{:03}", c, n).unwrap();
+    assert_eq!(
+        *buf,
+        *OmgWtf8::from_wide(&[0xd83d, 0x78, 0x2d, 0x30, 0x30, 0x37])
+    );
+    buf.write_char('😀').unwrap();
+    assert_eq!(&buf[8..], "😀");
+}
+
+#[test]
+fn test_push() {
+    let s = OmgWtf8::from_str("😀A😑");
+    let mut buf = OmgWtf8Buf::new();
+    buf.push_omgwtf8(&s[..2]);
+    assert_eq!(buf.as_bytes(), b"\xed\xa0\xbd");
+    buf.push_code_unit(0xd83d);
+    assert_eq!(buf.as_bytes(), b"\xed\xa0\xbd\xed\xa0\xbd");
+    buf.push_code_unit(0xde00);
+    assert_eq!(buf.as_bytes(), b"\xed\xa0\xbd\xf0\x9f\x98\x80");
+    buf.push_code_unit(0xde00);
+    assert_eq!(buf.as_bytes(), b"\xed\xa0\xbd\xf0\x9f\x98\x80\xed\xb8\x80");
+    buf.push_code_unit(0x41);
+    buf.push_omgwtf8(&s[..2]);
+    buf.push_omgwtf8(&s[2..7]);
+    assert_eq!(buf[10..].as_bytes(), b"A\xf0\x9f\x98\x80A\xed\xa0\xbd");
+}
+
+#[test]
+fn test_push_wide() {
+    let mut buf = OmgWtf8Buf::from("a");
+    buf.push_wide(&[0xdc00, 0xd83d]);
+    assert_eq!(buf.as_bytes(), b"a\xed\xb0\x80\xed\xa0\xbd");
+    buf.push_wide(&[]);
+    buf.push_wide(&[0xde00, 0xd83d, 0xd83d, 0xde00, 0x62, 0xd800]);
+    let expected = [
+        0x61, 0xdc00, 0xd83d, 0xde00, 0xd83d, 0xd83d, 0xde00, 0x62, 0xd800,
+    ];
+    assert_eq!(*buf, *OmgWtf8::from_wide(&expected));
+    assert!(buf.is_canonical());
+
+    let mut buf = OmgWtf8Buf::from("😀");
+    buf.truncate(2);
+    buf.push_wide(&[0xde01]);
+    assert_eq!(buf, "😁");
+}
+
+#[test]
+fn test_pop_code_unit() {
+    let mut buf = OmgWtf8Buf::from("a\u{e9}\u{6e2c}😀");
+    buf.push_code_unit(0xdc00);
+    assert_eq!(buf.pop_code_unit(), Some(0xdc00));
+    assert_eq!(buf.pop_code_unit(), Some(0xde00));
+    assert_eq!(buf.as_bytes(), b"a\xc3\xa9\xe6\xb8\xac\xed\xa0\xbd");
+    assert!(buf.is_canonical());
+    assert_eq!(buf.pop_code_unit(), Some(0xd83d));
+    assert_eq!(buf.pop_code_unit(), Some(0x6e2c));
+    assert_eq!(buf.pop_code_unit(), Some(0xe9));
+    assert_eq!(buf.pop_code_unit(), Some(0x61));
+    assert_eq!(buf.pop_code_unit(), None);
+    assert!(buf.is_empty());
+
+    let s = OmgWtf8::from_str("😀A😑");
+    let mut buf = s.to_owned();
+    let mut units: Vec<_> = ::std::iter::from_fn(|| buf.pop_code_unit()).collect();
+    units.reverse();
+    assert_eq!(*s, units[..]);
+}
+
+#[test]
+fn test_insert_remove() {
+    let cp = |c| CodePoint::from_u32(c).unwrap();
+
+    let mut buf = OmgWtf8Buf::from("😀A");
+    buf.insert(2, cp(0x62));
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0x62, 0xde00, 0x41]));
+    assert_eq!(buf.remove(3), cp(0x62));
+    assert_eq!(buf, "😀A");
+    buf.insert(4, cp(0xd83d));
+    buf.insert(7, cp(0xde11));
+    assert_eq!(buf, "😀😑A");
+    assert_eq!(buf.remove(0), cp(0x1f600));
+    assert_eq!(buf.remove(2), cp(0xde11));
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0x41]));
+    assert!(buf.is_canonical());
+
+    let s = OmgWtf8::from_str("😀A😑");
+    let mut buf = OmgWtf8Buf::from("xy");
+    buf.insert_omgwtf8(1, &s[2..7]);
+    assert_eq!(
+        *buf,
+        *OmgWtf8::from_wide(&[0x78, 0xde00, 0x41, 0xd83d, 0x79])
+    );
+    buf.insert_omgwtf8(1, &s[..2]);
+    let index = buf.len() - 1;
+    buf.insert_omgwtf8(index, &s[7..]);
+    assert_eq!(buf, "x😀A😑y");
+}
+
+#[test]
+fn test_insert_remove_wide() {
+    let mut buf = OmgWtf8Buf::from("😀A");
+    buf.insert_wide(1, 0x62);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0x62, 0xde00, 0x41]));
+    assert_eq!(buf.remove_wide(1), 0x62);
+    assert_eq!(buf, "😀A");
+    assert_eq!(buf.remove_wide(0), 0xd83d);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xde00, 0x41]));
+    buf.insert_wide(0, 0xd83d);
+    assert_eq!(buf, "😀A");
+    assert_eq!(buf.remove_wide(1), 0xde00);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0x41]));
+    buf.insert_wide(2, 0x1234);
+    assert_eq!(buf.remove_wide(2), 0x1234);
+}
+
+#[test]
+#[should_panic(expected = "wide index 3 is out of bounds of string of wide length 2")]
+fn test_insert_wide_out_of_bounds() {
+    OmgWtf8Buf::from("ab").insert_wide(3, 0x41);
+}
+
+#[test]
+#[should_panic(expected = "byte index 1 is not a char boundary")]
+fn test_insert_interior() {
+    OmgWtf8Buf::from("\u{e9}").insert(1, CodePoint::from('a'));
+}
+
+#[test]
+fn test_retain() {
+    let mut buf = OmgWtf8Buf::from("a😀b\u{e9}c");
+    buf.retain(|c| c.to_u32() > 0x7f);
+    assert_eq!(buf, "😀\u{e9}");
+
+    let mut buf: OmgWtf8Buf = vec![0xd83d, 0x41, 0xde00, 0x42, 0xdc00]
+        .into_iter()
+        .collect();
+    let mut seen = vec![];
+    buf.retain(|c| {
+        seen.push(c.to_u32());
+        c.to_u32() != 0x41
+    });
+    assert_eq!(seen, vec![0xd83d, 0x41, 0xde00, 0x42, 0xdc00]);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0xde00, 0x42, 0xdc00]));
+    assert_eq!(&buf[..4], "😀");
+    assert!(buf.is_canonical());
+    buf.retain(|c| !c.is_surrogate());
+    assert_eq!(buf, "😀B");
+}
+
+#[test]
+fn test_truncate() {
+    let mut buf = OmgWtf8Buf::from("a😀b");
+    buf.truncate(10);
+    assert_eq!(buf, "a😀b");
+    buf.truncate(5);
+    assert_eq!(buf, "a😀");
+    buf.truncate(3);
+    assert_eq!(buf.as_bytes(), b"a\xed\xa0\xbd");
+    buf.truncate(0);
+    assert!(buf.is_empty());
+}
+
+#[test]
+#[should_panic(
+    expected = "byte index 2 is not a char boundary (FourByteSeq1) of string of length 6"
+)]
+fn test_truncate_interior() {
+    OmgWtf8Buf::from("a😀b").truncate(2);
+}
+
+#[test]
+fn test_capacity() {
+    let mut buf = OmgWtf8Buf::with_capacity(10);
+    assert!(buf.capacity() >= 10);
+    buf.push_omgwtf8(OmgWtf8::from_str("abc"));
+    buf.reserve(20);
+    assert!(buf.capacity() >= 23);
+    buf.reserve_exact(30);
+    assert!(buf.capacity() >= 33);
+    buf.shrink_to_fit();
+    assert_eq!(buf, "abc");
+    assert!(buf.capacity() >= 3);
+}
+
+#[test]
+fn test_string_conversion() {
+    use std::convert::TryInto;
+
+    let string = String::from("a😀b");
+    let ptr = string.as_ptr();
+    let buf = OmgWtf8Buf::from(string);
+    assert_eq!(buf.as_bytes().as_ptr(), ptr);
+    let string: String = buf.try_into().unwrap();
+    assert_eq!(string.as_ptr(), ptr);
+    assert_eq!(string, "a😀b");
+
+    let mut buf = OmgWtf8Buf::from(string);
+    buf.truncate(3);
+    let buf = buf.into_string().unwrap_err();
+    assert_eq!(buf.as_bytes(), b"a\xed\xa0\xbd");
+    assert_eq!(buf.as_bytes().as_ptr(), ptr);
+}
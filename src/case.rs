@@ -0,0 +1,212 @@
+#[cfg(feature = "unicode")]
+use caseless::{default_case_fold_str, Caseless};
+#[cfg(feature = "unicode")]
+use std::char::decode_utf16;
+use std::str;
+use validate::find_surrogate;
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8 {
+    /// Returns a copy of this string where ASCII letters are mapped to their
+    /// lowercase equivalents.
+    ///
+    /// Non-ASCII characters and unpaired surrogates are left unchanged.
+    pub fn to_ascii_lowercase(&self) -> OmgWtf8Buf {
+        let mut buf = self.to_owned();
+        buf.make_ascii_lowercase();
+        buf
+    }
+
+    /// Returns a copy of this string where ASCII letters are mapped to their
+    /// uppercase equivalents.
+    ///
+    /// Non-ASCII characters and unpaired surrogates are left unchanged.
+    pub fn to_ascii_uppercase(&self) -> OmgWtf8Buf {
+        let mut buf = self.to_owned();
+        buf.make_ascii_uppercase();
+        buf
+    }
+
+    /// Converts this string to its ASCII lowercase equivalent in place.
+    ///
+    /// Non-ASCII characters and unpaired surrogates are left unchanged.
+    pub fn make_ascii_lowercase(&mut self) {
+        self.0.make_ascii_lowercase()
+    }
+
+    /// Converts this string to its ASCII uppercase equivalent in place.
+    ///
+    /// Non-ASCII characters and unpaired surrogates are left unchanged.
+    pub fn make_ascii_uppercase(&mut self) {
+        self.0.make_ascii_uppercase()
+    }
+
+    /// Returns the lowercase equivalent of this string.
+    ///
+    /// Every run of valid Unicode scalar values is mapped like
+    /// `str::to_lowercase`. Unpaired surrogates are left unchanged.
+    pub fn to_lowercase(&self) -> OmgWtf8Buf {
+        self.map_valid_runs(str::to_lowercase)
+    }
+
+    /// Returns the uppercase equivalent of this string.
+    ///
+    /// Every run of valid Unicode scalar values is mapped like
+    /// `str::to_uppercase`. Unpaired surrogates are left unchanged.
+    pub fn to_uppercase(&self) -> OmgWtf8Buf {
+        self.map_valid_runs(str::to_uppercase)
+    }
+
+    /// Applies a string transformation to every run of valid UTF-8 between
+    /// the unpaired surrogates, and collects the result in canonical form.
+    pub(crate) fn map_valid_runs<F: Fn(&str) -> String>(&self, f: F) -> OmgWtf8Buf {
+        let (begin, mut middle, end) = self.canonicalize();
+        let mut bytes = Vec::with_capacity(self.len());
+        if begin != 0 {
+            bytes.extend_from_slice(&[0xed, (begin >> 8) as u8, begin as u8]);
+        }
+        loop {
+            let pos = find_surrogate(middle).unwrap_or(middle.len());
+            bytes.extend_from_slice(
+                f(unsafe { str::from_utf8_unchecked(&middle[..pos]) }).as_bytes(),
+            );
+            if pos == middle.len() {
+                break;
+            }
+            bytes.extend_from_slice(&middle[pos..pos + 3]);
+            middle = &middle[pos + 3..];
+        }
+        if end != 0 {
+            bytes.extend_from_slice(&[0xed, (end >> 8) as u8, end as u8]);
+        }
+        OmgWtf8Buf(bytes)
+    }
+
+    /// Returns this string after full Unicode case folding.
+    ///
+    /// Every run of valid Unicode scalar values is folded, and unpaired
+    /// surrogates are left unchanged. Two strings are equal after folding
+    /// exactly when they are equal by [`eq_ignore_case`], so the result can
+    /// be stored as the key of a case-insensitive index.
+    ///
+    /// [`eq_ignore_case`]: #method.eq_ignore_case
+    #[cfg(feature = "unicode")]
+    pub fn casefold(&self) -> OmgWtf8Buf {
+        self.map_valid_runs(default_case_fold_str)
+    }
+
+    /// Checks whether two strings are equal after full Unicode case folding.
+    ///
+    /// Unpaired surrogates are not case-folded, and are compared as-is.
+    #[cfg(feature = "unicode")]
+    pub fn eq_ignore_case(&self, other: &Self) -> bool {
+        fn case_fold(s: &OmgWtf8) -> impl Iterator<Item = Result<char, u16>> + '_ {
+            decode_utf16(s.encode_wide()).flat_map(|r| {
+                let (c, surrogate) = match r {
+                    Ok(c) => (Some(c), None),
+                    Err(e) => (None, Some(e.unpaired_surrogate())),
+                };
+                c.into_iter()
+                    .default_case_fold()
+                    .map(Ok)
+                    .chain(surrogate.map(Err))
+            })
+        }
+        case_fold(self).eq(case_fold(other))
+    }
+}
+
+#[test]
+fn test_to_ascii_case() {
+    let s = OmgWtf8::from_str("C:\\Ünï😀.TxT");
+    assert_eq!(s.to_ascii_lowercase(), "c:\\Ünï😀.txt");
+    assert_eq!(s.to_ascii_uppercase(), "C:\\ÜNï😀.TXT");
+    assert_eq!(s[..8].to_ascii_uppercase(), "C:\\ÜNï");
+    assert_eq!(
+        *s[10..].to_ascii_lowercase(),
+        *OmgWtf8::from_wide(&[0xde00, 0x2e, 0x74, 0x78, 0x74])
+    );
+    assert!(s[10..].to_ascii_lowercase().is_canonical());
+}
+
+#[test]
+fn test_make_ascii_case() {
+    let mut string = String::from("C:\\Ünï😀.TxT");
+    OmgWtf8::from_mut_str(&mut string).make_ascii_uppercase();
+    assert_eq!(string, "C:\\ÜNï😀.TXT");
+
+    let mut buf = OmgWtf8Buf::from(&*OmgWtf8::from_wide(&[0xdc00, 0x61, 0xd800]));
+    buf.make_ascii_uppercase();
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xdc00, 0x41, 0xd800]));
+    buf.as_mut_omgwtf8().make_ascii_lowercase();
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xdc00, 0x61, 0xd800]));
+
+    let mut boxed: Box<OmgWtf8> = OmgWtf8::from_str("😀A😑")[2..7].into();
+    boxed.make_ascii_lowercase();
+    assert_eq!(&*boxed, &*OmgWtf8::from_wide(&[0xde00, 0x61, 0xd83d]));
+}
+
+#[test]
+fn test_to_case() {
+    let s = OmgWtf8::from_str("Straße ΟΔΟΣ 😀");
+    assert_eq!(s.to_uppercase(), "STRASSE ΟΔΟΣ 😀");
+    assert_eq!(s.to_lowercase(), "straße οδος 😀");
+
+    let s = OmgWtf8::from_wide(&[0xdc00, 0xdf, 0xd800, 0x3a3, 0x61, 0xd800]);
+    assert_eq!(
+        *s.to_uppercase(),
+        *OmgWtf8::from_wide(&[0xdc00, 0x53, 0x53, 0xd800, 0x3a3, 0x41, 0xd800])
+    );
+    assert_eq!(
+        *s.to_lowercase(),
+        *OmgWtf8::from_wide(&[0xdc00, 0xdf, 0xd800, 0x3c3, 0x61, 0xd800])
+    );
+
+    let s = OmgWtf8::from_str("😀É😀");
+    assert_eq!(
+        *s[2..8].to_lowercase(),
+        *OmgWtf8::from_wide(&[0xde00, 0xe9, 0xd83d])
+    );
+    assert!(s[2..8].to_lowercase().is_canonical());
+}
+
+#[cfg(feature = "unicode")]
+#[test]
+fn test_eq_ignore_case() {
+    let s = OmgWtf8::from_str("Straße😀");
+    assert!(s.eq_ignore_case(OmgWtf8::from_str("STRASSE😀")));
+    assert!(s.eq_ignore_case(OmgWtf8::from_str("straße😀")));
+    assert!(!s.eq_ignore_case(OmgWtf8::from_str("strase😀")));
+    let mut wide: Vec<u16> = "STRASSE".encode_utf16().collect();
+    wide.push(0xd83d);
+    assert!(s[..9].eq_ignore_case(&OmgWtf8::from_wide(&wide)));
+    assert!(!s[..9].eq_ignore_case(OmgWtf8::from_str("straße")));
+
+    let a = OmgWtf8::from_wide(&[0x41, 0xdc00, 0x42]);
+    let b = OmgWtf8::from_wide(&[0x61, 0xdc00, 0x62]);
+    let c = OmgWtf8::from_wide(&[0x61, 0xdc01, 0x62]);
+    assert!(a.eq_ignore_case(&b));
+    assert!(!a.eq_ignore_case(&c));
+}
+
+#[cfg(feature = "unicode")]
+#[test]
+fn test_casefold() {
+    let s = OmgWtf8::from_str("Straße ΟΔΟΣ😀");
+    assert_eq!(s.casefold(), "strasse οδοσ😀");
+    assert_eq!(s.casefold(), OmgWtf8::from_str("STRASSE οδος😀").casefold());
+
+    let a = OmgWtf8::from_wide(&[0xdc00, 0x41, 0xdf, 0xd800]);
+    assert_eq!(
+        *a.casefold(),
+        *OmgWtf8::from_wide(&[0xdc00, 0x61, 0x73, 0x73, 0xd800])
+    );
+    let b = OmgWtf8::from_wide(&[0xdc00, 0x61, 0x53, 0x73, 0xd800]);
+    assert!(a.eq_ignore_case(&b));
+    assert_eq!(a.casefold(), b.casefold());
+
+    let s = OmgWtf8::from_str("😀É😀");
+    let folded = s[2..8].casefold();
+    assert!(folded.is_canonical());
+    assert_eq!(*folded, *OmgWtf8::from_wide(&[0xde00, 0xe9, 0xd83d]));
+}
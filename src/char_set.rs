@@ -0,0 +1,333 @@
+//! Patterns matching any character of a set.
+
+use pattern::{
+    DoubleEndedSearcher, EndCursor, Haystack, Pattern, ReverseSearcher, Searcher, StartCursor,
+};
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+use std::str;
+use OmgWtf8;
+
+/// A set of characters, used as a pattern matching any one of them.
+///
+/// The set precomputes which bytes may start the UTF-8 encoding of its
+/// characters, so scanning a haystack only decodes the characters starting
+/// with such a byte. Unpaired surrogates are never matched.
+///
+/// ```
+/// use omgwtf8::{CharSet, MatchExt, OmgWtf8};
+///
+/// let digits = CharSet::from_ranges(vec!['0'..='9', '٠'..='٩']);
+/// let s = OmgWtf8::from_str("abc٣12");
+/// assert_eq!(s.find(&digits), Some(3));
+/// assert_eq!(s.split(&digits).count(), 4);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CharSet {
+    /// Sorted, non-overlapping and non-adjacent ranges of the characters.
+    ranges: Vec<RangeInclusive<char>>,
+    /// Bit `b` is set if a character in the set may start with the byte `b`.
+    first_bytes: [u64; 4],
+}
+
+fn width_from_first_byte(b: u8) -> usize {
+    match b {
+        0x00..=0x7f => 1,
+        0x80..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
+
+fn first_byte(c: char) -> u8 {
+    let mut buf = [0; 4];
+    c.encode_utf8(&mut buf);
+    buf[0]
+}
+
+impl CharSet {
+    /// Creates a set from a list of characters.
+    pub fn new(chars: &[char]) -> Self {
+        Self::from_ranges(chars.iter().map(|&c| c..=c))
+    }
+
+    /// Creates a set from ranges of characters. Empty ranges are ignored.
+    pub fn from_ranges<I: IntoIterator<Item = RangeInclusive<char>>>(ranges: I) -> Self {
+        let mut sorted = ranges
+            .into_iter()
+            .filter(|r| r.start() <= r.end())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|r| *r.start());
+        let mut ranges: Vec<RangeInclusive<char>> = Vec::with_capacity(sorted.len());
+        for r in sorted {
+            if let Some(last) = ranges.last_mut() {
+                if *r.start() as u32 <= *last.end() as u32 + 1 {
+                    if r.end() > last.end() {
+                        *last = *last.start()..=*r.end();
+                    }
+                    continue;
+                }
+            }
+            ranges.push(r);
+        }
+
+        // the first byte of the UTF-8 encoding grows with the code point, so
+        // every character in a range starts with a byte between those of the
+        // range's bounds.
+        let mut first_bytes = [0; 4];
+        for r in &ranges {
+            for b in first_byte(*r.start())..=first_byte(*r.end()) {
+                first_bytes[usize::from(b >> 6)] |= 1 << (b & 63);
+            }
+        }
+        CharSet {
+            ranges,
+            first_bytes,
+        }
+    }
+
+    /// Returns whether the character is in this set.
+    pub fn contains(&self, c: char) -> bool {
+        self.ranges
+            .binary_search_by(|r| {
+                if *r.end() < c {
+                    Ordering::Less
+                } else if *r.start() > c {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    fn may_start_with(&self, b: u8) -> bool {
+        self.first_bytes[usize::from(b >> 6)] & (1 << (b & 63)) != 0
+    }
+
+    /// Returns the width of the character at `pos` if it is in this set.
+    fn match_at(&self, bytes: &[u8], pos: usize) -> Option<usize> {
+        let b = bytes[pos];
+        if !self.may_start_with(b) {
+            return None;
+        }
+        let width = width_from_first_byte(b);
+        // surrogates, and the high half of a split surrogate pair at the end,
+        // are rejected here.
+        let s = str::from_utf8(bytes.get(pos..pos + width)?).ok()?;
+        if self.contains(s.chars().next()?) {
+            Some(width)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> From<&'a [char]> for CharSet {
+    fn from(chars: &'a [char]) -> Self {
+        CharSet::new(chars)
+    }
+}
+
+/// Searcher for any character of a [`CharSet`](struct.CharSet.html).
+pub struct CharSetSearcher<'p, 'h> {
+    haystack: &'h OmgWtf8,
+    set: &'p CharSet,
+    begin: usize,
+    end: usize,
+}
+
+impl<'p, 'h> Pattern<&'h OmgWtf8> for &'p CharSet {
+    type Searcher = CharSetSearcher<'p, 'h>;
+
+    fn into_searcher(self, haystack: &'h OmgWtf8) -> CharSetSearcher<'p, 'h> {
+        CharSetSearcher {
+            haystack,
+            set: self,
+            begin: 0,
+            end: haystack.len(),
+        }
+    }
+
+    fn is_prefix_of(self, haystack: &'h OmgWtf8) -> bool {
+        !haystack.is_empty() && self.match_at(&haystack.0, 0).is_some()
+    }
+
+    fn is_suffix_of(self, haystack: &'h OmgWtf8) -> bool {
+        let bytes = &haystack.0;
+        (1..=4.min(bytes.len())).any(|width| {
+            let pos = bytes.len() - width;
+            self.match_at(bytes, pos) == Some(width)
+        })
+    }
+}
+
+impl<'p, 'h> Searcher<&'h OmgWtf8> for CharSetSearcher<'p, 'h> {
+    fn haystack(&self) -> &'h OmgWtf8 {
+        self.haystack
+    }
+
+    fn next_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        let bytes = &self.haystack.0[..self.end];
+        while self.begin < self.end {
+            let pos = self.begin;
+            self.begin += 1;
+            if let Some(width) = self.set.match_at(bytes, pos) {
+                self.begin = pos + width;
+                return Some((StartCursor::new(pos), EndCursor::new(pos + width)));
+            }
+        }
+        None
+    }
+
+    /// Returns the region between the current position and the next match
+    /// as a whole. The match itself is left for the next call.
+    fn next_reject(&mut self) -> Option<(StartCursor, EndCursor)> {
+        loop {
+            let begin = StartCursor::new(self.begin);
+            let end = match self.next_match() {
+                Some((start, _)) if start == begin => continue,
+                Some((start, _)) => {
+                    self.begin = start.pos();
+                    Haystack::start_to_end_cursor(&self.haystack, start)
+                }
+                None => EndCursor::new(self.end),
+            };
+            return if begin < end {
+                Some((begin, end))
+            } else {
+                None
+            };
+        }
+    }
+}
+
+impl<'p, 'h> ReverseSearcher<&'h OmgWtf8> for CharSetSearcher<'p, 'h> {
+    fn next_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        let bytes = &self.haystack.0[..self.end];
+        let mut end = self.end;
+        while end > self.begin {
+            // the match ending at `end` starts at most 4 bytes before it.
+            let found = (1..=4.min(end - self.begin))
+                .find(|&width| self.set.match_at(bytes, end - width) == Some(width));
+            if let Some(width) = found {
+                self.end = end - width;
+                return Some((StartCursor::new(end - width), EndCursor::new(end)));
+            }
+            end -= 1;
+        }
+        self.end = self.begin;
+        None
+    }
+
+    fn next_reject_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        loop {
+            let end = EndCursor::new(self.end);
+            let start = match self.next_match_back() {
+                Some((_, e)) if e == end => continue,
+                Some((_, e)) => {
+                    self.end = e.pos();
+                    Haystack::end_to_start_cursor(&self.haystack, e)
+                }
+                None => StartCursor::new(self.begin),
+            };
+            return if start < end {
+                Some((start, end))
+            } else {
+                None
+            };
+        }
+    }
+}
+
+// characters never overlap, so both ends find the same matches.
+impl<'p, 'h> DoubleEndedSearcher<&'h OmgWtf8> for CharSetSearcher<'p, 'h> {}
+
+#[test]
+fn test_char_set() {
+    let set = CharSet::from_ranges(vec![
+        'x'..='z',
+        'a'..='c',
+        'd'..='d',
+        '😀'..='😂',
+        'q'..='p',
+    ]);
+    assert_eq!(set.ranges, vec!['a'..='d', 'x'..='z', '😀'..='😂']);
+    assert!(set.contains('a'));
+    assert!(set.contains('d'));
+    assert!(set.contains('😁'));
+    assert!(!set.contains('e'));
+    assert!(!set.contains('😃'));
+    assert!(set.may_start_with(b'a'));
+    assert!(set.may_start_with(0xf0));
+    assert!(!set.may_start_with(b'A'));
+
+    let set = CharSet::new(&['b', '😀', 'b', '\u{d7ff}']);
+    assert_eq!(set, CharSet::from(&['😀', '\u{d7ff}', 'b'][..]));
+    assert_eq!(
+        set.ranges,
+        vec!['b'..='b', '\u{d7ff}'..='\u{d7ff}', '😀'..='😀']
+    );
+}
+
+#[test]
+fn test_char_set_searcher() {
+    fn matches(haystack: &OmgWtf8, set: &CharSet) -> Vec<(usize, usize)> {
+        let mut searcher = set.into_searcher(haystack);
+        let mut result = Vec::new();
+        while let Some((start, end)) = searcher.next_match() {
+            result.push((start.pos(), end.pos()));
+        }
+        result
+    }
+    fn matches_back(haystack: &OmgWtf8, set: &CharSet) -> Vec<(usize, usize)> {
+        let mut searcher = set.into_searcher(haystack);
+        let mut result = Vec::new();
+        while let Some((start, end)) = searcher.next_match_back() {
+            result.push((start.pos(), end.pos()));
+        }
+        result.reverse();
+        result
+    }
+
+    let set = CharSet::new(&['a', '😀', '\u{d7ff}']);
+    let haystack = OmgWtf8::from_str("😀a\u{d7ff}b😀");
+    assert_eq!(
+        matches(haystack, &set),
+        vec![(0, 4), (4, 5), (5, 8), (9, 13)]
+    );
+    assert_eq!(matches_back(haystack, &set), matches(haystack, &set));
+
+    // the halves of a split surrogate pair never match.
+    let haystack = &haystack[2..11];
+    assert_eq!(matches(haystack, &set), vec![(3, 4), (4, 7)]);
+    assert_eq!(matches_back(haystack, &set), matches(haystack, &set));
+    assert!(!set.is_prefix_of(haystack));
+    assert!(!set.is_suffix_of(haystack));
+    assert!(set.is_prefix_of(OmgWtf8::from_str("😀b")));
+    assert!(set.is_suffix_of(OmgWtf8::from_str("b😀")));
+    assert!(!set.is_suffix_of(OmgWtf8::EMPTY));
+
+    let haystack = OmgWtf8::from_wide(&[0xd83d, 0x61, 0x62, 0x61, 0x63]);
+    let mut searcher = set.into_searcher(&*haystack);
+    let reject = |start, end| Some((StartCursor::new(start), EndCursor::new(end)));
+    assert_eq!(searcher.next_reject(), reject(0, 3));
+    assert_eq!(searcher.next_reject(), reject(4, 5));
+    assert_eq!(searcher.next_reject(), reject(6, 7));
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = set.into_searcher(&*haystack);
+    assert_eq!(searcher.next_reject(), reject(0, 3));
+    assert_eq!(searcher.next_match(), reject(3, 4));
+    assert_eq!(searcher.next_reject(), reject(4, 5));
+    assert_eq!(searcher.next_match(), reject(5, 6));
+    assert_eq!(searcher.next_match(), None);
+
+    let mut searcher = set.into_searcher(&*haystack);
+    assert_eq!(searcher.next_reject_back(), reject(6, 7));
+    assert_eq!(searcher.next_match_back(), reject(5, 6));
+    assert_eq!(searcher.next_reject_back(), reject(4, 5));
+    assert_eq!(searcher.next_match_back(), reject(3, 4));
+    assert_eq!(searcher.next_reject_back(), reject(0, 3));
+    assert_eq!(searcher.next_reject_back(), None);
+}
@@ -1,7 +1,12 @@
-use OmgWtf8;
-use conv::ThreeByteSeq;
+use {OmgWtf8, OmgWtf8Buf};
+use conv::{code_unit_from_canonical, ThreeByteSeq};
+use std::borrow::Cow;
 use std::hash::{Hash, Hasher};
 use std::cmp::Ordering;
+#[cfg(windows)]
+use std::ffi::OsStr;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
 
 impl OmgWtf8 {
     /// Split the string into three parts: the beginning low surrogate, the
@@ -9,16 +14,16 @@ impl OmgWtf8 {
     pub(crate) fn canonicalize(&self) -> (u16, &[u8], u16) {
         let len = self.0.len();
         match len {
-            0...2 => (0, &self.0, 0),
+            0..=2 => (0, &self.0, 0),
             3 => match ThreeByteSeq::new(&self.0).canonicalize() {
-                c @ 0xa000...0xafff => (0, &[], c),
-                c @ 0xb000...0xbfff => (c, &[], 0),
+                c @ 0xa000..=0xafff => (0, &[], c),
+                c @ 0xb000..=0xbfff => (c, &[], 0),
                 _ => (0, &self.0, 0),
             },
-            4...5 => match ThreeByteSeq::new(&self.0).canonicalize() {
-                c @ 0xb000...0xbfff => (c, &self.0[3..], 0),
+            4..=5 => match ThreeByteSeq::new(&self.0).canonicalize() {
+                c @ 0xb000..=0xbfff => (c, &self.0[3..], 0),
                 _ => match ThreeByteSeq::new(&self.0[len - 3..]).canonicalize() {
-                    c @ 0xa000...0xafff => (0, &self.0[..len - 3], c),
+                    c @ 0xa000..=0xafff => (0, &self.0[..len - 3], c),
                     _ => (0, &self.0, 0),
                 },
             },
@@ -26,14 +31,108 @@ impl OmgWtf8 {
                 let beg = ThreeByteSeq::new(&self.0).canonicalize();
                 let end = ThreeByteSeq::new(&self.0[len - 3..]).canonicalize();
                 match (beg, end) {
-                    (0xb000...0xbfff, 0xa000...0xafff) => (beg, &self.0[3..len - 3], end),
-                    (0xb000...0xbfff, _) => (beg, &self.0[3..], 0),
-                    (_, 0xa000...0xafff) => (0, &self.0[..len - 3], end),
+                    (0xb000..=0xbfff, 0xa000..=0xafff) => (beg, &self.0[3..len - 3], end),
+                    (0xb000..=0xbfff, _) => (beg, &self.0[3..], 0),
+                    (_, 0xa000..=0xafff) => (0, &self.0[..len - 3], end),
                     _ => (0, &self.0, 0),
                 }
             }
         }
     }
+
+    /// Splits the string into three parts: the unpaired low surrogate at the
+    /// beginning, the well-formed WTF-8 string in the middle, and the
+    /// unpaired high surrogate at the end.
+    ///
+    /// The surrogates are returned as WTF-16 code units, no matter whether
+    /// they are in split or canonical representation.
+    pub fn surrogate_parts(&self) -> (Option<u16>, &Self, Option<u16>) {
+        let (begin, middle, end) = self.canonicalize();
+        let to_code_unit = |c| {
+            if c == 0 {
+                None
+            } else {
+                Some(code_unit_from_canonical(c))
+            }
+        };
+        (
+            to_code_unit(begin),
+            unsafe { Self::from_bytes_unchecked(middle) },
+            to_code_unit(end),
+        )
+    }
+
+    /// Returns whether the first UTF-16 code unit of this string is `c`.
+    ///
+    /// A string starting with the low half of a split 4-byte sequence starts
+    /// with that low surrogate, consistent with `surrogate_parts()`.
+    pub fn starts_with_code_unit(&self, c: u16) -> bool {
+        self.first_code_unit() == Some(c)
+    }
+
+    /// Returns whether the last UTF-16 code unit of this string is `c`.
+    ///
+    /// A string ending with the high half of a split 4-byte sequence ends
+    /// with that high surrogate, consistent with `surrogate_parts()`.
+    pub fn ends_with_code_unit(&self, c: u16) -> bool {
+        self.last_code_unit() == Some(c)
+    }
+}
+
+impl OmgWtf8 {
+    /// Compares two strings by their canonical bytes.
+    ///
+    /// This ordering is consistent with `==`, and is cheaper than `cmp()`
+    /// when both strings are already canonical, but the order of strings
+    /// containing surrogates is not meaningful beyond being total.
+    pub fn cmp_bytes(&self, other: &Self) -> Ordering {
+        if self.is_canonical() && other.is_canonical() {
+            return self.0.cmp(&other.0);
+        }
+        fn canonical_bytes(s: &OmgWtf8) -> impl Iterator<Item = u8> + '_ {
+            let (begin, middle, end) = s.canonicalize();
+            let surrogate = |c: u16| {
+                let len = if c == 0 { 0 } else { 3 };
+                IntoIterator::into_iter([0xed, (c >> 8) as u8, c as u8]).take(len)
+            };
+            surrogate(begin)
+                .chain(middle.iter().cloned())
+                .chain(surrogate(end))
+        }
+        canonical_bytes(self).cmp(canonical_bytes(other))
+    }
+
+    /// Compares two strings by their UTF-16 code units, i.e. the sequences
+    /// produced by `encode_wide()`.
+    ///
+    /// This is the order of `Ord`, and is used by Windows and JavaScript
+    /// when sorting strings. It differs from the UTF-8 order for code points
+    /// U+E000 to U+FFFF, which are sorted after all supplementary code
+    /// points.
+    pub fn cmp_wide(&self, other: &Self) -> Ordering {
+        self.encode_wide().cmp(other.encode_wide())
+    }
+}
+
+/// A wrapper of an OMG-WTF-8 string which is ordered by
+/// [`OmgWtf8::cmp_bytes`] instead of `OmgWtf8::cmp`.
+///
+/// This is useful as a `BTreeMap` key when only a consistent order is needed.
+///
+/// [`OmgWtf8::cmp_bytes`]: struct.OmgWtf8.html#method.cmp_bytes
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OrdBytes<'a>(pub &'a OmgWtf8);
+
+impl<'a> Ord for OrdBytes<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp_bytes(other.0)
+    }
+}
+
+impl<'a> PartialOrd for OrdBytes<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 /// Two OMG-WTF-8 strings can be compared for equality.
@@ -42,16 +141,27 @@ impl Eq for OmgWtf8 {}
 /// Two OMG-WTF-8 strings can be compared for partial equality.
 impl PartialEq for OmgWtf8 {
     fn eq(&self, other: &Self) -> bool {
-        self.canonicalize() == other.canonicalize()
+        // both representations of a surrogate are 3 bytes long, so equal
+        // strings always have the same length, and only differ at the edges.
+        if self.0.len() != other.0.len() {
+            return false;
+        }
+        self.0 == other.0 || self.canonicalize() == other.canonicalize()
     }
 }
 
-/// Two OMG-WTF-8 strings can be totally ordered.
+/// Two OMG-WTF-8 strings are totally ordered by their UTF-16 code units, the
+/// same as [`cmp_wide`](struct.OmgWtf8.html#method.cmp_wide).
 ///
-/// Note that the exact ordering is unspecified when unpaired surrogate exists.
+/// This order is independent of how the surrogates are represented, so a
+/// string sorts the same as its `encode_wide()` output. Note that it differs
+/// from the order of `str` for code points U+E000 to U+FFFF.
 impl Ord for OmgWtf8 {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.canonicalize().cmp(&other.canonicalize())
+        if self.0 == other.0 {
+            return Ordering::Equal;
+        }
+        self.cmp_wide(other)
     }
 }
 
@@ -63,9 +173,173 @@ impl PartialOrd for OmgWtf8 {
 }
 
 /// An OMG-WTF-8 string can be hashed for use in `HashMap` and `HashSet`.
+///
+/// The canonical bytes are fed to the hasher followed by a `0xff` byte, so
+/// strings which compare equal hash equally, whichever representation their
+/// surrogates use. A valid UTF-8 string feeds the same bytes as its `str`, but
+/// the hashes may still differ with a hasher overriding `Hasher::write_str`.
 impl Hash for OmgWtf8 {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.canonicalize().hash(state)
+        let (begin, middle, end) = self.canonicalize();
+        if begin != 0 {
+            state.write(&[0xed, (begin >> 8) as u8, begin as u8]);
+        }
+        state.write(middle);
+        if end != 0 {
+            state.write(&[0xed, (end >> 8) as u8, end as u8]);
+        }
+        state.write_u8(0xff);
+    }
+}
+
+/// Implements `PartialEq` and `PartialOrd` in both directions between an
+/// OMG-WTF-8 string type and a UTF-8 string type.
+///
+/// The UTF-8 string is compared as an OMG-WTF-8 string, so the ordering is
+/// consistent with `Ord for OmgWtf8`, i.e. the UTF-16 code unit order.
+macro_rules! impl_cmp_str {
+    ($([$($life:lifetime),*] $lhs:ty, $rhs:ty;)*) => {$(
+        impl<$($life),*> PartialEq<$rhs> for $lhs {
+            fn eq(&self, other: &$rhs) -> bool {
+                let (a, b): (&OmgWtf8, &str) = (self, other);
+                *a == *OmgWtf8::from_str(b)
+            }
+        }
+
+        impl<$($life),*> PartialEq<$lhs> for $rhs {
+            fn eq(&self, other: &$lhs) -> bool {
+                let (a, b): (&str, &OmgWtf8) = (self, other);
+                *OmgWtf8::from_str(a) == *b
+            }
+        }
+
+        impl<$($life),*> PartialOrd<$rhs> for $lhs {
+            fn partial_cmp(&self, other: &$rhs) -> Option<Ordering> {
+                let (a, b): (&OmgWtf8, &str) = (self, other);
+                a.partial_cmp(OmgWtf8::from_str(b))
+            }
+        }
+
+        impl<$($life),*> PartialOrd<$lhs> for $rhs {
+            fn partial_cmp(&self, other: &$lhs) -> Option<Ordering> {
+                let (a, b): (&str, &OmgWtf8) = (self, other);
+                OmgWtf8::from_str(a).partial_cmp(b)
+            }
+        }
+    )*};
+}
+
+impl_cmp_str! {
+    [] OmgWtf8, str;
+    ['a] OmgWtf8, &'a str;
+    [] OmgWtf8, String;
+    ['a] OmgWtf8, Cow<'a, str>;
+    ['a] &'a OmgWtf8, str;
+    ['a] &'a OmgWtf8, String;
+    ['a, 'b] &'a OmgWtf8, Cow<'b, str>;
+    [] OmgWtf8Buf, str;
+    ['a] OmgWtf8Buf, &'a str;
+    [] OmgWtf8Buf, String;
+    ['a] OmgWtf8Buf, Cow<'a, str>;
+}
+
+/// Implements `PartialEq` and `PartialOrd` in both directions between two
+/// forms of OMG-WTF-8 strings, e.g. owned and borrowed.
+///
+/// The borrowed strings are compared, so surrogates compare equal regardless
+/// of their representation.
+macro_rules! impl_cmp_forms {
+    ($([$($life:lifetime),*] $lhs:ty, $rhs:ty;)*) => {$(
+        impl<$($life),*> PartialEq<$rhs> for $lhs {
+            fn eq(&self, other: &$rhs) -> bool {
+                let (a, b): (&OmgWtf8, &OmgWtf8) = (self, other);
+                *a == *b
+            }
+        }
+
+        impl<$($life),*> PartialEq<$lhs> for $rhs {
+            fn eq(&self, other: &$lhs) -> bool {
+                let (a, b): (&OmgWtf8, &OmgWtf8) = (self, other);
+                *a == *b
+            }
+        }
+
+        impl<$($life),*> PartialOrd<$rhs> for $lhs {
+            fn partial_cmp(&self, other: &$rhs) -> Option<Ordering> {
+                let (a, b): (&OmgWtf8, &OmgWtf8) = (self, other);
+                a.partial_cmp(b)
+            }
+        }
+
+        impl<$($life),*> PartialOrd<$lhs> for $rhs {
+            fn partial_cmp(&self, other: &$lhs) -> Option<Ordering> {
+                let (a, b): (&OmgWtf8, &OmgWtf8) = (self, other);
+                a.partial_cmp(b)
+            }
+        }
+    )*};
+}
+
+impl_cmp_forms! {
+    [] OmgWtf8, OmgWtf8Buf;
+    ['a] &'a OmgWtf8, OmgWtf8Buf;
+    [] Box<OmgWtf8>, OmgWtf8Buf;
+    [] OmgWtf8, Box<OmgWtf8>;
+    ['a] &'a OmgWtf8, Box<OmgWtf8>;
+}
+
+/// Implements `PartialEq` in both directions between `Cow<OmgWtf8>` and an
+/// OMG-WTF-8 string type, regardless of whether the `Cow` is borrowed.
+macro_rules! impl_eq_cow {
+    ($([$($life:lifetime),*] $rhs:ty;)*) => {$(
+        impl<'c, $($life),*> PartialEq<$rhs> for Cow<'c, OmgWtf8> {
+            fn eq(&self, other: &$rhs) -> bool {
+                let (a, b): (&OmgWtf8, &OmgWtf8) = (self, other);
+                *a == *b
+            }
+        }
+
+        impl<'c, $($life),*> PartialEq<Cow<'c, OmgWtf8>> for $rhs {
+            fn eq(&self, other: &Cow<'c, OmgWtf8>) -> bool {
+                let (a, b): (&OmgWtf8, &OmgWtf8) = (self, other);
+                *a == *b
+            }
+        }
+    )*};
+}
+
+impl_eq_cow! {
+    [] OmgWtf8;
+    ['a] &'a OmgWtf8;
+    [] OmgWtf8Buf;
+    [] Box<OmgWtf8>;
+}
+
+/// An OMG-WTF-8 string can be compared with a sequence of UTF-16 code units.
+impl PartialEq<[u16]> for OmgWtf8 {
+    fn eq(&self, other: &[u16]) -> bool {
+        self.encode_wide().eq(other.iter().cloned())
+    }
+}
+
+impl PartialEq<OmgWtf8> for [u16] {
+    fn eq(&self, other: &OmgWtf8) -> bool {
+        *other == *self
+    }
+}
+
+/// An OMG-WTF-8 string can be compared with an OS string on Windows.
+#[cfg(windows)]
+impl PartialEq<OsStr> for OmgWtf8 {
+    fn eq(&self, other: &OsStr) -> bool {
+        self.encode_wide().eq(other.encode_wide())
+    }
+}
+
+#[cfg(windows)]
+impl PartialEq<OmgWtf8> for OsStr {
+    fn eq(&self, other: &OmgWtf8) -> bool {
+        *other == *self
     }
 }
 
@@ -175,3 +449,281 @@ fn test_ow8_canonicalized_equality() {
         );
     }
 }
+
+#[test]
+fn test_ow8_equality_fast_path() {
+    let s = OmgWtf8::from_str("😀a😀");
+    assert_eq!(s, s);
+    assert_eq!(
+        &s[2..],
+        &*OmgWtf8::from_wide(&[0xde00, 0x61, 0xd83d, 0xde00])
+    );
+    assert_eq!(
+        &s[..7],
+        &*OmgWtf8::from_wide(&[0xd83d, 0xde00, 0x61, 0xd83d])
+    );
+    assert_ne!(&s[..7], &s[..5]);
+    assert_ne!(&s[2..], &s[4..]);
+}
+
+#[test]
+fn test_starts_ends_with_code_unit() {
+    let s = OmgWtf8::from_str("😀A😑");
+    assert!(s.starts_with_code_unit(0xd83d));
+    assert!(!s.starts_with_code_unit(0xde00));
+    assert!(s.ends_with_code_unit(0xde11));
+    assert!(!s.ends_with_code_unit(0xd83d));
+    assert!(s[2..].starts_with_code_unit(0xde00));
+    assert!(s[..7].ends_with_code_unit(0xd83d));
+    assert!(s[2..7].starts_with_code_unit(0xde00));
+    assert!(s[2..7].ends_with_code_unit(0xd83d));
+    assert!(s[2..4].ends_with_code_unit(0xde00));
+    assert!(s[4..5].starts_with_code_unit(0x41));
+    assert!(s[4..5].ends_with_code_unit(0x41));
+
+    let s = OmgWtf8::from_wide(&[0xdc00, 0x41, 0xdbff]);
+    assert!(s.starts_with_code_unit(0xdc00));
+    assert!(s.ends_with_code_unit(0xdbff));
+    assert!(!OmgWtf8::EMPTY.starts_with_code_unit(0));
+    assert!(!OmgWtf8::EMPTY.ends_with_code_unit(0));
+}
+
+#[test]
+fn test_surrogate_parts() {
+    let s = OmgWtf8::from_str("😀A😑");
+    assert_eq!(s.surrogate_parts(), (None, s, None));
+    assert_eq!(
+        s[2..7].surrogate_parts(),
+        (Some(0xde00), OmgWtf8::from_str("A"), Some(0xd83d))
+    );
+    let s = OmgWtf8::from_wide(&[0xdc00, 0x41, 0xdbff]);
+    assert_eq!(
+        s.surrogate_parts(),
+        (Some(0xdc00), OmgWtf8::from_str("A"), Some(0xdbff))
+    );
+    let s = OmgWtf8::from_wide(&[0xdbff, 0x41, 0xdc00]);
+    assert_eq!(s.surrogate_parts(), (None, &*s, None));
+    let s = OmgWtf8::from_wide(&[0xd800]);
+    assert_eq!(
+        s.surrogate_parts(),
+        (None, OmgWtf8::from_str(""), Some(0xd800))
+    );
+}
+
+#[test]
+fn test_cmp_str() {
+    let s = OmgWtf8::from_str("😀A😑");
+    assert_eq!(s, "😀A😑");
+    assert_eq!("😀A😑", s);
+    assert_eq!(*s, *"😀A😑");
+    assert_eq!(s, &String::from("😀A😑"));
+    assert_eq!(String::from("😀A😑"), s);
+    assert_ne!(&s[2..7], "A");
+    assert_ne!(String::from("A"), &s[2..7]);
+    assert_eq!(&s[4..5], "A");
+    assert!(s > "😀A");
+    assert!("😀A" < s);
+    let owned = String::from("😀B");
+    assert!(owned > s);
+    assert!(s < owned);
+
+    let buf = OmgWtf8Buf::from("A😑");
+    assert_eq!(buf, "A😑");
+    assert_eq!("A😑", buf);
+    assert_eq!(buf, *"A😑");
+    assert_eq!(buf, String::from("A😑"));
+    assert_eq!(String::from("A😑"), buf);
+    assert_ne!(s[4..7].to_owned(), "A");
+    assert!(buf < "B");
+    let owned = String::from("A");
+    assert!(owned < buf);
+    assert!(buf > owned);
+
+    // the order is the UTF-16 code unit order, even against UTF-8 strings.
+    let s = OmgWtf8::from_str("\u{ff61}");
+    assert!(*s > "😀");
+    assert!("😀" < *s);
+    assert!(s > *"😀");
+    assert!(*"😀" < s);
+    let (buf, borrowed) = (s.to_owned(), Cow::Borrowed("😀"));
+    assert!(buf > borrowed);
+    assert!(s > borrowed);
+    assert!(borrowed < *s);
+    let cow = Cow::from(s);
+    assert!(*cow > *"😀");
+    assert_eq!(cow, Cow::<str>::Borrowed("\u{ff61}"));
+
+    let mut sorted = [
+        Cow::from(OmgWtf8::from_str("\u{ff61}")),
+        Cow::from(OmgWtf8Buf::from("😀")),
+        Cow::from(OmgWtf8::from_str("a")),
+    ];
+    sorted.sort();
+    assert_eq!(sorted, ["a", "😀", "\u{ff61}"].map(Cow::<str>::Borrowed));
+    assert_eq!(sorted.iter().filter(|s| ***s < *"😁").count(), 2);
+}
+
+#[test]
+fn test_cmp_forms() {
+    let s = OmgWtf8::from_str("😀A😑");
+    let split = &s[2..];
+    let buf = split.to_owned();
+    let boxed = OmgWtf8::from_wide(&[0xde00, 0x41, 0xd83d, 0xde11]);
+    assert_eq!(buf.as_bytes(), boxed.as_bytes());
+    assert_ne!(split.as_bytes(), buf.as_bytes());
+
+    assert_eq!(*split, buf);
+    assert_eq!(buf, *split);
+    assert_eq!(split, buf);
+    assert_eq!(buf, split);
+    assert_eq!(boxed, buf);
+    assert_eq!(buf, boxed);
+    assert_eq!(*split, boxed);
+    assert_eq!(boxed, *split);
+    assert_eq!(split, boxed);
+    assert_eq!(boxed, split);
+    assert_eq!(Cow::Borrowed(split), boxed);
+    assert_eq!(boxed, Cow::Borrowed(split));
+    assert_ne!(&s[..2], buf);
+    assert_ne!(boxed, &s[..2]);
+
+    let other = OmgWtf8::from_str("x").to_owned().into_boxed_omgwtf8();
+    let boxes = [other, boxed];
+    assert_eq!(boxes.iter().position(|b| *b == split), Some(1));
+    let bufs = [OmgWtf8Buf::from("x"), buf];
+    assert_eq!(bufs.iter().position(|b| *b == boxes[1]), Some(1));
+
+    // the order is the UTF-16 code unit order, like `Ord for OmgWtf8`.
+    let high = OmgWtf8::from_str("\u{ff61}");
+    let low = OmgWtf8::from_str("😀");
+    let (high_buf, low_box) = (high.to_owned(), low.to_owned().into_boxed_omgwtf8());
+    assert!(*high > low_box);
+    assert!(low_box < *high);
+    assert!(high > low_box);
+    assert!(low_box < high);
+    assert!(high_buf > low_box);
+    assert!(low_box < high_buf);
+    assert!(*low < high_buf);
+    assert!(high_buf > *low);
+    assert!(low < high_buf);
+    assert!(high_buf > low);
+    assert_eq!(high.partial_cmp(&high_buf), Some(Ordering::Equal));
+}
+
+#[test]
+fn test_eq_wide() {
+    let s = OmgWtf8::from_str("😀A😑");
+    let wide = [0xd83d, 0xde00, 0x41, 0xd83d, 0xde11];
+    assert_eq!(*s, wide[..]);
+    assert_eq!(wide[..], *s);
+    assert_eq!(&s[2..7], &wide[1..4]);
+    assert_eq!(&wide[1..4], &s[2..7]);
+    assert_ne!(&s[2..7], &wide[1..3]);
+    assert_ne!(&s[..4], &wide[..]);
+    assert_eq!(OmgWtf8::EMPTY, &[][..]);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_eq_os_str() {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    let s = OmgWtf8::from_str("😀A😑");
+    let os = OsString::from_wide(&[0xde00, 0x41, 0xd83d]);
+    assert_eq!(&s[2..7], &*os);
+    assert_eq!(&*os, &s[2..7]);
+    assert_ne!(&s[4..7], &*os);
+}
+
+#[test]
+fn test_hash_parity_with_str() {
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash<T: Hash + ?Sized>(t: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    for s in &["", "a", "😀A😑", "測試"] {
+        assert_eq!(hash(OmgWtf8::from_str(s)), hash(*s));
+        assert_eq!(hash(&OmgWtf8Buf::from(*s)), hash(*s));
+    }
+
+    let s = OmgWtf8::from_str("😀A😑");
+    let wide = OmgWtf8::from_wide(&[0xde00, 0x41, 0xd83d]);
+    assert_eq!(hash(&s[2..7]), hash(&*wide));
+    assert_ne!(hash(&s[2..7]), hash("A"));
+}
+
+#[test]
+fn test_cmp_bytes() {
+    use std::collections::BTreeSet;
+
+    let s = OmgWtf8::from_str("😀A😑");
+    let wide = OmgWtf8::from_wide(&[0xde00, 0x41, 0xd83d]);
+    assert_eq!(s[2..7].cmp_bytes(&wide), Ordering::Equal);
+    assert_eq!(wide.cmp_bytes(&s[2..7]), Ordering::Equal);
+    assert_eq!(s[2..].cmp_bytes(&s[2..7]), Ordering::Greater);
+    assert_eq!(s[..4].cmp_bytes(&s[..5]), Ordering::Less);
+    assert_eq!(OmgWtf8::from_str("b").cmp_bytes(&s[2..7]), Ordering::Less);
+
+    let mut set = BTreeSet::new();
+    assert!(set.insert(OrdBytes(&s[2..7])));
+    assert!(!set.insert(OrdBytes(&wide)));
+    assert!(set.insert(OrdBytes(&s[4..])));
+    assert!(set.insert(OrdBytes(s)));
+    assert_eq!(set.len(), 3);
+}
+
+#[test]
+fn test_ord_is_wide_order() {
+    let wide: &[&[u16]] = &[
+        &[],
+        &[0x41],
+        &[0x41, 0xd83d],
+        &[0xd83d],
+        &[0xd83d, 0xde00],
+        &[0xd83d, 0xde00, 0x41],
+        &[0xde00],
+        &[0xde00, 0xd83d],
+        &[0xe000],
+        &[0xff61],
+    ];
+    let strings = wide
+        .iter()
+        .map(|w| OmgWtf8::from_wide(w))
+        .collect::<Vec<_>>();
+    for (i, a) in strings.iter().enumerate() {
+        for (j, b) in strings.iter().enumerate() {
+            assert_eq!(a.cmp(b), i.cmp(&j), "{:?} {:?}", a, b);
+        }
+    }
+
+    // the order does not depend on the representation of surrogates.
+    let s = OmgWtf8::from_str("😀A😀");
+    let canonical = OmgWtf8::from_wide(&[0xde00, 0x41, 0xd83d, 0xde00]);
+    assert_eq!(s[2..].cmp(&canonical), Ordering::Equal);
+    assert_eq!(s[2..].cmp(&strings[7]), Ordering::Less);
+    assert_eq!(s[..7].cmp(&strings[5]), Ordering::Greater);
+    assert_eq!(s[..7].cmp(&strings[6]), Ordering::Less);
+    let buf = OmgWtf8Buf::from("\u{ff61}");
+    assert!(buf > "😀");
+}
+
+#[test]
+fn test_cmp_wide() {
+    let a = OmgWtf8::from_str("\u{ff61}");
+    let b = OmgWtf8::from_str("😀");
+    assert_eq!(a.cmp_bytes(b), Ordering::Less);
+    assert_eq!(a.cmp_wide(b), Ordering::Greater);
+    assert_eq!(a.cmp(b), Ordering::Greater);
+    assert_eq!(b.cmp_wide(&b[..2]), Ordering::Greater);
+    assert_eq!(b[2..].cmp_wide(&b[..2]), Ordering::Greater);
+    assert_eq!(
+        b[2..].cmp_wide(&OmgWtf8::from_wide(&[0xde00])),
+        Ordering::Equal
+    );
+    assert_eq!(OmgWtf8::EMPTY.cmp_wide(&b[2..]), Ordering::Less);
+}
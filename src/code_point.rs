@@ -0,0 +1,529 @@
+use conv::{code_unit_from_canonical, ThreeByteSeq};
+use std::char;
+use std::fmt;
+use OmgWtf8;
+
+/// A Unicode code point, which may be an unpaired surrogate.
+///
+/// This is the unit of iteration and editing of OMG-WTF-8 strings. A
+/// supplementary character is always a single code point, while a surrogate
+/// half split from it is a separate code point in U+D800 to U+DFFF.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CodePoint(u32);
+
+impl CodePoint {
+    /// Creates a code point from its numeric value, returning `None` if the
+    /// value is above U+10FFFF.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        if value <= 0x10_ffff {
+            Some(CodePoint(value))
+        } else {
+            None
+        }
+    }
+
+    /// Creates a code point from a Unicode scalar value.
+    pub fn from_char(c: char) -> Self {
+        CodePoint(c as u32)
+    }
+
+    /// Returns the numeric value of this code point.
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+
+    /// Converts this code point to a `char`, returning `None` if it is a
+    /// surrogate.
+    pub fn to_char(self) -> Option<char> {
+        char::from_u32(self.0)
+    }
+
+    /// Returns whether this code point is a surrogate (U+D800 to U+DFFF).
+    pub fn is_surrogate(self) -> bool {
+        0xd800 <= self.0 && self.0 <= 0xdfff
+    }
+
+    /// Encodes this code point as WTF-8 into the buffer, and returns the
+    /// encoded string.
+    pub(crate) fn encode(self, buf: &mut [u8; 4]) -> &OmgWtf8 {
+        let c = self.0;
+        let len = match c {
+            0..=0x7f => {
+                buf[0] = c as u8;
+                1
+            }
+            0x80..=0x7ff => {
+                buf[0] = (c >> 6 | 0xc0) as u8;
+                buf[1] = (c & 0x3f | 0x80) as u8;
+                2
+            }
+            0x800..=0xffff => {
+                buf[0] = (c >> 12 | 0xe0) as u8;
+                buf[1] = (c >> 6 & 0x3f | 0x80) as u8;
+                buf[2] = (c & 0x3f | 0x80) as u8;
+                3
+            }
+            _ => {
+                buf[0] = (c >> 18 | 0xf0) as u8;
+                buf[1] = (c >> 12 & 0x3f | 0x80) as u8;
+                buf[2] = (c >> 6 & 0x3f | 0x80) as u8;
+                buf[3] = (c & 0x3f | 0x80) as u8;
+                4
+            }
+        };
+        unsafe { OmgWtf8::from_bytes_unchecked(&buf[..len]) }
+    }
+}
+
+impl From<char> for CodePoint {
+    fn from(c: char) -> Self {
+        CodePoint::from_char(c)
+    }
+}
+
+impl fmt::Debug for CodePoint {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "U+{:04X}", self.0)
+    }
+}
+
+impl OmgWtf8 {
+    /// Creates a string of a single code point from its numeric value,
+    /// returning `None` if the value is above U+10FFFF.
+    ///
+    /// A surrogate value creates an unpaired surrogate in canonical
+    /// representation, which is useful as a needle.
+    ///
+    /// ```
+    /// use omgwtf8::{MatchExt, OmgWtf8};
+    ///
+    /// let needle = OmgWtf8::from_code_point(0xde00).unwrap();
+    /// assert_eq!(OmgWtf8::from_str("a😀").find(&*needle), Some(3));
+    /// assert!(OmgWtf8::from_code_point(0x110000).is_none());
+    /// ```
+    pub fn from_code_point(c: u32) -> Option<Box<Self>> {
+        let c = CodePoint::from_u32(c)?;
+        Some(Box::from(c.encode(&mut [0; 4])))
+    }
+
+    /// Creates a string of a single character.
+    pub fn from_char(c: char) -> Box<Self> {
+        Box::from(CodePoint::from_char(c).encode(&mut [0; 4]))
+    }
+
+    /// Returns an iterator over the code points of this string.
+    ///
+    /// A supplementary character is yielded as a single code point, while an
+    /// unpaired surrogate, whether in split or canonical representation, is
+    /// yielded as a surrogate code point.
+    pub fn chars(&self) -> Chars<'_> {
+        Chars { rest: self }
+    }
+
+    /// Returns the numeric values of the code points of this string, i.e.
+    /// its UTF-32 form where unpaired surrogates are kept as individual
+    /// values.
+    pub fn to_code_points(&self) -> Vec<u32> {
+        self.chars().map(CodePoint::to_u32).collect()
+    }
+
+    /// Returns an iterator over the code points of this string, with their
+    /// byte offsets and UTF-16 offsets, as `(byte_offset, wide_offset, c)`.
+    ///
+    /// This allows keeping the indices of this string in sync with those of
+    /// its `encode_wide()` output in a single pass.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("é😀a");
+    /// let offsets = s
+    ///     .wide_indices()
+    ///     .map(|(i, j, _)| (i, j))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(offsets, vec![(0, 0), (2, 1), (6, 3)]);
+    /// ```
+    pub fn wide_indices(&self) -> WideIndices<'_> {
+        WideIndices {
+            rest: self,
+            byte_offset: 0,
+            wide_offset: 0,
+        }
+    }
+
+    /// Decodes the first code point of this string, and returns it with the
+    /// number of bytes it occupies.
+    pub(crate) fn first_code_point(&self) -> Option<(CodePoint, usize)> {
+        let bytes = &self.0;
+        let lead = *bytes.first()? as u32;
+        let cont = |i: usize| bytes[i] as u32 & 0x3f;
+        Some(match lead {
+            0x00..=0x7f => (CodePoint(lead), 1),
+            0x80..=0xbf => {
+                // a low surrogate in split representation.
+                let c = ThreeByteSeq::new(bytes).canonicalize();
+                (CodePoint(code_unit_from_canonical(c) as u32), 3)
+            }
+            0xc0..=0xdf => (CodePoint((lead & 0x1f) << 6 | cont(1)), 2),
+            0xe0..=0xef => (CodePoint((lead & 0x0f) << 12 | cont(1) << 6 | cont(2)), 3),
+            _ if bytes.len() == 3 => {
+                // a high surrogate in split representation.
+                let c = ThreeByteSeq::new(bytes).canonicalize();
+                (CodePoint(code_unit_from_canonical(c) as u32), 3)
+            }
+            _ => (
+                CodePoint((lead & 0x07) << 18 | cont(1) << 12 | cont(2) << 6 | cont(3)),
+                4,
+            ),
+        })
+    }
+}
+
+impl OmgWtf8 {
+    /// Returns the first UTF-16 code unit of this string, or `None` if it is
+    /// empty.
+    ///
+    /// Only the first code point is decoded. A string starting with a
+    /// supplementary character starts with its high surrogate.
+    pub fn first_code_unit(&self) -> Option<u16> {
+        let c = self.first_code_point()?.0.to_u32();
+        Some(if c >= 0x10000 {
+            0xd800 | ((c - 0x10000) >> 10) as u16
+        } else {
+            c as u16
+        })
+    }
+
+    /// Returns the last UTF-16 code unit of this string, or `None` if it is
+    /// empty.
+    ///
+    /// Only the last code point is decoded. A string ending with a
+    /// supplementary character ends with its low surrogate.
+    pub fn last_code_unit(&self) -> Option<u16> {
+        let c = self.last_code_point()?.0.to_u32();
+        Some(if c >= 0x10000 {
+            0xdc00 | (c & 0x3ff) as u16
+        } else {
+            c as u16
+        })
+    }
+
+    /// Splits off the first UTF-16 code unit of this string, or returns
+    /// `None` if it is empty.
+    ///
+    /// If the string starts with a supplementary character, the rest starts
+    /// with its low surrogate in split representation.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("😀a");
+    /// let (c, rest) = s.split_first_code_unit().unwrap();
+    /// assert_eq!(c, 0xd83d);
+    /// assert_eq!(rest, &*OmgWtf8::from_wide(&[0xde00, 0x61]));
+    /// ```
+    pub fn split_first_code_unit(&self) -> Option<(u16, &Self)> {
+        let (c, len) = self.first_code_point()?;
+        let c = c.to_u32();
+        Some(if c >= 0x10000 {
+            (0xd800 | ((c - 0x10000) >> 10) as u16, &self[2..])
+        } else {
+            (c as u16, &self[len..])
+        })
+    }
+
+    /// Splits off the last UTF-16 code unit of this string, or returns
+    /// `None` if it is empty.
+    ///
+    /// If the string ends with a supplementary character, the rest ends with
+    /// its high surrogate in split representation.
+    pub fn split_last_code_unit(&self) -> Option<(&Self, u16)> {
+        let (c, len) = self.last_code_point()?;
+        let c = c.to_u32();
+        let end = self.len() - len;
+        Some(if c >= 0x10000 {
+            (&self[..end + 2], 0xdc00 | (c & 0x3ff) as u16)
+        } else {
+            (&self[..end], c as u16)
+        })
+    }
+
+    /// Decodes the last code point of this string, and returns it with the
+    /// number of bytes it occupies.
+    pub(crate) fn last_code_point(&self) -> Option<(CodePoint, usize)> {
+        let bytes = &self.0;
+        let len = bytes.len();
+        // the last code point starts at the last non-continuation byte. A
+        // split high surrogate at the end starts with the lead byte of its
+        // 4-byte sequence, and is then decoded from its 3 bytes alone.
+        let start = (len.saturating_sub(4)..len)
+            .rev()
+            .find(|&i| bytes[i] & 0xc0 != 0x80)
+            // only continuation bytes: the whole string is a low surrogate
+            // in split representation.
+            .unwrap_or(0);
+        let last = unsafe { OmgWtf8::from_bytes_unchecked(&bytes[start..]) };
+        last.first_code_point()
+    }
+}
+
+/// An iterator over the code points of an OMG-WTF-8 string.
+///
+/// This is returned by [`OmgWtf8::chars`].
+///
+/// [`OmgWtf8::chars`]: struct.OmgWtf8.html#method.chars
+#[derive(Clone, Debug)]
+pub struct Chars<'a> {
+    rest: &'a OmgWtf8,
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = CodePoint;
+    fn next(&mut self) -> Option<CodePoint> {
+        let (c, len) = self.rest.first_code_point()?;
+        self.rest = unsafe { OmgWtf8::from_bytes_unchecked(&self.rest.0[len..]) };
+        Some(c)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rest.len();
+        (len.div_ceil(4), Some(len))
+    }
+
+    fn last(mut self) -> Option<CodePoint> {
+        self.next_back()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Chars<'a> {
+    fn next_back(&mut self) -> Option<CodePoint> {
+        let (c, len) = self.rest.last_code_point()?;
+        let end = self.rest.len() - len;
+        self.rest = unsafe { OmgWtf8::from_bytes_unchecked(&self.rest.0[..end]) };
+        Some(c)
+    }
+}
+
+/// An iterator over the code points of an OMG-WTF-8 string with their byte
+/// and UTF-16 offsets.
+///
+/// This is returned by [`OmgWtf8::wide_indices`].
+///
+/// [`OmgWtf8::wide_indices`]: struct.OmgWtf8.html#method.wide_indices
+#[derive(Clone, Debug)]
+pub struct WideIndices<'a> {
+    rest: &'a OmgWtf8,
+    byte_offset: usize,
+    wide_offset: usize,
+}
+
+impl<'a> Iterator for WideIndices<'a> {
+    type Item = (usize, usize, CodePoint);
+    fn next(&mut self) -> Option<(usize, usize, CodePoint)> {
+        let (c, len) = self.rest.first_code_point()?;
+        let item = (self.byte_offset, self.wide_offset, c);
+        self.rest = unsafe { OmgWtf8::from_bytes_unchecked(&self.rest.0[len..]) };
+        self.byte_offset += len;
+        self.wide_offset += if c.to_u32() >= 0x10000 { 2 } else { 1 };
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rest.len();
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+#[test]
+fn test_code_point() {
+    assert_eq!(
+        CodePoint::from_u32(0x10ffff).map(CodePoint::to_u32),
+        Some(0x10ffff)
+    );
+    assert_eq!(CodePoint::from_u32(0x110000), None);
+    assert_eq!(CodePoint::from_char('😀').to_char(), Some('😀'));
+    assert_eq!(CodePoint::from_u32(0xd800).unwrap().to_char(), None);
+    assert!(CodePoint::from_u32(0xdfff).unwrap().is_surrogate());
+    assert!(!CodePoint::from('\u{e000}').is_surrogate());
+    assert_eq!(format!("{:?}", CodePoint::from('a')), "U+0061");
+    assert_eq!(format!("{:?}", CodePoint::from('😀')), "U+1F600");
+}
+
+#[test]
+fn test_from_code_point() {
+    for &c in &[0, 0x41, 0xe9, 0x6e2c, 0xd800, 0xdfff, 0xffff, 0x1f600] {
+        let s = OmgWtf8::from_code_point(c).unwrap();
+        assert!(s.is_canonical());
+        assert_eq!(
+            s.chars().map(CodePoint::to_u32).collect::<Vec<_>>(),
+            vec![c]
+        );
+    }
+    assert_eq!(OmgWtf8::from_code_point(0xdc00).unwrap().len(), 3);
+    assert_eq!(
+        OmgWtf8::from_code_point(0x10ffff).unwrap().as_bytes(),
+        "\u{10ffff}".as_bytes()
+    );
+    assert_eq!(OmgWtf8::from_code_point(0x110000), None);
+    assert_eq!(&*OmgWtf8::from_char('😀'), "😀");
+    assert_eq!(&*OmgWtf8::from_char('a'), "a");
+}
+
+#[test]
+fn test_first_code_point() {
+    fn first(s: &OmgWtf8) -> Option<(u32, usize)> {
+        s.first_code_point().map(|(c, len)| (c.to_u32(), len))
+    }
+
+    let s = OmgWtf8::from_str("😀A\u{e9}\u{6e2c}");
+    assert_eq!(first(s), Some((0x1f600, 4)));
+    assert_eq!(first(&s[2..]), Some((0xde00, 3)));
+    assert_eq!(first(&s[..2]), Some((0xd83d, 3)));
+    assert_eq!(first(&s[4..]), Some((0x41, 1)));
+    assert_eq!(first(&s[5..]), Some((0xe9, 2)));
+    assert_eq!(first(&s[7..]), Some((0x6e2c, 3)));
+    assert_eq!(first(&OmgWtf8::from_wide(&[0xdc00])), Some((0xdc00, 3)));
+    assert_eq!(first(OmgWtf8::EMPTY), None);
+
+    let mut buf = [0; 4];
+    for &c in &[0x41, 0xe9, 0x6e2c, 0xd800, 0x1f600] {
+        let cp = CodePoint::from_u32(c).unwrap();
+        assert_eq!(
+            cp.encode(&mut buf).first_code_point(),
+            Some((cp, cp.encode(&mut [0; 4]).len()))
+        );
+    }
+}
+
+#[test]
+fn test_last_code_point() {
+    fn last(s: &OmgWtf8) -> Option<(u32, usize)> {
+        s.last_code_point().map(|(c, len)| (c.to_u32(), len))
+    }
+
+    let s = OmgWtf8::from_str("\u{6e2c}\u{e9}A😀");
+    assert_eq!(last(s), Some((0x1f600, 4)));
+    assert_eq!(last(&s[..8]), Some((0xd83d, 3)));
+    assert_eq!(last(&s[8..]), Some((0xde00, 3)));
+    assert_eq!(last(&s[..6]), Some((0x41, 1)));
+    assert_eq!(last(&s[..5]), Some((0xe9, 2)));
+    assert_eq!(last(&s[..3]), Some((0x6e2c, 3)));
+    assert_eq!(last(&OmgWtf8::from_wide(&[0xd800])), Some((0xd800, 3)));
+    assert_eq!(last(OmgWtf8::EMPTY), None);
+}
+
+#[test]
+fn test_first_last_code_unit() {
+    let s = OmgWtf8::from_str("😀A\u{e9}😑");
+    assert_eq!(s.first_code_unit(), Some(0xd83d));
+    assert_eq!(s.last_code_unit(), Some(0xde11));
+    assert_eq!(s[2..].first_code_unit(), Some(0xde00));
+    assert_eq!(s[..9].last_code_unit(), Some(0xd83d));
+    assert_eq!(s[4..7].first_code_unit(), Some(0x41));
+    assert_eq!(s[4..7].last_code_unit(), Some(0xe9));
+    assert_eq!(s[2..4].first_code_unit(), Some(0xde00));
+    assert_eq!(s[2..4].last_code_unit(), Some(0xde00));
+
+    let s = OmgWtf8::from_wide(&[0xdc00, 0x41, 0xd800]);
+    assert_eq!(s.first_code_unit(), Some(0xdc00));
+    assert_eq!(s.last_code_unit(), Some(0xd800));
+    assert_eq!(OmgWtf8::EMPTY.first_code_unit(), None);
+    assert_eq!(OmgWtf8::EMPTY.last_code_unit(), None);
+}
+
+#[test]
+fn test_split_first_last_code_unit() {
+    let s = OmgWtf8::from_str("😀A\u{e9}😑");
+    let (c, rest) = s.split_first_code_unit().unwrap();
+    assert_eq!((c, rest), (0xd83d, &s[2..]));
+    assert_eq!(rest.split_first_code_unit(), Some((0xde00, &s[4..])));
+    let (rest, c) = s.split_last_code_unit().unwrap();
+    assert_eq!((rest, c), (&s[..9], 0xde11));
+    assert_eq!(rest.split_last_code_unit(), Some((&s[..7], 0xd83d)));
+    assert_eq!(OmgWtf8::EMPTY.split_first_code_unit(), None);
+    assert_eq!(OmgWtf8::EMPTY.split_last_code_unit(), None);
+
+    // popping every code unit from either end yields `encode_wide()`.
+    let units = s.encode_wide().collect::<Vec<_>>();
+    let mut rest = s;
+    let mut front = Vec::new();
+    while let Some((c, r)) = rest.split_first_code_unit() {
+        front.push(c);
+        rest = r;
+    }
+    assert_eq!(front, units);
+    let mut rest = &s[2..9];
+    let mut back = Vec::new();
+    while let Some((r, c)) = rest.split_last_code_unit() {
+        back.push(c);
+        rest = r;
+    }
+    back.reverse();
+    assert_eq!(back, &units[1..5]);
+}
+
+#[test]
+fn test_chars() {
+    fn chars(s: &OmgWtf8) -> Vec<u32> {
+        s.chars().map(CodePoint::to_u32).collect()
+    }
+
+    let s = OmgWtf8::from_str("😀A\u{e9}😑");
+    assert_eq!(chars(s), vec![0x1f600, 0x41, 0xe9, 0x1f611]);
+    assert_eq!(chars(&s[2..9]), vec![0xde00, 0x41, 0xe9, 0xd83d]);
+    assert_eq!(chars(&s[2..4]), vec![0xde00]);
+    assert_eq!(
+        chars(&OmgWtf8::from_wide(&[0xdc00, 0xd800])),
+        vec![0xdc00, 0xd800]
+    );
+    assert!(chars(OmgWtf8::EMPTY).is_empty());
+
+    fn rev_chars(s: &OmgWtf8) -> Vec<u32> {
+        let mut chars = s.chars().rev().map(CodePoint::to_u32).collect::<Vec<_>>();
+        chars.reverse();
+        chars
+    }
+
+    for s in &[s, &s[2..9], &s[2..4], &s[..2], &s[4..9]] {
+        assert_eq!(rev_chars(s), chars(s));
+        assert_eq!(s.chars().last(), s.chars().next_back());
+    }
+    assert_eq!(
+        rev_chars(&OmgWtf8::from_wide(&[0xdc00, 0xd800])),
+        vec![0xdc00, 0xd800]
+    );
+    assert_eq!(s[..9].chars().last(), CodePoint::from_u32(0xd83d));
+
+    let mut iter = s[2..].chars();
+    assert_eq!(iter.next().map(CodePoint::to_u32), Some(0xde00));
+    assert_eq!(iter.next_back(), Some(CodePoint::from('😑')));
+    assert_eq!(iter.next_back(), Some(CodePoint::from('\u{e9}')));
+    assert_eq!(iter.next(), Some(CodePoint::from('A')));
+    assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_wide_indices() {
+    fn indices(s: &OmgWtf8) -> Vec<(usize, usize, u32)> {
+        s.wide_indices()
+            .map(|(i, j, c)| (i, j, c.to_u32()))
+            .collect()
+    }
+
+    let s = OmgWtf8::from_str("😀A\u{e9}😑");
+    assert_eq!(
+        indices(s),
+        vec![(0, 0, 0x1f600), (4, 2, 0x41), (5, 3, 0xe9), (7, 4, 0x1f611)]
+    );
+    assert_eq!(
+        indices(&s[2..9]),
+        vec![(0, 0, 0xde00), (3, 1, 0x41), (4, 2, 0xe9), (6, 3, 0xd83d)]
+    );
+    for s in &[s, &s[2..9], &s[..2]] {
+        for (i, j, c) in s.wide_indices() {
+            assert_eq!(s[i..].len_wide(), s.len_wide() - j);
+            assert_eq!(s[i..].chars().next(), Some(c));
+        }
+    }
+    assert_eq!(OmgWtf8::EMPTY.wide_indices().next(), None);
+}
@@ -1,11 +1,83 @@
+use errors::Utf16Error;
 use OmgWtf8;
+use std::borrow::Cow;
 use std::str::from_utf8;
 use std::fmt;
+use validate::is_continuation_byte;
+
+/// Panics if the bytes are not well-formed OMG-WTF-8, in debug builds or with
+/// the `strict` feature only.
+///
+/// This scans the whole string, so it is only used by the public unchecked
+/// constructors, where the bytes come from outside of this crate.
+#[inline]
+pub(crate) fn debug_check_well_formed(s: &[u8]) {
+    if cfg!(any(debug_assertions, feature = "strict")) {
+        assert!(
+            OmgWtf8::validate(s).is_well_formed(),
+            "bytes are not well-formed OMG-WTF-8: {:x?}",
+            s
+        );
+    }
+}
+
+/// Panics if the bytes are not well-formed OMG-WTF-8 with the `strict`
+/// feature, or if the sequences at either edge are not well-formed in debug
+/// builds.
+///
+/// The internal unchecked constructors run on every slicing and search step,
+/// so a plain debug build only checks the bytes at the edges, keeping the
+/// tests fast. This still catches a cut in the middle of a sequence where the
+/// string is constructed, rather than at some later search or conversion
+/// giving a wrong result. The `strict` feature scans the whole string, which
+/// also catches corruption in the middle, e.g. a surrogate pair encoded as
+/// two 3-byte sequences.
+#[inline]
+fn debug_check_unchecked(s: &[u8]) {
+    if cfg!(feature = "strict") {
+        debug_check_well_formed(s);
+    } else if cfg!(debug_assertions) {
+        assert!(
+            has_well_formed_edges(s),
+            "bytes are not well-formed OMG-WTF-8: {:x?}",
+            s
+        );
+    }
+}
+
+/// Checks that the bytes start with a lead byte or a low surrogate in split
+/// representation, and end with a complete sequence or a high surrogate in
+/// split representation.
+fn has_well_formed_edges(s: &[u8]) -> bool {
+    let leading = s
+        .iter()
+        .take(4)
+        .take_while(|&&b| is_continuation_byte(b))
+        .count();
+    if leading == s.len() {
+        // the string is empty, or only a low surrogate.
+        return leading == 0 || leading == 3;
+    }
+    let trailing = s
+        .iter()
+        .rev()
+        .take(4)
+        .take_while(|&&b| is_continuation_byte(b))
+        .count();
+    let width = match s[s.len() - 1 - trailing] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    };
+    (leading == 0 || leading == 3) && (trailing + 1 == width || (width == 4 && trailing == 2))
+}
 
 /// Represents a 3-byte sequence as part of a well-formed OMG-WTF-8 sequence.
 ///
 /// Internally, the sequence is encoded as a big-endian integer to simplify
 /// computation.
+#[derive(Copy, Clone)]
 pub(crate) struct ThreeByteSeq(u32);
 impl ThreeByteSeq {
     /// Canonicalizes the 3-byte sequence.
@@ -22,9 +94,9 @@ impl ThreeByteSeq {
     /// canonical representation.
     pub(crate) fn canonicalize(self) -> u16 {
         (match self.0 {
-            0xeda000...0xedffff => self.0,
-            0x800000...0xbfffff => self.0 | 0xb000,
-            0xf00000...0xffffffff => {
+            0xeda000..=0xedffff => self.0,
+            0x800000..=0xbfffff => self.0 | 0xb000,
+            0xf00000..=0xffffffff => {
                 ((self.0 >> 4 & 0x303 | self.0 >> 6 & 0x1c3c) - 0x100) | 0xa080
             }
             _ => 0,
@@ -34,10 +106,10 @@ impl ThreeByteSeq {
     /// Extracts a WTF-16 code unit from the 3-byte sequence.
     pub(crate) fn as_code_unit(self) -> u16 {
         (match self.0 {
-            0xf00000...0xffffffff => {
+            0xf00000..=0xffffffff => {
                 (self.0 >> 4 & 3 | self.0 >> 6 & 0xfc | self.0 >> 8 & 0x700) + 0xd7c0
             }
-            0x800000...0xbfffff => self.0 & 0x3f | self.0 >> 2 & 0x3c0 | 0xdc00,
+            0x800000..=0xbfffff => self.0 & 0x3f | self.0 >> 2 & 0x3c0 | 0xdc00,
             _ => self.0 & 0x3f | self.0 >> 2 & 0xfc0 | self.0 >> 4 & 0xf000,
         }) as u16
     }
@@ -48,6 +120,12 @@ impl ThreeByteSeq {
     }
 }
 
+/// Converts the last 2 bytes of a canonical surrogate (see
+/// `ThreeByteSeq::canonicalize`) into the WTF-16 code unit.
+pub(crate) fn code_unit_from_canonical(c: u16) -> u16 {
+    0xd000 | (c & 0x3f00) >> 2 | (c & 0x3f)
+}
+
 #[test]
 fn test_3bs_canonicalize() {
     fn canonicalize(a: u32) -> u16 {
@@ -74,19 +152,90 @@ fn test_3bs_canonicalize() {
 }
 
 impl OmgWtf8 {
+    /// The empty OMG-WTF-8 string.
+    pub const EMPTY: &'static OmgWtf8 = unsafe { &*(&[] as *const [u8] as *const OmgWtf8) };
+
     /// Creates a new OMG-WTF-8 string from a UTF-8 string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> &Self {
         unsafe { Self::from_bytes_unchecked(s.as_bytes()) }
     }
 
+    /// Creates a new mutable OMG-WTF-8 string from a mutable UTF-8 string.
+    pub fn from_mut_str(s: &mut str) -> &mut Self {
+        unsafe { Self::from_bytes_unchecked_mut(s.as_bytes_mut()) }
+    }
+
     /// Creates a new OMG-WTF-8 string from raw bytes without checking for
     /// well-formed-ness.
+    ///
+    /// With the `strict` feature, this panics if the bytes are not
+    /// well-formed. In debug builds, this panics if the sequences at either
+    /// edge are not well-formed.
     pub(crate) unsafe fn from_bytes_unchecked(s: &[u8]) -> &Self {
+        debug_check_unchecked(s);
         &*(s as *const [u8] as *const Self)
     }
 
-    #[cfg(test)]
-    pub(crate) fn as_bytes(&self) -> &[u8] {
+    /// Creates a new mutable OMG-WTF-8 string from raw bytes without checking
+    /// for well-formed-ness, except with the `strict` feature, or at the edges
+    /// in debug builds.
+    pub(crate) unsafe fn from_bytes_unchecked_mut(s: &mut [u8]) -> &mut Self {
+        debug_check_unchecked(s);
+        &mut *(s as *mut [u8] as *mut Self)
+    }
+
+    /// Creates a new boxed OMG-WTF-8 string from raw bytes without checking
+    /// for well-formed-ness, except with the `strict` feature, or at the edges
+    /// in debug builds.
+    pub(crate) unsafe fn from_boxed_bytes_unchecked(s: Box<[u8]>) -> Box<Self> {
+        debug_check_unchecked(&s);
+        Box::from_raw(Box::into_raw(s) as *mut Self)
+    }
+
+    /// Returns whether both ends of this string are in canonical
+    /// representation, i.e. this string does not start or end with a split
+    /// surrogate.
+    ///
+    /// A canonical OMG-WTF-8 string is also a well-formed WTF-8 string.
+    pub fn is_canonical(&self) -> bool {
+        let len = self.0.len();
+        len < 3 || (self.0[0] & 0xc0 != 0x80 && self.0[len - 3] < 0xf0)
+    }
+
+    /// Canonicalizes both ends of this boxed string in place, reusing the
+    /// allocation.
+    pub fn canonicalize_owned(mut self: Box<Self>) -> Box<Self> {
+        canonicalize_in_place(&mut self.0);
+        self
+    }
+
+    /// Converts this string to standard WTF-8, where surrogates at the edges
+    /// are never in split representation.
+    ///
+    /// The bytes are borrowed if this string is already canonical.
+    pub fn to_wtf8(&self) -> Cow<'_, [u8]> {
+        if self.is_canonical() {
+            Cow::Borrowed(&self.0)
+        } else {
+            let mut bytes = self.0.to_vec();
+            canonicalize_in_place(&mut bytes);
+            Cow::Owned(bytes)
+        }
+    }
+
+    /// Converts a standard WTF-8 byte slice into an OMG-WTF-8 string,
+    /// returning `None` if the bytes are not well-formed WTF-8.
+    ///
+    /// Unlike `from_bytes`, surrogates in split representation are rejected.
+    pub fn from_wtf8(bytes: &[u8]) -> Option<&Self> {
+        Self::from_bytes(bytes).ok().filter(|s| s.is_canonical())
+    }
+
+    /// Returns the underlying bytes of this string.
+    ///
+    /// Surrogates at the edges may be in split representation.
+    pub fn as_bytes(&self) -> &[u8] {
         &self.0
     }
 
@@ -97,22 +246,35 @@ impl OmgWtf8 {
         from_utf8(&self.0).ok()
     }
 
+    /// Same as `to_str()`, but returns an error with the position of the
+    /// first unpaired surrogate if this string is not valid UTF-8.
+    pub fn to_str_checked(&self) -> Result<&str, Utf16Error> {
+        if let Some(s) = self.to_str() {
+            return Ok(s);
+        }
+        let (byte_offset, wide_offset, c) = self
+            .wide_indices()
+            .find(|&(_, _, c)| c.is_surrogate())
+            .expect("a string which is not valid UTF-8 must contain a surrogate");
+        Err(Utf16Error::new(byte_offset, wide_offset, c.to_u32() as u16))
+    }
+
     /// Converts from UCS-2 to OMG-WTF-8.
     pub fn from_wide(ucs2: &[u16]) -> Box<Self> {
         let mut buf = Vec::with_capacity(ucs2.len());
         let mut it = ucs2.iter().fuse().cloned();
         'outer: while let Some(mut c1) = it.next() {
-            if let 0xd800...0xdbff = c1 {
+            if let 0xd800..=0xdbff = c1 {
                 // we've got a high surrogate. check if it is followed by a
                 // low surrogate.
-                while let Some(c2) = it.next() {
+                for c2 in it.by_ref() {
                     match c2 {
-                        0xd800...0xdbff => {
+                        0xd800..=0xdbff => {
                             // we've got another high surrogate, keep checking
                             encode_unit(&mut buf, c1);
                             c1 = c2;
                         }
-                        0xdc00...0xdfff => {
+                        0xdc00..=0xdfff => {
                             // we've got a low surrogate, write a 4-byte sequence.
                             let c = ((c1 as u32 & 0x3ff) << 10 | (c2 as u32 & 0x3ff)) + 0x1_0000;
                             buf.push((c >> 18 | 0xf0) as u8);
@@ -133,15 +295,30 @@ impl OmgWtf8 {
             encode_unit(&mut buf, c1);
         }
 
-        unsafe { Box::from_raw(Box::into_raw(buf.into_boxed_slice()) as *mut Self) }
+        unsafe { Self::from_boxed_bytes_unchecked(buf.into_boxed_slice()) }
     }
 
-    pub fn encode_wide(&self) -> EncodeWide {
+    pub fn encode_wide(&self) -> EncodeWide<'_> {
         EncodeWide {
             src: &self.0,
             low_surrogate: None,
         }
     }
+
+    /// Returns the length of this string in UTF-16 code units, i.e. the
+    /// number of items `encode_wide()` yields, without decoding.
+    pub fn len_wide(&self) -> usize {
+        let (begin, middle, end) = self.canonicalize();
+        let units = middle
+            .iter()
+            .map(|&b| match b {
+                0x80..=0xbf => 0,
+                0xf0..=0xff => 2,
+                _ => 1,
+            })
+            .sum::<usize>();
+        units + (begin != 0) as usize + (end != 0) as usize
+    }
 }
 
 impl<'a> From<&'a str> for &'a OmgWtf8 {
@@ -149,6 +326,16 @@ impl<'a> From<&'a str> for &'a OmgWtf8 {
         OmgWtf8::from_str(s)
     }
 }
+impl<'a> From<&'a mut str> for &'a mut OmgWtf8 {
+    fn from(s: &'a mut str) -> &'a mut OmgWtf8 {
+        OmgWtf8::from_mut_str(s)
+    }
+}
+impl AsRef<OmgWtf8> for OmgWtf8 {
+    fn as_ref(&self) -> &OmgWtf8 {
+        self
+    }
+}
 impl AsRef<OmgWtf8> for str {
     fn as_ref(&self) -> &OmgWtf8 {
         OmgWtf8::from_str(self)
@@ -169,31 +356,56 @@ impl fmt::Debug for OmgWtf8 {
 impl<'a> From<&'a OmgWtf8> for Box<OmgWtf8> {
     fn from(s: &'a OmgWtf8) -> Box<OmgWtf8> {
         let mut boxed_slice = Box::<[u8]>::from(&s.0);
-        let len = boxed_slice.len();
-        if len >= 3 {
-            if let 0x80...0xbf = boxed_slice[0] {
-                let c = ThreeByteSeq::new(&boxed_slice).canonicalize();
-                boxed_slice[0] = 0xed;
-                boxed_slice[1] = (c >> 8) as u8;
-                boxed_slice[2] = c as u8;
-            }
-            if let 0xf0...0xff = boxed_slice[len - 3] {
-                let c = ThreeByteSeq::new(&boxed_slice[(len - 3)..]).canonicalize();
-                boxed_slice[len - 3] = 0xed;
-                boxed_slice[len - 2] = (c >> 8) as u8;
-                boxed_slice[len - 1] = c as u8;
-            }
+        canonicalize_in_place(&mut boxed_slice);
+        unsafe { OmgWtf8::from_boxed_bytes_unchecked(boxed_slice) }
+    }
+}
+
+impl Default for &OmgWtf8 {
+    fn default() -> Self {
+        OmgWtf8::EMPTY
+    }
+}
+
+impl Default for Box<OmgWtf8> {
+    fn default() -> Self {
+        unsafe { OmgWtf8::from_boxed_bytes_unchecked(Box::default()) }
+    }
+}
+
+/// Boxed strings are cloned byte-for-byte, preserving the representation.
+impl Clone for Box<OmgWtf8> {
+    fn clone(&self) -> Self {
+        unsafe { OmgWtf8::from_boxed_bytes_unchecked(Box::from(&self.0)) }
+    }
+}
+
+/// Converts the split surrogates at both ends of an OMG-WTF-8 sequence into
+/// canonical representation.
+pub(crate) fn canonicalize_in_place(bytes: &mut [u8]) {
+    let len = bytes.len();
+    if len >= 3 {
+        if let 0x80..=0xbf = bytes[0] {
+            let c = ThreeByteSeq::new(bytes).canonicalize();
+            bytes[0] = 0xed;
+            bytes[1] = (c >> 8) as u8;
+            bytes[2] = c as u8;
+        }
+        if let 0xf0..=0xff = bytes[len - 3] {
+            let c = ThreeByteSeq::new(&bytes[(len - 3)..]).canonicalize();
+            bytes[len - 3] = 0xed;
+            bytes[len - 2] = (c >> 8) as u8;
+            bytes[len - 1] = c as u8;
         }
-        unsafe { Box::from_raw(Box::into_raw(boxed_slice) as *mut OmgWtf8) }
     }
 }
 
-fn encode_unit(buf: &mut Vec<u8>, c: u16) {
+pub(crate) fn encode_unit(buf: &mut Vec<u8>, c: u16) {
     match c {
-        0...0x7f => {
+        0..=0x7f => {
             buf.push(c as u8);
         }
-        0x80...0x7ff => {
+        0x80..=0x7ff => {
             buf.push((c >> 6 | 0xc0) as u8);
             buf.push((c & 0x3f | 0x80) as u8);
         }
@@ -205,6 +417,7 @@ fn encode_unit(buf: &mut Vec<u8>, c: u16) {
     }
 }
 
+#[derive(Clone)]
 pub struct EncodeWide<'a> {
     src: &'a [u8],
     low_surrogate: Option<u16>,
@@ -222,14 +435,14 @@ impl<'a> Iterator for EncodeWide<'a> {
 
         let b1 = self.src[0];
         let (consume_len, code_unit) = match b1 {
-            0...0x7f => (1, b1 as u16),
-            0xc0...0xdf => {
+            0..=0x7f => (1, b1 as u16),
+            0xc0..=0xdf => {
                 // 2-byte sequence
                 let b1 = b1 as u16;
                 let b2 = self.src[1] as u16;
                 (2, (b1 & 0x1f) << 6 | (b2 & 0x3f))
             }
-            0xf0...0xff if self.src.len() >= 4 => {
+            0xf0..=0xff if self.src.len() >= 4 => {
                 // 4-byte sequence
                 let b1 = b1 as u32;
                 let b2 = self.src[1] as u32;
@@ -330,6 +543,59 @@ fn test_encode_wide() {
     );
 }
 
+#[test]
+fn test_is_canonical() {
+    assert!(OmgWtf8::from_str("").is_canonical());
+    assert!(OmgWtf8::from_str("😊").is_canonical());
+    assert!(!OmgWtf8::from_str("😊")[2..].is_canonical());
+    assert!(!OmgWtf8::from_str("😊")[..2].is_canonical());
+    assert!(OmgWtf8::from_wide(&[0xdddd, 0xd888, 0xdddd, 0xd888]).is_canonical());
+    assert!(!OmgWtf8::from_str("a😊b😊c")[3..].is_canonical());
+    assert!(OmgWtf8::from_str("a😊b😊c")[5..].is_canonical());
+}
+
+#[test]
+fn test_canonicalize_owned() {
+    let s = unsafe { OmgWtf8::from_bytes_unchecked(b"\xb2\x87\x9d\xf0\xb2\x87\x9d\xf0\xb2\x87") };
+    let boxed = unsafe { OmgWtf8::from_boxed_bytes_unchecked(Box::from(s.as_bytes())) };
+    assert!(!boxed.is_canonical());
+    let ptr = boxed.as_bytes().as_ptr();
+    let boxed = boxed.canonicalize_owned();
+    assert!(boxed.is_canonical());
+    assert_eq!(boxed.as_bytes().as_ptr(), ptr);
+    assert_eq!(
+        boxed.as_bytes(),
+        b"\xed\xb7\x9d\xf0\xb2\x87\x9d\xed\xa2\x88",
+    );
+}
+
+#[test]
+fn test_default() {
+    #[derive(Default)]
+    struct S<'a> {
+        borrowed: &'a OmgWtf8,
+        boxed: Box<OmgWtf8>,
+        owned: ::OmgWtf8Buf,
+    }
+
+    let s = S::default();
+    assert!(OmgWtf8::EMPTY.is_empty());
+    assert!(s.borrowed.is_empty());
+    assert!(s.boxed.is_empty());
+    assert!(s.owned.is_empty());
+    assert_eq!(s.borrowed, OmgWtf8::from_str(""));
+}
+
+#[test]
+fn test_clone_boxed() {
+    let boxed = OmgWtf8::from_wide(&[0xdddd, 0x41, 0xd888]);
+    let cloned = boxed.clone();
+    assert_eq!(cloned.as_bytes(), boxed.as_bytes());
+    assert_ne!(cloned.as_bytes().as_ptr(), boxed.as_bytes().as_ptr());
+    let owned: ::OmgWtf8Buf = (*boxed).to_owned();
+    assert_eq!(owned.as_bytes(), boxed.as_bytes());
+}
+
 #[test]
 fn test_boxing_should_canonicalize() {
     assert_eq!(
@@ -347,3 +613,81 @@ fn test_boxing_should_canonicalize() {
         b"\xed\xb7\x9d\xf0\xb2\x87\x9d\xed\xa2\x88",
     );
 }
+
+#[test]
+fn test_wtf8() {
+    let s = OmgWtf8::from_str("😀a😑");
+    assert!(match s.to_wtf8() {
+        Cow::Borrowed(b) => b == s.as_bytes(),
+        Cow::Owned(_) => false,
+    });
+    assert_eq!(&*s[2..7].to_wtf8(), b"\xed\xb8\x80a\xed\xa0\xbd");
+    assert_eq!(
+        OmgWtf8::from_wtf8(b"\xed\xb8\x80a\xed\xa0\xbd"),
+        Some(&s[2..7])
+    );
+    assert_eq!(OmgWtf8::from_wtf8(s[2..7].as_bytes()), None);
+    assert_eq!(OmgWtf8::from_wtf8(b"\xed\xa0\xbd\xed\xb8\x80"), None);
+    assert_eq!(OmgWtf8::from_wtf8(b"\xff"), None);
+    assert_eq!(s[2..7].to_owned().into_wtf8(), b"\xed\xb8\x80a\xed\xa0\xbd");
+}
+
+#[test]
+fn test_len_wide() {
+    let s = OmgWtf8::from_str("a😀\u{d7ff}😑");
+    for &(start, end) in &[(0, 12), (3, 12), (0, 10), (3, 10), (3, 5), (5, 10), (0, 0)] {
+        let s = &s[start..end];
+        assert_eq!(s.len_wide(), s.encode_wide().count(), "{:?}", s);
+    }
+    assert_eq!(OmgWtf8::from_wide(&[0xdc00, 0xd800]).len_wide(), 2);
+}
+
+#[test]
+fn test_has_well_formed_edges() {
+    for s in &[
+        &b""[..],
+        b"a",
+        b"\xc3\xa9",
+        b"\xed\xa0\x80",
+        b"\xf0\x9f\x98\x80",
+        b"\x9f\x98\x80",
+        b"\xf0\x9f\x98",
+        b"\x9f\x98\x80\xf0\x9f\x98",
+        b"\x9f\x98\x80a\xf0\x9f\x98",
+    ] {
+        assert!(has_well_formed_edges(s), "{:x?}", s);
+    }
+    for s in &[
+        &b"\x80"[..],
+        b"\x98\x80",
+        b"\x80\x80\x80\x80",
+        b"\x80\x80\x80\x80a",
+        b"a\xf0\x9f",
+        b"a\xe2\x82",
+        b"\xc3",
+        b"a\x80",
+    ] {
+        assert!(!has_well_formed_edges(s), "{:x?}", s);
+    }
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "strict"))]
+#[should_panic(expected = "bytes are not well-formed OMG-WTF-8")]
+fn test_from_bytes_unchecked_invalid_edge() {
+    let s = OmgWtf8::from_str("a😀");
+    unsafe {
+        OmgWtf8::from_bytes_unchecked(&s.as_bytes()[..3]);
+    }
+}
+
+#[test]
+#[cfg(feature = "strict")]
+#[should_panic(expected = "bytes are not well-formed OMG-WTF-8")]
+fn test_from_bytes_unchecked_invalid_middle() {
+    // the edges are well-formed, but the surrogate pair must be encoded as a
+    // 4-byte sequence.
+    unsafe {
+        OmgWtf8::from_bytes_unchecked(b"\xed\xa0\x80\xed\xb0\x80");
+    }
+}
@@ -0,0 +1,558 @@
+//! Cursors into haystacks.
+//!
+//! A cursor is a position inside a haystack, counted in elements for slices
+//! and in bytes for OMG-WTF-8 strings. Cursors can only be created by the
+//! haystacks and searchers of this crate, and every conversion checks that
+//! the position is valid for the given haystack, so passing a cursor to the
+//! wrong haystack panics or gives a wrong result instead of causing undefined
+//! behavior.
+//!
+//! All the reasoning about where a cursor may point inside an OMG-WTF-8
+//! string lives in this module, which is also the only place in the pattern
+//! API requiring `unsafe`.
+
+use pattern::{Haystack, OwnedHaystack};
+use std::cmp::Ordering;
+#[cfg(windows)]
+use std::ffi::OsStr;
+use validate::is_continuation_byte;
+use {OmgWtf8, OmgWtf8Buf};
+
+/// A cursor pointing at the start of a match or a range.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StartCursor {
+    pos: usize,
+    /// Whether the cursor starts the low surrogate half of a 4-byte sequence
+    /// in a byte slice. Unlike `&OmgWtf8`, the cursor conversions of a slice
+    /// cannot look at the elements, so this is recorded when the cursor is
+    /// created by a searcher.
+    shared: bool,
+}
+
+/// A cursor pointing at the end of a match or a range.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EndCursor {
+    pos: usize,
+    /// Whether the cursor ends the high surrogate half of a 4-byte sequence
+    /// in a byte slice.
+    shared: bool,
+}
+
+impl StartCursor {
+    pub(crate) fn new(pos: usize) -> Self {
+        StartCursor { pos, shared: false }
+    }
+
+    pub(crate) fn pos(self) -> usize {
+        self.pos
+    }
+}
+
+impl EndCursor {
+    pub(crate) fn new(pos: usize) -> Self {
+        EndCursor { pos, shared: false }
+    }
+
+    pub(crate) fn pos(self) -> usize {
+        self.pos
+    }
+}
+
+impl PartialEq<EndCursor> for StartCursor {
+    fn eq(&self, other: &EndCursor) -> bool {
+        self.pos == other.pos
+    }
+}
+
+impl PartialEq<StartCursor> for EndCursor {
+    fn eq(&self, other: &StartCursor) -> bool {
+        self.pos == other.pos
+    }
+}
+
+impl PartialOrd<EndCursor> for StartCursor {
+    fn partial_cmp(&self, other: &EndCursor) -> Option<Ordering> {
+        Some(self.pos.cmp(&other.pos))
+    }
+}
+
+impl PartialOrd<StartCursor> for EndCursor {
+    fn partial_cmp(&self, other: &StartCursor) -> Option<Ordering> {
+        Some(self.pos.cmp(&other.pos))
+    }
+}
+
+fn check_bounds(pos: usize, len: usize) -> usize {
+    assert!(
+        pos <= len,
+        "cursor {} is out of bounds of haystack of length {}",
+        pos,
+        len
+    );
+    pos
+}
+
+/// The cursors of a slice count elements. When a byte slice is searched for
+/// an OMG-WTF-8 needle, a match may also start with the low surrogate half or
+/// end with the high surrogate half of a 4-byte sequence. Like `&OmgWtf8`, the
+/// two halves share the bytes of the sequence: splitting around such a match
+/// cuts the sequence into the split representations of its surrogates, and
+/// the offset of a cursor inside the sequence is that of its third byte.
+impl<T> Haystack for &[T] {
+    type StartCursor = StartCursor;
+    type EndCursor = EndCursor;
+    type Piece = Self;
+
+    fn cursor_at_front(_: &Self) -> StartCursor {
+        StartCursor::new(0)
+    }
+
+    fn cursor_at_back(hs: &Self) -> EndCursor {
+        EndCursor::new(hs.len())
+    }
+
+    fn start_to_end_cursor(hs: &Self, cur: StartCursor) -> EndCursor {
+        if cur.shared {
+            EndCursor {
+                pos: check_bounds(cur.pos + 2, hs.len()),
+                shared: true,
+            }
+        } else {
+            EndCursor::new(check_bounds(cur.pos, hs.len()))
+        }
+    }
+
+    fn end_to_start_cursor(hs: &Self, cur: EndCursor) -> StartCursor {
+        if cur.shared {
+            StartCursor {
+                pos: check_bounds(cur.pos, hs.len()) - 2,
+                shared: true,
+            }
+        } else {
+            StartCursor::new(check_bounds(cur.pos, hs.len()))
+        }
+    }
+
+    fn start_cursor_to_offset(hs: &Self, cur: StartCursor) -> usize {
+        check_bounds(cur.pos, hs.len()) + cur.shared as usize
+    }
+
+    fn end_cursor_to_offset(hs: &Self, cur: EndCursor) -> usize {
+        check_bounds(cur.pos, hs.len()) - cur.shared as usize
+    }
+
+    fn range_to_self(hs: Self, start: StartCursor, end: EndCursor) -> Self {
+        let (start, end) = byte_range(&hs, start, end);
+        &hs[start..end]
+    }
+}
+
+impl<T: Clone> OwnedHaystack for &[T] {
+    type Owned = Vec<T>;
+
+    fn push_to_owned(owned: &mut Vec<T>, hs: Self) {
+        owned.extend_from_slice(hs);
+    }
+}
+
+/// Checks that a start cursor points at a character boundary, or at the
+/// second byte of a complete 4-byte sequence, where the low surrogate starts.
+///
+/// A high surrogate in split representation at the end has no low surrogate
+/// half, so no cursor may point inside it.
+fn check_start(bytes: &[u8], cur: StartCursor) -> usize {
+    let pos = check_bounds(cur.pos, bytes.len());
+    let valid = pos == 0
+        || pos == bytes.len()
+        || !is_continuation_byte(bytes[pos])
+        || (bytes[pos - 1] >= 0xf0 && pos + 2 < bytes.len());
+    assert!(valid, "start cursor {} is not a valid position", pos);
+    pos
+}
+
+/// Checks that an end cursor points at a character boundary, or at the
+/// fourth byte of a complete 4-byte sequence, where the high surrogate ends.
+fn check_end(bytes: &[u8], cur: EndCursor) -> usize {
+    let pos = check_bounds(cur.pos, bytes.len());
+    let valid = pos == 0
+        || pos == bytes.len()
+        || !is_continuation_byte(bytes[pos])
+        || (pos >= 3 && bytes[pos - 3] >= 0xf0 && pos < bytes.len());
+    assert!(valid, "end cursor {} is not a valid position", pos);
+    pos
+}
+
+/// Returns whether a valid cursor at `pos` points inside a 4-byte sequence.
+fn is_inside_four_byte_seq(bytes: &[u8], pos: usize) -> bool {
+    pos != 0 && pos != bytes.len() && is_continuation_byte(bytes[pos])
+}
+
+// The cursor conversions of the bytes of a well-formed OMG-WTF-8 string.
+
+fn start_to_end_cursor(bytes: &[u8], cur: StartCursor) -> EndCursor {
+    let pos = check_start(bytes, cur);
+    if is_inside_four_byte_seq(bytes, pos) {
+        EndCursor::new(pos + 2)
+    } else {
+        EndCursor::new(pos)
+    }
+}
+
+fn end_to_start_cursor(bytes: &[u8], cur: EndCursor) -> StartCursor {
+    let pos = check_end(bytes, cur);
+    if is_inside_four_byte_seq(bytes, pos) {
+        StartCursor::new(pos - 2)
+    } else {
+        StartCursor::new(pos)
+    }
+}
+
+fn start_cursor_to_offset(bytes: &[u8], cur: StartCursor) -> usize {
+    let pos = check_start(bytes, cur);
+    if is_inside_four_byte_seq(bytes, pos) {
+        pos + 1
+    } else {
+        pos
+    }
+}
+
+fn end_cursor_to_offset(bytes: &[u8], cur: EndCursor) -> usize {
+    let pos = check_end(bytes, cur);
+    if is_inside_four_byte_seq(bytes, pos) {
+        pos - 1
+    } else {
+        pos
+    }
+}
+
+impl Haystack for &OmgWtf8 {
+    type StartCursor = StartCursor;
+    type EndCursor = EndCursor;
+    type Piece = Self;
+
+    fn cursor_at_front(_: &Self) -> StartCursor {
+        StartCursor::new(0)
+    }
+
+    fn cursor_at_back(hs: &Self) -> EndCursor {
+        EndCursor::new(hs.0.len())
+    }
+
+    fn start_to_end_cursor(hs: &Self, cur: StartCursor) -> EndCursor {
+        start_to_end_cursor(&hs.0, cur)
+    }
+
+    fn end_to_start_cursor(hs: &Self, cur: EndCursor) -> StartCursor {
+        end_to_start_cursor(&hs.0, cur)
+    }
+
+    fn start_cursor_to_offset(hs: &Self, cur: StartCursor) -> usize {
+        start_cursor_to_offset(&hs.0, cur)
+    }
+
+    fn end_cursor_to_offset(hs: &Self, cur: EndCursor) -> usize {
+        end_cursor_to_offset(&hs.0, cur)
+    }
+
+    fn range_to_self(hs: Self, start: StartCursor, end: EndCursor) -> Self {
+        let (start, end) = byte_range(&hs, start, end);
+        // both ends are validated, and since the range is not empty, they
+        // are not in the middle of the same 4-byte sequence, so the slice is
+        // well-formed.
+        unsafe { OmgWtf8::from_bytes_unchecked(&hs.0[start..end]) }
+    }
+}
+
+impl OwnedHaystack for &OmgWtf8 {
+    type Owned = OmgWtf8Buf;
+
+    fn push_to_owned(owned: &mut OmgWtf8Buf, hs: Self) {
+        owned.push_omgwtf8(hs);
+    }
+}
+
+/// On Windows, the encoded bytes of an `OsStr` are WTF-8, so it has the same
+/// cursors as `&OmgWtf8`. The pieces cut from it are `&OmgWtf8` instead, since
+/// they may start or end with half of a surrogate pair.
+#[cfg(windows)]
+impl<'a> Haystack for &'a OsStr {
+    type StartCursor = StartCursor;
+    type EndCursor = EndCursor;
+    type Piece = &'a OmgWtf8;
+
+    fn cursor_at_front(_: &Self) -> StartCursor {
+        StartCursor::new(0)
+    }
+
+    fn cursor_at_back(hs: &Self) -> EndCursor {
+        EndCursor::new(hs.as_encoded_bytes().len())
+    }
+
+    fn start_to_end_cursor(hs: &Self, cur: StartCursor) -> EndCursor {
+        start_to_end_cursor(hs.as_encoded_bytes(), cur)
+    }
+
+    fn end_to_start_cursor(hs: &Self, cur: EndCursor) -> StartCursor {
+        end_to_start_cursor(hs.as_encoded_bytes(), cur)
+    }
+
+    fn start_cursor_to_offset(hs: &Self, cur: StartCursor) -> usize {
+        start_cursor_to_offset(hs.as_encoded_bytes(), cur)
+    }
+
+    fn end_cursor_to_offset(hs: &Self, cur: EndCursor) -> usize {
+        end_cursor_to_offset(hs.as_encoded_bytes(), cur)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the encoded bytes of the piece are not well-formed
+    /// OMG-WTF-8, which is never the case with the WTF-8 used by std on
+    /// Windows.
+    fn range_to_self(hs: Self, start: StartCursor, end: EndCursor) -> &'a OmgWtf8 {
+        let (start, end) = byte_range(&hs, start, end);
+        OmgWtf8::from_bytes(&hs.as_encoded_bytes()[start..end])
+            .expect("the encoded bytes of an OsStr are not WTF-8")
+    }
+}
+
+#[cfg(windows)]
+impl<'a> OwnedHaystack for &'a OsStr {
+    type Owned = OmgWtf8Buf;
+
+    fn push_to_owned(owned: &mut OmgWtf8Buf, hs: &'a OmgWtf8) {
+        owned.push_omgwtf8(hs);
+    }
+}
+
+/// Returns the bytes of `hs` covered by the range between the cursors.
+fn byte_range<H>(hs: &H, start: StartCursor, end: EndCursor) -> (usize, usize)
+where
+    H: Haystack<StartCursor = StartCursor, EndCursor = EndCursor>,
+{
+    let start_offset = H::start_cursor_to_offset(hs, start);
+    let end_offset = H::end_cursor_to_offset(hs, end);
+    assert!(
+        start_offset <= end_offset,
+        "start cursor {} is after end cursor {}",
+        start.pos,
+        end.pos
+    );
+    if start_offset == end_offset {
+        // the two cursors may point at different bytes of the same 4-byte
+        // sequence.
+        (start_offset, start_offset)
+    } else {
+        (start.pos, end.pos)
+    }
+}
+
+fn check_not_inside_four_byte_seq(hs: &OmgWtf8, pos: usize) {
+    assert!(
+        !is_inside_four_byte_seq(&hs.0, pos),
+        "cannot split a surrogate pair in a mutable haystack"
+    );
+}
+
+/// Returns whether `bytes[pos..pos + 3]` are the continuation bytes of a
+/// 4-byte sequence, i.e. where a start cursor begins the low surrogate half.
+///
+/// The bytes are not required to be well-formed, so the lead byte and the
+/// range of the second byte are checked as well.
+pub(crate) fn is_split_low(bytes: &[u8], pos: usize) -> bool {
+    if pos == 0 || bytes.len() < pos + 3 {
+        return false;
+    }
+    let second = bytes[pos];
+    let second_in_range = match bytes[pos - 1] {
+        0xf0 => (0x90..=0xbf).contains(&second),
+        0xf1..=0xf3 => is_continuation_byte(second),
+        0xf4 => (0x80..=0x8f).contains(&second),
+        _ => false,
+    };
+    second_in_range && is_continuation_byte(bytes[pos + 1]) && is_continuation_byte(bytes[pos + 2])
+}
+
+/// Returns whether an end cursor at `pos` ends the high surrogate half of a
+/// 4-byte sequence.
+fn is_split_high_end(bytes: &[u8], pos: usize) -> bool {
+    pos >= 2 && is_split_low(bytes, pos - 2)
+}
+
+/// Creates a start cursor at `pos` of raw bytes, which shares the 4-byte
+/// sequence if it begins the low surrogate half.
+pub(crate) fn byte_start_cursor(bytes: &[u8], pos: usize) -> StartCursor {
+    StartCursor {
+        pos,
+        shared: is_split_low(bytes, pos),
+    }
+}
+
+/// Creates an end cursor at `pos` of raw bytes, which shares the 4-byte
+/// sequence if it ends the high surrogate half.
+pub(crate) fn byte_end_cursor(bytes: &[u8], pos: usize) -> EndCursor {
+    EndCursor {
+        pos,
+        shared: is_split_high_end(bytes, pos),
+    }
+}
+
+/// Splits a mutable haystack into the disjoint ranges before `end` and after
+/// `start`.
+///
+/// # Panics
+///
+/// Panics if either cursor is in the middle of a surrogate pair, or if `start`
+/// is before `end`.
+pub(crate) fn split_mut(
+    hs: &mut OmgWtf8,
+    end: EndCursor,
+    start: StartCursor,
+) -> (&mut OmgWtf8, &mut OmgWtf8) {
+    let end = check_end(&hs.0, end);
+    let start = check_start(&hs.0, start);
+    check_not_inside_four_byte_seq(hs, end);
+    check_not_inside_four_byte_seq(hs, start);
+    assert!(
+        end <= start,
+        "start cursor {} is before end cursor {}",
+        start,
+        end
+    );
+    let (head, tail) = hs.0.split_at_mut(start);
+    // both positions are at character boundaries.
+    unsafe {
+        (
+            OmgWtf8::from_bytes_unchecked_mut(&mut head[..end]),
+            OmgWtf8::from_bytes_unchecked_mut(tail),
+        )
+    }
+}
+
+#[test]
+fn test_ow8_cursors() {
+    let hs = OmgWtf8::from_str("a😱b");
+    assert_eq!(
+        Haystack::start_cursor_to_offset(&hs, StartCursor::new(2)),
+        3
+    );
+    assert_eq!(Haystack::end_cursor_to_offset(&hs, EndCursor::new(4)), 3);
+    assert_eq!(
+        Haystack::start_to_end_cursor(&hs, StartCursor::new(2)),
+        EndCursor::new(4)
+    );
+    assert_eq!(
+        Haystack::end_to_start_cursor(&hs, EndCursor::new(4)),
+        StartCursor::new(2)
+    );
+    assert_eq!(
+        Haystack::range_to_self(hs, StartCursor::new(2), EndCursor::new(4)),
+        OmgWtf8::EMPTY
+    );
+    assert_eq!(
+        Haystack::range_to_self(hs, StartCursor::new(2), EndCursor::new(6)),
+        &hs[3..]
+    );
+    assert_eq!(
+        Haystack::range_to_self(hs, StartCursor::new(0), EndCursor::new(4)),
+        &hs[..3]
+    );
+
+    let hs = &OmgWtf8::from_str("😱")[2..];
+    assert_eq!(
+        Haystack::start_cursor_to_offset(&hs, StartCursor::new(0)),
+        0
+    );
+    assert_eq!(
+        Haystack::end_to_start_cursor(&hs, EndCursor::new(0)),
+        StartCursor::new(0)
+    );
+    assert_eq!(
+        Haystack::range_to_self(hs, StartCursor::new(0), EndCursor::new(3)),
+        hs
+    );
+}
+
+#[test]
+fn test_ow8_split_mut() {
+    let mut s = String::from("a😱b");
+    let hs = OmgWtf8::from_mut_str(&mut s);
+    let (head, tail) = split_mut(hs, EndCursor::new(1), StartCursor::new(1));
+    head.make_ascii_uppercase();
+    assert_eq!(head, "A");
+    assert_eq!(tail, "😱b");
+    let (head, tail) = split_mut(tail, EndCursor::new(4), StartCursor::new(4));
+    assert_eq!(head, "😱");
+    assert_eq!(tail, "b");
+    assert_eq!(s, "A😱b");
+}
+
+#[test]
+#[should_panic(expected = "cannot split a surrogate pair in a mutable haystack")]
+fn test_ow8_split_mut_surrogate_pair() {
+    let mut s = String::from("a😱b");
+    let hs = OmgWtf8::from_mut_str(&mut s);
+    split_mut(hs, EndCursor::new(4), StartCursor::new(2));
+}
+
+#[test]
+#[should_panic(expected = "start cursor 3 is not a valid position")]
+fn test_ow8_invalid_start_cursor() {
+    let hs = OmgWtf8::from_str("a😱b");
+    Haystack::start_cursor_to_offset(&hs, StartCursor::new(3));
+}
+
+#[test]
+#[should_panic(expected = "start cursor 1 is not a valid position")]
+fn test_ow8_start_cursor_inside_split_high_surrogate() {
+    // the haystack is `f0 9f 98`, which has no low surrogate half to start
+    // from.
+    let hs = &OmgWtf8::from_str("😀")[..2];
+    Haystack::range_to_self(hs, StartCursor::new(1), EndCursor::new(3));
+}
+
+#[test]
+fn test_byte_slice_cursors() {
+    // "a", "😱", an incomplete 4-byte sequence.
+    let hs = &b"a\xf0\x9f\x98\xb1\xf0\x9f\x98"[..];
+    let low = byte_start_cursor(hs, 2);
+    let high = byte_end_cursor(hs, 4);
+    assert_eq!(Haystack::start_cursor_to_offset(&hs, low), 3);
+    assert_eq!(Haystack::end_cursor_to_offset(&hs, high), 3);
+    assert_eq!(Haystack::start_to_end_cursor(&hs, low), high);
+    assert_eq!(Haystack::end_to_start_cursor(&hs, high), low);
+    assert_eq!(Haystack::range_to_self(hs, low, high), b"");
+    assert_eq!(
+        Haystack::range_to_self(hs, low, byte_end_cursor(hs, 5)),
+        b"\x9f\x98\xb1"
+    );
+    assert_eq!(
+        Haystack::range_to_self(hs, StartCursor::new(0), high),
+        b"a\xf0\x9f\x98"
+    );
+
+    // the bytes of an incomplete sequence are not shared.
+    assert_eq!(byte_start_cursor(hs, 6), StartCursor::new(6));
+    assert_eq!(byte_end_cursor(hs, 7), EndCursor::new(7));
+}
+
+#[test]
+fn test_is_split_low() {
+    assert!(is_split_low(b"\xf0\x90\x80\x80", 1));
+    assert!(is_split_low(b"\xf3\x80\x80\x80", 1));
+    assert!(is_split_low(b"\xf4\x8f\xbf\xbf", 1));
+    assert!(!is_split_low(b"\xf0\x90\x80\x80", 0));
+    assert!(!is_split_low(b"\xf0\x90\x80", 1));
+    assert!(!is_split_low(b"\xf0\x8f\x80\x80", 1));
+    assert!(!is_split_low(b"\xf4\x90\x80\x80", 1));
+    assert!(!is_split_low(b"\xf5\x80\x80\x80", 1));
+    assert!(!is_split_low(b"\xff\x9f\x98\xb1", 1));
+    assert!(!is_split_low(b"\xf0\x90\x80a", 1));
+}
+
+#[test]
+#[should_panic(expected = "cursor 4 is out of bounds of haystack of length 3")]
+fn test_slice_cursor_out_of_bounds() {
+    let hs = &[1, 2, 3][..];
+    Haystack::range_to_self(hs, StartCursor::new(0), EndCursor::new(4));
+}
@@ -0,0 +1,233 @@
+//! Incremental decoding of UTF-16.
+
+use std::io::{self, Read};
+use OmgWtf8Buf;
+
+/// A decoder converting UTF-16 code units arriving in chunks into OMG-WTF-8.
+///
+/// A high surrogate at the end of a chunk is held back until the next chunk
+/// arrives, so that a surrogate pair cut by the chunk boundary is still
+/// decoded as a supplementary character, even if the output buffer is
+/// drained between the chunks.
+///
+/// ```
+/// use omgwtf8::{OmgWtf8, OmgWtf8Buf, WideDecoder};
+///
+/// let mut decoder = WideDecoder::new();
+/// let mut out = OmgWtf8Buf::new();
+/// decoder.decode(&[0x61, 0xd83d], &mut out);
+/// assert_eq!(out, "a");
+/// decoder.decode(&[0xde00], &mut out);
+/// decoder.finish(&mut out);
+/// assert_eq!(out, "a😀");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WideDecoder {
+    pending: Option<u16>,
+}
+
+impl WideDecoder {
+    /// Creates a new decoder.
+    pub fn new() -> Self {
+        WideDecoder { pending: None }
+    }
+
+    /// Returns whether a high surrogate from the previous chunk is held back.
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Decodes a chunk of code units, and appends the result to `out`.
+    ///
+    /// Unpaired surrogates are preserved. If the chunk ends with a high
+    /// surrogate, it is not written until the next call to `decode` or
+    /// `finish`.
+    pub fn decode(&mut self, chunk: &[u16], out: &mut OmgWtf8Buf) {
+        let (&last, rest) = match chunk.split_last() {
+            Some(split) => split,
+            None => return,
+        };
+        out.reserve(chunk.len());
+        if let Some(high) = self.pending.take() {
+            out.push_code_unit(high);
+        }
+        for &c in rest {
+            out.push_code_unit(c);
+        }
+        if let 0xd800..=0xdbff = last {
+            self.pending = Some(last);
+        } else {
+            out.push_code_unit(last);
+        }
+    }
+
+    /// Writes the held back high surrogate, if any, to `out` as an unpaired
+    /// surrogate, and resets the decoder.
+    pub fn finish(&mut self, out: &mut OmgWtf8Buf) {
+        if let Some(high) = self.pending.take() {
+            out.push_code_unit(high);
+        }
+    }
+}
+
+/// The byte order of a UTF-16 byte stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Utf16ByteOrder {
+    /// Little endian (UTF-16LE), the format usually produced on Windows.
+    LittleEndian,
+    /// Big endian (UTF-16BE).
+    BigEndian,
+    /// Detects the byte order from the byte order mark at the start of the
+    /// stream, which is then skipped. Without a byte order mark, the stream
+    /// is assumed to be little endian.
+    DetectBom,
+}
+
+/// What to do when a UTF-16 byte stream has an odd number of bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OddBytePolicy {
+    /// Fails with an `InvalidData` error.
+    Error,
+    /// Discards the trailing byte.
+    Ignore,
+    /// Replaces the trailing byte with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+}
+
+impl OmgWtf8Buf {
+    /// Reads a UTF-16 byte stream to the end, and decodes it into a new
+    /// buffer.
+    ///
+    /// Unpaired surrogates in the stream are preserved. A byte order mark is
+    /// only skipped when using `Utf16ByteOrder::DetectBom`, and is otherwise
+    /// decoded as U+FEFF.
+    pub fn read_utf16<R: Read>(
+        mut reader: R,
+        byte_order: Utf16ByteOrder,
+        odd_byte: OddBytePolicy,
+    ) -> io::Result<Self> {
+        let mut out = OmgWtf8Buf::new();
+        let mut decoder = WideDecoder::new();
+        let mut bytes = [0; 8192];
+        let mut units = Vec::with_capacity(bytes.len() / 2);
+        let mut big_endian = byte_order == Utf16ByteOrder::BigEndian;
+        let mut detect_bom = byte_order == Utf16ByteOrder::DetectBom;
+        // number of bytes carried over from the last read, either 0 or 1.
+        let mut carry = 0;
+        loop {
+            let len = match reader.read(&mut bytes[carry..]) {
+                Ok(0) => break,
+                Ok(n) => carry + n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let mut chunk = &bytes[..len & !1];
+            if detect_bom && chunk.len() >= 2 {
+                detect_bom = false;
+                match chunk[..2] {
+                    [0xff, 0xfe] => chunk = &chunk[2..],
+                    [0xfe, 0xff] => {
+                        big_endian = true;
+                        chunk = &chunk[2..];
+                    }
+                    _ => {}
+                }
+            }
+            units.clear();
+            units.extend(chunk.chunks(2).map(|pair| {
+                if big_endian {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                }
+            }));
+            decoder.decode(&units, &mut out);
+            carry = len & 1;
+            bytes[0] = bytes[len - 1];
+        }
+        if carry != 0 {
+            match odd_byte {
+                OddBytePolicy::Error => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "odd number of bytes in UTF-16 stream",
+                    ));
+                }
+                OddBytePolicy::Ignore => {}
+                OddBytePolicy::Replace => decoder.decode(&[0xfffd], &mut out),
+            }
+        }
+        decoder.finish(&mut out);
+        Ok(out)
+    }
+}
+
+#[test]
+fn test_wide_decoder() {
+    use OmgWtf8;
+
+    let wide = [0x61, 0xd83d, 0xde00, 0xdc00, 0xd800, 0xd83d, 0xde11, 0xd800];
+    let expected = OmgWtf8::from_wide(&wide);
+    for i in 0..=wide.len() {
+        for j in i..=wide.len() {
+            let mut decoder = WideDecoder::new();
+            let mut out = OmgWtf8Buf::new();
+            let mut drained = OmgWtf8Buf::new();
+            for chunk in &[&wide[..i], &wide[i..j], &wide[j..]] {
+                decoder.decode(chunk, &mut out);
+                drained.push_omgwtf8(&out);
+                out = OmgWtf8Buf::new();
+            }
+            assert!(decoder.has_pending());
+            decoder.finish(&mut out);
+            assert!(!decoder.has_pending());
+            drained.push_omgwtf8(&out);
+            assert_eq!(drained.as_bytes(), expected.as_bytes(), "{} {}", i, j);
+        }
+    }
+}
+
+#[test]
+fn test_read_utf16() {
+    use OmgWtf8;
+
+    /// A reader returning at most one byte at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn read(bytes: &[u8], byte_order: Utf16ByteOrder, odd_byte: OddBytePolicy) -> Vec<u8> {
+        let buf = OmgWtf8Buf::read_utf16(bytes, byte_order, odd_byte).unwrap();
+        let trickled = OmgWtf8Buf::read_utf16(Trickle(bytes), byte_order, odd_byte).unwrap();
+        assert_eq!(buf.as_bytes(), trickled.as_bytes());
+        buf.as_bytes().to_vec()
+    }
+
+    use self::OddBytePolicy::*;
+    use self::Utf16ByteOrder::*;
+
+    let le = b"a\0=\xd8\0\xde\0\xdc";
+    let be = b"\0a\xd8=\xde\0\xdc\0";
+    let expected = OmgWtf8::from_wide(&[0x61, 0xd83d, 0xde00, 0xdc00]);
+    assert_eq!(read(le, LittleEndian, Error), expected.as_bytes());
+    assert_eq!(read(be, BigEndian, Error), expected.as_bytes());
+    assert_eq!(read(le, DetectBom, Error), expected.as_bytes());
+    assert_eq!(read(b"\xff\xfea\0", DetectBom, Error), b"a");
+    assert_eq!(read(b"\xfe\xff\0a", DetectBom, Error), b"a");
+    assert_eq!(read(b"\xff\xfea\0", LittleEndian, Error), b"\xef\xbb\xbfa");
+    assert_eq!(read(b"", DetectBom, Error), b"");
+
+    assert!(OmgWtf8Buf::read_utf16(&b"a\0="[..], LittleEndian, Error).is_err());
+    assert_eq!(read(b"a\0=", LittleEndian, Ignore), b"a");
+    assert_eq!(
+        read(b"=\xd8=", LittleEndian, Replace),
+        b"\xed\xa0\xbd\xef\xbf\xbd"
+    );
+}
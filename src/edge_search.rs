@@ -0,0 +1,149 @@
+//! Searching for needles with surrogates at the edges without regex.
+//!
+//! A surrogate at the edge of a needle matches both its canonical `ed xx xx`
+//! form and the half of a 4-byte sequence, so it cannot be searched as plain
+//! bytes.
+
+use memchr::{memchr2_iter, memchr_iter};
+use validate::is_continuation_byte;
+
+/// The minimum length of the middle of a needle to use this search instead
+/// of a regex.
+pub(crate) const MIN_MIDDLE_LEN: usize = 16;
+
+/// A needle split into an optional low surrogate at the beginning, the
+/// well-formed WTF-8 bytes in the middle, and an optional high surrogate at
+/// the end.
+///
+/// The middle is found with a Horspool skip table, then the surrogates are
+/// checked against both their canonical and split representations.
+pub(crate) struct Horspool<'p> {
+    begin: u16,
+    middle: &'p [u8],
+    end: u16,
+    /// How far the window may move when its last byte is the index.
+    skip: [usize; 256],
+}
+
+/// Checks whether `bytes` starts with the canonical low surrogate `c`,
+/// either as `ed bx xx` or as the last 3 bytes of a 4-byte sequence.
+pub(crate) fn matches_low_surrogate(c: u16, bytes: &[u8]) -> bool {
+    let (hi, lo) = ((c >> 8) as u8, c as u8);
+    bytes.len() >= 3
+        && bytes[2] == lo
+        && ((bytes[0] == 0xed && bytes[1] == hi)
+            || (is_continuation_byte(bytes[0])
+                && is_continuation_byte(bytes[1])
+                && bytes[1] & 0xf == hi & 0xf))
+}
+
+/// Checks whether `bytes` starts with the canonical high surrogate `c`,
+/// either as `ed ax xx` or as the first 3 bytes of a 4-byte sequence.
+pub(crate) fn matches_high_surrogate(c: u16, bytes: &[u8]) -> bool {
+    let (hi, lo) = ((c >> 8) as u8, c as u8);
+    bytes.len() >= 3
+        && ((bytes[0] == 0xed && bytes[1] == hi && bytes[2] == lo)
+            || (bytes[0] == high_surrogate_first_byte(c)
+                && bytes[1] == ((split_high_surrogate(c) >> 2) & 0x3f | 0x80) as u8
+                && bytes[2] >> 4 == (split_high_surrogate(c) & 3 | 8) as u8))
+}
+
+/// Returns the bits of the code point of a 4-byte sequence covered by the
+/// canonical high surrogate `c`.
+fn split_high_surrogate(c: u16) -> u16 {
+    (c & 0x3f | (c >> 2) & 0x3c0) + 0x40
+}
+
+/// Returns the first byte of a 4-byte sequence starting with the canonical
+/// high surrogate `c`.
+fn high_surrogate_first_byte(c: u16) -> u8 {
+    ((split_high_surrogate(c) >> 8) | 0xf0) as u8
+}
+
+impl<'p> Horspool<'p> {
+    /// Creates the searcher from the parts returned by `canonicalize()`.
+    ///
+    /// The middle must not be empty.
+    pub(crate) fn new(begin: u16, middle: &'p [u8], end: u16) -> Self {
+        let len = middle.len();
+        let mut skip = [len; 256];
+        for (i, &b) in middle[..len - 1].iter().enumerate() {
+            skip[usize::from(b)] = len - 1 - i;
+        }
+        Horspool {
+            begin,
+            middle,
+            end,
+            skip,
+        }
+    }
+
+    fn prefix_len(&self) -> usize {
+        if self.begin != 0 {
+            3
+        } else {
+            0
+        }
+    }
+
+    fn suffix_len(&self) -> usize {
+        if self.end != 0 {
+            3
+        } else {
+            0
+        }
+    }
+
+    /// Finds the first match in `bytes`, returning its byte range.
+    pub(crate) fn find(&self, bytes: &[u8]) -> Option<(usize, usize)> {
+        let (prefix_len, suffix_len) = (self.prefix_len(), self.suffix_len());
+        let len = self.middle.len();
+        let last = self.middle[len - 1];
+        let mut i = prefix_len;
+        while i + len + suffix_len <= bytes.len() {
+            let b = bytes[i + len - 1];
+            if b == last
+                && &bytes[i..i + len] == self.middle
+                && (prefix_len == 0 || matches_low_surrogate(self.begin, &bytes[i - 3..i]))
+                && (suffix_len == 0 || matches_high_surrogate(self.end, &bytes[i + len..]))
+            {
+                return Some((i - prefix_len, i + len + suffix_len));
+            }
+            i += self.skip[usize::from(b)];
+        }
+        None
+    }
+}
+
+/// Finds the first occurrence of the canonical surrogate `c`, returning its
+/// byte range.
+pub(crate) fn find_surrogate(c: u16, bytes: &[u8]) -> Option<(usize, usize)> {
+    let start = if c >= 0xb000 {
+        // both forms of a low surrogate end with the same byte.
+        memchr_iter(c as u8, bytes)
+            .filter(|&i| i >= 2)
+            .map(|i| i - 2)
+            .find(|&i| matches_low_surrogate(c, &bytes[i..]))
+    } else {
+        memchr2_iter(0xed, high_surrogate_first_byte(c), bytes)
+            .find(|&i| matches_high_surrogate(c, &bytes[i..]))
+    }?;
+    Some((start, start + 3))
+}
+
+/// Finds the last occurrence of the canonical surrogate `c`, returning its
+/// byte range.
+pub(crate) fn rfind_surrogate(c: u16, bytes: &[u8]) -> Option<(usize, usize)> {
+    let start = if c >= 0xb000 {
+        memchr_iter(c as u8, bytes)
+            .rev()
+            .filter(|&i| i >= 2)
+            .map(|i| i - 2)
+            .find(|&i| matches_low_surrogate(c, &bytes[i..]))
+    } else {
+        memchr2_iter(0xed, high_surrogate_first_byte(c), bytes)
+            .rev()
+            .find(|&i| matches_high_surrogate(c, &bytes[i..]))
+    }?;
+    Some((start, start + 3))
+}
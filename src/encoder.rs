@@ -0,0 +1,80 @@
+//! Streaming encoding to UTF-16.
+
+use std::io::{self, Write};
+use OmgWtf8;
+
+impl OmgWtf8 {
+    /// Writes this string to `writer` as UTF-16LE bytes.
+    ///
+    /// Unpaired surrogates are written as is. The code units are written in
+    /// batches directly from `encode_wide` without building a `Vec<u16>`.
+    pub fn write_utf16le<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_utf16(writer, false, false)
+    }
+
+    /// Writes this string to `writer` as UTF-16BE bytes.
+    pub fn write_utf16be<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_utf16(writer, true, false)
+    }
+
+    /// Writes a byte order mark followed by this string to `writer` as
+    /// UTF-16LE bytes.
+    pub fn write_utf16le_with_bom<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_utf16(writer, false, true)
+    }
+
+    /// Writes a byte order mark followed by this string to `writer` as
+    /// UTF-16BE bytes.
+    pub fn write_utf16be_with_bom<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_utf16(writer, true, true)
+    }
+
+    fn write_utf16<W: Write>(&self, mut writer: W, big_endian: bool, bom: bool) -> io::Result<()> {
+        let mut bytes = [0; 4096];
+        let mut len = 0;
+        let units = (if bom { Some(0xfeff) } else { None })
+            .into_iter()
+            .chain(self.encode_wide());
+        for c in units {
+            if len == bytes.len() {
+                writer.write_all(&bytes)?;
+                len = 0;
+            }
+            let pair = if big_endian {
+                c.to_be_bytes()
+            } else {
+                c.to_le_bytes()
+            };
+            bytes[len..len + 2].copy_from_slice(&pair);
+            len += 2;
+        }
+        writer.write_all(&bytes[..len])
+    }
+}
+
+#[test]
+fn test_write_utf16() {
+    use {OddBytePolicy, OmgWtf8Buf, Utf16ByteOrder};
+
+    let s = OmgWtf8::from_str("a😀");
+    let mut le = Vec::new();
+    s[..3].write_utf16le(&mut le).unwrap();
+    assert_eq!(le, b"a\0=\xd8");
+    let mut be = Vec::new();
+    s[3..].write_utf16be(&mut be).unwrap();
+    assert_eq!(be, b"\xde\0");
+    let mut bom = Vec::new();
+    s.write_utf16le_with_bom(&mut bom).unwrap();
+    assert_eq!(bom, b"\xff\xfea\0=\xd8\0\xde");
+    bom.clear();
+    s.write_utf16be_with_bom(&mut bom).unwrap();
+    assert_eq!(bom, b"\xfe\xff\0a\xd8=\xde\0");
+
+    let long = OmgWtf8Buf::from(&*"a😀".repeat(1000)) + &s[..3];
+    let mut bytes = Vec::new();
+    long.write_utf16be_with_bom(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 2 + 2 * long.encode_wide().count());
+    let read = OmgWtf8Buf::read_utf16(&bytes[..], Utf16ByteOrder::DetectBom, OddBytePolicy::Error)
+        .unwrap();
+    assert_eq!(read, long);
+}
@@ -0,0 +1,63 @@
+//! Decoding legacy encodings with `encoding_rs`.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use {OddBytePolicy, OmgWtf8Buf, Utf16ByteOrder};
+
+impl OmgWtf8Buf {
+    /// Decodes bytes in the given encoding into a new buffer, and returns it
+    /// with the encoding actually used and whether there were malformed
+    /// sequences.
+    ///
+    /// Like `Encoding::decode`, a byte order mark overrides the encoding and
+    /// is removed. Malformed sequences are replaced by U+FFFD, except that
+    /// UTF-16 is decoded losslessly: unpaired surrogates are preserved, and
+    /// only an odd trailing byte is replaced and reported as malformed.
+    pub fn decode_with(
+        bytes: &[u8],
+        encoding: &'static Encoding,
+    ) -> (Self, &'static Encoding, bool) {
+        let (encoding, bom_len) = Encoding::for_bom(bytes).unwrap_or((encoding, 0));
+        let bytes = &bytes[bom_len..];
+        if encoding == UTF_16LE || encoding == UTF_16BE {
+            let byte_order = if encoding == UTF_16LE {
+                Utf16ByteOrder::LittleEndian
+            } else {
+                Utf16ByteOrder::BigEndian
+            };
+            let buf = OmgWtf8Buf::read_utf16(bytes, byte_order, OddBytePolicy::Replace).unwrap();
+            (buf, encoding, bytes.len() & 1 != 0)
+        } else {
+            let (s, had_errors) = encoding.decode_without_bom_handling(bytes);
+            (OmgWtf8Buf::from(s.into_owned()), encoding, had_errors)
+        }
+    }
+}
+
+#[test]
+fn test_decode_with() {
+    use encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1252};
+    use OmgWtf8;
+
+    let (buf, encoding, had_errors) = OmgWtf8Buf::decode_with(b"caf\xe9", WINDOWS_1252);
+    assert_eq!(
+        (buf, encoding, had_errors),
+        (OmgWtf8Buf::from("café"), WINDOWS_1252, false)
+    );
+
+    let (buf, _, had_errors) = OmgWtf8Buf::decode_with(b"\x82\xa0\xff", SHIFT_JIS);
+    assert_eq!(buf, "あ\u{fffd}");
+    assert!(had_errors);
+
+    let (buf, encoding, had_errors) = OmgWtf8Buf::decode_with(b"=\xd8a\0\0\xdc", UTF_16LE);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0x61, 0xdc00]));
+    assert_eq!(encoding, UTF_16LE);
+    assert!(!had_errors);
+
+    let (buf, encoding, had_errors) = OmgWtf8Buf::decode_with(b"\xfe\xff\xd8=\0", UTF_8);
+    assert_eq!(*buf, *OmgWtf8::from_wide(&[0xd83d, 0xfffd]));
+    assert_eq!(encoding, UTF_16BE);
+    assert!(had_errors);
+
+    let (buf, encoding, _) = OmgWtf8Buf::decode_with(b"\xef\xbb\xbfa", UTF_16LE);
+    assert_eq!((buf, encoding), (OmgWtf8Buf::from("a"), UTF_8));
+}
@@ -0,0 +1,119 @@
+//! Reading environment variables.
+//!
+//! On Windows, environment variables are UTF-16 strings and are converted
+//! losslessly. On Unix, the bytes are used if they are well-formed
+//! OMG-WTF-8. On other platforms, only valid Unicode strings are supported.
+
+use std::env::{self, VarsOs};
+use std::ffi::{OsStr, OsString};
+use OmgWtf8;
+
+/// Converts an OS string, returning `None` if it is not representable.
+fn from_os_string(s: OsString) -> Option<Box<OmgWtf8>> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        let wide = s.encode_wide().collect::<Vec<u16>>();
+        Some(OmgWtf8::from_wide(&wide))
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        OmgWtf8::from_bytes(s.as_bytes())
+            .ok()
+            .map(|s| s.to_owned().into_boxed_omgwtf8())
+    }
+    #[cfg(not(any(windows, unix)))]
+    {
+        s.into_string().ok().map(|s| OmgWtf8::from_str(&s).into())
+    }
+}
+
+/// Fetches the environment variable `key` from the current process.
+///
+/// Returns `None` if the variable is not set, or if its value is not
+/// representable on this platform.
+pub fn var<K: AsRef<OsStr>>(key: K) -> Option<Box<OmgWtf8>> {
+    env::var_os(key).and_then(from_os_string)
+}
+
+/// Returns an iterator over the environment variables of the current
+/// process, as `(key, value)` pairs.
+///
+/// Variables whose key or value is not representable on this platform are
+/// skipped.
+pub fn vars() -> Vars {
+    Vars {
+        inner: env::vars_os(),
+    }
+}
+
+/// An iterator over the environment variables of the current process.
+///
+/// This is returned by [`vars`].
+///
+/// [`vars`]: fn.vars.html
+#[derive(Debug)]
+pub struct Vars {
+    inner: VarsOs,
+}
+
+impl Iterator for Vars {
+    type Item = (Box<OmgWtf8>, Box<OmgWtf8>);
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, value) in self.inner.by_ref() {
+            if let (Some(key), Some(value)) = (from_os_string(key), from_os_string(value)) {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+#[test]
+fn test_var() {
+    env::set_var("OMGWTF8_TEST_VAR", "a😀");
+    assert_eq!(&*var("OMGWTF8_TEST_VAR").unwrap(), OmgWtf8::from_str("a😀"));
+    assert!(vars().any(|(k, v)| *k == *"OMGWTF8_TEST_VAR" && *v == *"a😀"));
+    assert_eq!(var("OMGWTF8_TEST_VAR_UNSET"), None);
+    env::remove_var("OMGWTF8_TEST_VAR");
+    assert_eq!(var("OMGWTF8_TEST_VAR"), None);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_var_surrogate() {
+    use std::os::windows::ffi::OsStringExt;
+
+    env::set_var(
+        "OMGWTF8_TEST_SURROGATE",
+        OsString::from_wide(&[0x61, 0xd800]),
+    );
+    assert_eq!(
+        &*var("OMGWTF8_TEST_SURROGATE").unwrap(),
+        &*OmgWtf8::from_wide(&[0x61, 0xd800])
+    );
+    env::remove_var("OMGWTF8_TEST_SURROGATE");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_var_surrogate() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let value = OsString::from_vec(b"a\xed\xa0\x80".to_vec());
+    env::set_var("OMGWTF8_TEST_SURROGATE", value);
+    assert_eq!(
+        &*var("OMGWTF8_TEST_SURROGATE").unwrap(),
+        &*OmgWtf8::from_wide(&[0x61, 0xd800])
+    );
+    env::set_var("OMGWTF8_TEST_INVALID", OsString::from_vec(b"\xff".to_vec()));
+    assert_eq!(var("OMGWTF8_TEST_INVALID"), None);
+    assert!(vars().all(|(k, _)| *k != *"OMGWTF8_TEST_INVALID"));
+    env::remove_var("OMGWTF8_TEST_SURROGATE");
+    env::remove_var("OMGWTF8_TEST_INVALID");
+}
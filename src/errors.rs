@@ -0,0 +1,179 @@
+//! Error types.
+//!
+//! These are returned by the conversions and operations which report why they
+//! failed. Other conversions, e.g. `OmgWtf8::from_wtf8`,
+//! `OmgWtf8::from_code_point` or percent decoding, only return `None` on
+//! failure. All of these types implement `std::error::Error`, and can be
+//! converted into an `io::Error` to be propagated through I/O code.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use validate::Violation;
+
+pub use nul::NulError;
+pub use slice::SliceError;
+
+/// The error returned by [`OmgWtf8::from_bytes`] when the bytes are not a
+/// well-formed OMG-WTF-8 string.
+///
+/// [`OmgWtf8::from_bytes`]: ../struct.OmgWtf8.html#method.from_bytes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FromBytesError {
+    violation: Violation,
+    valid_up_to: usize,
+}
+
+impl FromBytesError {
+    pub(crate) fn new(violation: Violation, valid_up_to: usize) -> Self {
+        FromBytesError {
+            violation,
+            valid_up_to,
+        }
+    }
+
+    /// The first rule violation found in the bytes.
+    pub fn violation(&self) -> Violation {
+        self.violation
+    }
+
+    /// Returns the length of the longest prefix of the bytes which is
+    /// well-formed.
+    ///
+    /// This may be past the offset of the violation, when the offending
+    /// sequence is itself well-formed at the end of a string, e.g. the high
+    /// surrogate of a surrogate pair encoded as two 3-byte sequences.
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+}
+
+impl fmt::Display for FromBytesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid OMG-WTF-8 sequence ({:?}) at byte index {}",
+            self.violation.kind(),
+            self.violation.offset()
+        )
+    }
+}
+
+impl Error for FromBytesError {}
+
+/// The error returned by [`OmgWtf8::to_str_checked`] when the string contains
+/// an unpaired surrogate, which cannot be represented in UTF-8.
+///
+/// [`OmgWtf8::to_str_checked`]: ../struct.OmgWtf8.html#method.to_str_checked
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Utf16Error {
+    byte_offset: usize,
+    wide_offset: usize,
+    surrogate: u16,
+}
+
+impl Utf16Error {
+    pub(crate) fn new(byte_offset: usize, wide_offset: usize, surrogate: u16) -> Self {
+        Utf16Error {
+            byte_offset,
+            wide_offset,
+            surrogate,
+        }
+    }
+
+    /// Returns the length of the longest prefix of the string which is valid
+    /// UTF-8, i.e. the byte offset of the first unpaired surrogate.
+    pub fn valid_up_to(&self) -> usize {
+        self.byte_offset
+    }
+
+    /// Returns the position of the first unpaired surrogate, in UTF-16 code
+    /// units.
+    pub fn wide_position(&self) -> usize {
+        self.wide_offset
+    }
+
+    /// Returns the first unpaired surrogate.
+    pub fn unpaired_surrogate(&self) -> u16 {
+        self.surrogate
+    }
+}
+
+impl fmt::Display for Utf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unpaired surrogate {:x} found at position: {}",
+            self.surrogate, self.wide_offset
+        )
+    }
+}
+
+impl Error for Utf16Error {}
+
+impl From<FromBytesError> for io::Error {
+    fn from(e: FromBytesError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl From<Utf16Error> for io::Error {
+    fn from(e: Utf16Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl From<SliceError> for io::Error {
+    fn from(e: SliceError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+impl From<NulError> for io::Error {
+    fn from(e: NulError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+#[test]
+fn test_utf16_error() {
+    use OmgWtf8;
+
+    let s = OmgWtf8::from_wide(&[0x61, 0xd83d, 0xde00, 0xdc00, 0x62, 0xd800]);
+    assert_eq!(s[..5].to_str_checked(), Ok("a😀"));
+    let err = s.to_str_checked().unwrap_err();
+    assert_eq!(err.valid_up_to(), 5);
+    assert_eq!(err.wide_position(), 3);
+    assert_eq!(err.unpaired_surrogate(), 0xdc00);
+    assert_eq!(
+        err.to_string(),
+        "unpaired surrogate dc00 found at position: 3"
+    );
+
+    let err = s[3..].to_str_checked().unwrap_err();
+    assert_eq!((err.valid_up_to(), err.wide_position()), (0, 0));
+    assert_eq!(err.unpaired_surrogate(), 0xde00);
+
+    let err = io::Error::from(err);
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        "unpaired surrogate de00 found at position: 0"
+    );
+}
+
+#[test]
+fn test_io_error() {
+    use OmgWtf8;
+
+    let read = |bytes| -> io::Result<usize> { Ok(OmgWtf8::from_bytes(bytes)?.len_wide()) };
+    assert_eq!(read(b"a\xf0\x9f\x98\x80").unwrap(), 3);
+    let err = read(b"a\xc0\x80").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.get_ref().unwrap().is::<FromBytesError>());
+
+    let err = io::Error::from(OmgWtf8::from_str("a\0").to_wide_nul_checked().unwrap_err());
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = io::Error::from(OmgWtf8::from_str("😀").slice_checked(1..).unwrap_err());
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
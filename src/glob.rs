@@ -0,0 +1,79 @@
+//! Windows-style wildcard matching.
+//!
+//! Matching works on UTF-16 code units like the Windows file system APIs: `*`
+//! matches any sequence of code units, and `?` matches a single code unit,
+//! which may be a lone surrogate or half of a surrogate pair. Both the
+//! pattern and the text may contain unpaired surrogates.
+
+use OmgWtf8;
+
+/// Returns whether `text` matches the wildcard `pattern` entirely.
+///
+/// ```
+/// use omgwtf8::{glob, OmgWtf8};
+///
+/// let name = OmgWtf8::from_str("photo.JPG");
+/// assert!(glob::matches(OmgWtf8::from_str("*.JPG"), name));
+/// assert!(glob::matches(OmgWtf8::from_str("ph?to.*"), name));
+/// assert!(!glob::matches(OmgWtf8::from_str("*.png"), name));
+/// ```
+pub fn matches(pattern: &OmgWtf8, text: &OmgWtf8) -> bool {
+    let pattern = pattern.encode_wide().collect::<Vec<u16>>();
+    let text = text.encode_wide().collect::<Vec<u16>>();
+
+    let (mut p, mut t) = (0, 0);
+    // the position after the last `*` in the pattern, and the position in
+    // the text it is currently matched up to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(&0x2a) => {
+                p += 1;
+                backtrack = Some((p, t));
+                continue;
+            }
+            Some(&c) if c == 0x3f || c == text[t] => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match backtrack {
+            Some((star_p, star_t)) => {
+                p = star_p;
+                t = star_t + 1;
+                backtrack = Some((star_p, t));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == 0x2a)
+}
+
+#[test]
+fn test_matches() {
+    fn m(pattern: &str, text: &str) -> bool {
+        matches(OmgWtf8::from_str(pattern), OmgWtf8::from_str(text))
+    }
+
+    assert!(m("", ""));
+    assert!(m("*", ""));
+    assert!(!m("?", ""));
+    assert!(m("a*b*c", "aXbYbZc"));
+    assert!(!m("a*b*c", "aXbYbZ"));
+    assert!(m("*.txt", "a.b.txt"));
+    assert!(m("**a", "ba"));
+    assert!(!m("*.txt", "a.txt.bak"));
+    assert!(m("??", "😀"));
+    assert!(!m("?", "😀"));
+    assert!(m("?*", "😀"));
+
+    let s = OmgWtf8::from_str("😀.txt");
+    let lone = OmgWtf8::from_wide(&[0xde00, 0x2a]);
+    assert!(matches(&lone, &s[2..]));
+    assert!(!matches(&lone, s));
+    assert!(matches(omgwtf8!(r"?\u{de00}.*"), s));
+    assert!(!matches(omgwtf8!(r"?\u{de00}.*"), &s[2..]));
+    assert!(matches(omgwtf8!(r"*\u{d83d}"), &s[..2]));
+}
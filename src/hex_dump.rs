@@ -0,0 +1,87 @@
+//! Hex dump of the underlying bytes.
+
+use std::fmt::{self, Write};
+use OmgWtf8;
+
+impl OmgWtf8 {
+    /// Returns an adapter which displays the underlying bytes of this string
+    /// as a hex dump, in the format of `hexdump -C`.
+    ///
+    /// Each line shows the offset of its first byte, up to 16 bytes in hex,
+    /// and the printable ASCII characters among them.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("😀 OMG-WTF-8 string");
+    /// assert_eq!(
+    ///     s.hex_dump().to_string(),
+    ///     "00000000  f0 9f 98 80 20 4f 4d 47  2d 57 54 46 2d 38 20 73  |.... OMG-WTF-8 s|\n\
+    ///      00000010  74 72 69 6e 67                                    |tring|"
+    /// );
+    /// ```
+    pub fn hex_dump(&self) -> HexDump<'_> {
+        HexDump(self)
+    }
+}
+
+/// An adapter displaying the bytes of an OMG-WTF-8 string as a hex dump.
+///
+/// This is returned by [`OmgWtf8::hex_dump`].
+///
+/// [`OmgWtf8::hex_dump`]: struct.OmgWtf8.html#method.hex_dump
+#[derive(Copy, Clone, Debug)]
+pub struct HexDump<'a>(&'a OmgWtf8);
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, line) in self.0.as_bytes().chunks(16).enumerate() {
+            if i != 0 {
+                f.write_char('\n')?;
+            }
+            write!(f, "{:08x} ", i * 16)?;
+            for j in 0..16 {
+                if j == 8 {
+                    f.write_char(' ')?;
+                }
+                match line.get(j) {
+                    Some(b) => write!(f, " {:02x}", b)?,
+                    None => f.write_str("   ")?,
+                }
+            }
+            f.write_str("  |")?;
+            for &b in line {
+                let c = if b == b' ' || b.is_ascii_graphic() {
+                    b as char
+                } else {
+                    '.'
+                };
+                f.write_char(c)?;
+            }
+            f.write_char('|')?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_hex_dump() {
+    assert_eq!(OmgWtf8::EMPTY.hex_dump().to_string(), "");
+
+    let s = OmgWtf8::from_str("a😀b");
+    assert_eq!(
+        s[..3].hex_dump().to_string(),
+        "00000000  61 f0 9f 98                                       |a...|"
+    );
+    assert_eq!(
+        s[3..].hex_dump().to_string(),
+        "00000000  9f 98 80 62                                       |...b|"
+    );
+
+    let s = OmgWtf8::from_str("0123456789abcdef\t");
+    assert_eq!(
+        s.hex_dump().to_string(),
+        "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+         00000010  09                                                |.|"
+    );
+}
@@ -0,0 +1,109 @@
+//! JSON string escapes.
+
+use std::fmt::Write;
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8 {
+    /// Escapes this string as the content of a JSON string literal, i.e. the
+    /// part between the quotes.
+    ///
+    /// Every unpaired surrogate is written as a `\uXXXX` escape, so the
+    /// result is valid UTF-8 and can be parsed back losslessly with
+    /// `OmgWtf8Buf::from_json_escaped`.
+    pub fn to_json_escaped(&self) -> String {
+        let mut escaped = String::with_capacity(self.len());
+        for c in self.chars() {
+            match c.to_char() {
+                Some('"') => escaped.push_str("\\\""),
+                Some('\\') => escaped.push_str("\\\\"),
+                Some('\u{8}') => escaped.push_str("\\b"),
+                Some('\u{c}') => escaped.push_str("\\f"),
+                Some('\n') => escaped.push_str("\\n"),
+                Some('\r') => escaped.push_str("\\r"),
+                Some('\t') => escaped.push_str("\\t"),
+                Some(c) if c >= ' ' => escaped.push(c),
+                _ => write!(escaped, "\\u{:04X}", c.to_u32()).unwrap(),
+            }
+        }
+        escaped
+    }
+}
+
+impl OmgWtf8Buf {
+    /// Parses the content of a JSON string literal, i.e. the part between the
+    /// quotes, returning `None` if it is not valid.
+    ///
+    /// Like JavaScript, a `\uXXXX` escape may produce an unpaired surrogate.
+    /// Escaped surrogates forming a pair are merged into a supplementary
+    /// character.
+    pub fn from_json_escaped(s: &str) -> Option<Self> {
+        let mut buf = OmgWtf8Buf::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(pos) = rest.find(|c| c == '\\' || c == '"' || c < ' ') {
+            buf.push_omgwtf8(OmgWtf8::from_str(&rest[..pos]));
+            let escape = &rest.as_bytes()[pos..];
+            let (c, len) = match escape {
+                [b'\\', b'u', hex @ ..] => {
+                    let hex = hex.get(..4)?;
+                    if !hex.iter().all(u8::is_ascii_hexdigit) {
+                        return None;
+                    }
+                    let hex = ::std::str::from_utf8(hex).unwrap();
+                    (u16::from_str_radix(hex, 16).unwrap(), 6)
+                }
+                [b'\\', b'"', ..] => (0x22, 2),
+                [b'\\', b'\\', ..] => (0x5c, 2),
+                [b'\\', b'/', ..] => (0x2f, 2),
+                [b'\\', b'b', ..] => (0x08, 2),
+                [b'\\', b'f', ..] => (0x0c, 2),
+                [b'\\', b'n', ..] => (0x0a, 2),
+                [b'\\', b'r', ..] => (0x0d, 2),
+                [b'\\', b't', ..] => (0x09, 2),
+                _ => return None,
+            };
+            buf.push_code_unit(c);
+            rest = &rest[pos + len..];
+        }
+        buf.push_omgwtf8(OmgWtf8::from_str(rest));
+        Some(buf)
+    }
+}
+
+#[test]
+fn test_from_json_escaped() {
+    fn parse(s: &str) -> Option<Vec<u16>> {
+        OmgWtf8Buf::from_json_escaped(s).map(|buf| buf.encode_wide().collect())
+    }
+
+    assert_eq!(parse("a😀"), Some(vec![0x61, 0xd83d, 0xde00]));
+    assert_eq!(parse(r"\ud83d\uDE00"), Some(vec![0xd83d, 0xde00]));
+    assert!(OmgWtf8Buf::from_json_escaped(r"\ud83d\uDE00").unwrap() == "😀");
+    assert_eq!(parse(r"\uDE00\ud83d"), Some(vec![0xde00, 0xd83d]));
+    assert_eq!(parse(r"x\ud83dy"), Some(vec![0x78, 0xd83d, 0x79]));
+    assert_eq!(
+        parse(r#"\"\\\/\b\f\n\r\t\u0000"#),
+        Some(vec![0x22, 0x5c, 0x2f, 8, 0xc, 0xa, 0xd, 9, 0])
+    );
+    assert_eq!(parse(""), Some(vec![]));
+
+    assert_eq!(parse(r"\x41"), None);
+    assert_eq!(parse(r"\u12"), None);
+    assert_eq!(parse(r"\u+123"), None);
+    assert_eq!(parse(r"\"), None);
+    assert_eq!(parse("\""), None);
+    assert_eq!(parse("\n"), None);
+}
+
+#[test]
+fn test_to_json_escaped() {
+    let s = OmgWtf8::from_str("a\"\\/\u{8}\u{c}\n\r\t\0\u{1f}\u{7f}😀");
+    let escaped = s.to_json_escaped();
+    assert_eq!(escaped, "a\\\"\\\\/\\b\\f\\n\\r\\t\\u0000\\u001F\u{7f}😀");
+    assert_eq!(*OmgWtf8Buf::from_json_escaped(&escaped).unwrap(), *s);
+
+    let lone = OmgWtf8::from_wide(&[0xde00, 0x61, 0xd83d]);
+    assert_eq!(lone.to_json_escaped(), r"\uDE00a\uD83D");
+    assert_eq!(s[14..].to_json_escaped(), r"\uDE00");
+    let parsed = OmgWtf8Buf::from_json_escaped(&lone.to_json_escaped()).unwrap();
+    assert_eq!(*parsed, *lone);
+}
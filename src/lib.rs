@@ -1,12 +1,118 @@
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "bstr")]
+extern crate bstr;
+#[cfg(feature = "unicode")]
+extern crate caseless;
+#[cfg(feature = "encoding_rs")]
+extern crate encoding_rs;
+#[cfg(feature = "wasm")]
+extern crate js_sys;
+extern crate memchr;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 extern crate regex;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
+#[cfg(feature = "unicode-width")]
+extern crate unicode_width;
+#[cfg(feature = "zerocopy")]
+extern crate zerocopy;
 
+#[doc(hidden)]
+#[macro_use]
+pub mod literal;
 mod slice;
+mod stream;
 mod conv;
 mod cmp;
 pub mod pattern;
+pub mod pattern_v2;
 mod matching;
+mod validate;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+#[cfg(feature = "bstr")]
+mod bstr_impls;
+mod buf;
+mod case;
+mod char_set;
+mod code_point;
+mod cursor;
+mod decoder;
+mod edge_search;
+mod encoder;
+pub mod errors;
+pub mod env;
+pub mod glob;
+#[cfg(feature = "encoding_rs")]
+mod encoding_rs_impls;
+mod hex_dump;
+mod json;
+mod measured;
+mod mutf8;
+#[cfg(feature = "unicode-normalization")]
+mod normalize;
+mod nul;
+#[cfg(windows)]
+mod os_str_impls;
+pub mod path;
+mod percent;
+mod portable;
+#[cfg(feature = "rayon")]
+mod rayon_impls;
+mod sanitize;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "serde")]
+pub mod serde_escaped;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "wasm")]
+mod wasm_impls;
+#[cfg(feature = "unicode-width")]
+mod width;
 
 /// An OMG-WTF-8 string.
+///
+/// With the `zerocopy` feature, this type implements `IntoBytes`,
+/// `Immutable` and `Unaligned`, so it can be written into
+/// zero-copy archives directly. Since not every byte sequence is a valid
+/// OMG-WTF-8 string, it is read back by re-validating the stored bytes using
+/// [`OmgWtf8::from_bytes`].
+///
+/// [`OmgWtf8::from_bytes`]: #method.from_bytes
+#[repr(transparent)]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::Unaligned)
+)]
 pub struct OmgWtf8([u8]);
 
-pub use matching::MatchExt;
+/// An owned, growable OMG-WTF-8 string.
+///
+/// The content is always canonical, i.e. it never starts or ends with a split
+/// surrogate, so it is also a well-formed WTF-8 string.
+#[derive(Clone, Default)]
+pub struct OmgWtf8Buf(Vec<u8>);
+
+pub use buf::{concat, join};
+pub use char_set::CharSet;
+pub use cmp::OrdBytes;
+pub use code_point::{Chars, CodePoint, WideIndices};
+pub use decoder::{OddBytePolicy, Utf16ByteOrder, WideDecoder};
+pub use errors::{FromBytesError, Utf16Error};
+pub use hex_dump::HexDump;
+pub use matching::{MatchExt, MatchRanges, MatchRangesWide, RSplit, Split, SplitMut, SplitOptions};
+pub use measured::OmgWtf8Measured;
+pub use nul::NulError;
+pub use percent::AsciiSet;
+pub use slice::{IndexType, SliceError};
+pub use stream::{StreamMatches, StreamSearcher};
+pub use validate::{ValidationReport, Violation, ViolationKind};
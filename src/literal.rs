@@ -0,0 +1,176 @@
+//! Support for the `omgwtf8!` macro.
+
+/// Creates a `&'static OmgWtf8` from a string literal at compile time.
+///
+/// Inside the literal, `\u{XXXX}` is replaced by the code point U+XXXX,
+/// which may be an unpaired surrogate, and `\\` is replaced by a single
+/// backslash. Since these escapes are interpreted by this macro rather than
+/// by the compiler, they are usually written in a raw string literal.
+///
+/// The result is in canonical representation. A high surrogate escape
+/// immediately followed by a low surrogate escape is rejected at compile
+/// time, because the pair should be written as a supplementary character.
+///
+/// ```
+/// # #[macro_use] extern crate omgwtf8;
+/// # fn main() {
+/// use omgwtf8::OmgWtf8;
+///
+/// const S: &OmgWtf8 = omgwtf8!(r"a\u{d800}\\b");
+/// assert_eq!(S, &*OmgWtf8::from_wide(&[0x61, 0xd800, 0x5c, 0x62]));
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate omgwtf8;
+/// # fn main() {
+/// let s = omgwtf8!(r"\u{d83d}\u{de00}");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! omgwtf8 {
+    ($s:expr) => {{
+        const INPUT: &str = $s;
+        const LEN: usize = $crate::literal::unescaped_len(INPUT);
+        const BYTES: [u8; LEN] = $crate::literal::unescape::<LEN>(INPUT);
+        const RESULT: &$crate::OmgWtf8 = $crate::literal::from_static_bytes(&BYTES);
+        RESULT
+    }};
+}
+
+use OmgWtf8;
+
+/// Decodes the item at index `i` of the literal, and returns the index of
+/// the next item, the value, and whether the value is an escaped code point
+/// rather than a raw byte.
+const fn decode(s: &[u8], i: usize) -> (usize, u32, bool) {
+    if s[i] != b'\\' {
+        return (i + 1, s[i] as u32, false);
+    }
+    if i + 1 < s.len() && s[i + 1] == b'\\' {
+        return (i + 2, b'\\' as u32, false);
+    }
+    if i + 2 >= s.len() || s[i + 1] != b'u' || s[i + 2] != b'{' {
+        panic!("invalid escape in omgwtf8! literal, expected a `\\u` escape or `\\\\`");
+    }
+    let mut j = i + 3;
+    let mut value = 0u32;
+    while j < s.len() && s[j] != b'}' {
+        let digit = match s[j] {
+            b'0'..=b'9' => s[j] - b'0',
+            b'a'..=b'f' => s[j] - b'a' + 10,
+            b'A'..=b'F' => s[j] - b'A' + 10,
+            _ => panic!("invalid hex digit in `\\u` escape of omgwtf8! literal"),
+        };
+        if j - i > 8 {
+            panic!("too many hex digits in `\\u` escape of omgwtf8! literal");
+        }
+        value = value << 4 | digit as u32;
+        j += 1;
+    }
+    if j == s.len() || j == i + 3 {
+        panic!("unterminated or empty `\\u` escape in omgwtf8! literal");
+    }
+    if value > 0x10_ffff {
+        panic!("code point in `\\u` escape of omgwtf8! literal is above U+10FFFF");
+    }
+    (j + 1, value, true)
+}
+
+/// Returns the number of bytes needed to encode a code point as WTF-8.
+const fn encoded_len(c: u32) -> usize {
+    match c {
+        0..=0x7f => 1,
+        0x80..=0x7ff => 2,
+        0x800..=0xffff => 3,
+        _ => 4,
+    }
+}
+
+/// Computes the length of the OMG-WTF-8 string described by the literal, and
+/// validates that it is in canonical representation.
+#[doc(hidden)]
+pub const fn unescaped_len(s: &str) -> usize {
+    let s = s.as_bytes();
+    let mut i = 0;
+    let mut len = 0;
+    let mut after_high_surrogate = false;
+    while i < s.len() {
+        let (next, c, escaped) = decode(s, i);
+        if escaped {
+            if after_high_surrogate && 0xdc00 <= c && c <= 0xdfff {
+                panic!("surrogate pair in omgwtf8! literal must be written as a supplementary character");
+            }
+            after_high_surrogate = 0xd800 <= c && c <= 0xdbff;
+            len += encoded_len(c);
+        } else {
+            after_high_surrogate = false;
+            len += 1;
+        }
+        i = next;
+    }
+    len
+}
+
+/// Encodes the OMG-WTF-8 string described by the literal. `N` must be the
+/// value returned by `unescaped_len`.
+#[doc(hidden)]
+pub const fn unescape<const N: usize>(s: &str) -> [u8; N] {
+    let s = s.as_bytes();
+    let mut out = [0; N];
+    let mut i = 0;
+    let mut len = 0;
+    while i < s.len() {
+        let (next, c, escaped) = decode(s, i);
+        if !escaped {
+            out[len] = c as u8;
+            len += 1;
+        } else {
+            match encoded_len(c) {
+                1 => out[len] = c as u8,
+                2 => {
+                    out[len] = (c >> 6 | 0xc0) as u8;
+                    out[len + 1] = (c & 0x3f | 0x80) as u8;
+                }
+                3 => {
+                    out[len] = (c >> 12 | 0xe0) as u8;
+                    out[len + 1] = (c >> 6 & 0x3f | 0x80) as u8;
+                    out[len + 2] = (c & 0x3f | 0x80) as u8;
+                }
+                _ => {
+                    out[len] = (c >> 18 | 0xf0) as u8;
+                    out[len + 1] = (c >> 12 & 0x3f | 0x80) as u8;
+                    out[len + 2] = (c >> 6 & 0x3f | 0x80) as u8;
+                    out[len + 3] = (c & 0x3f | 0x80) as u8;
+                }
+            }
+            len += encoded_len(c);
+        }
+        i = next;
+    }
+    out
+}
+
+/// Wraps the bytes produced by `unescape` as an OMG-WTF-8 string.
+#[doc(hidden)]
+pub const fn from_static_bytes(bytes: &'static [u8]) -> &'static OmgWtf8 {
+    unsafe { &*(bytes as *const [u8] as *const OmgWtf8) }
+}
+
+#[test]
+fn test_omgwtf8_macro() {
+    const PLAIN: &OmgWtf8 = omgwtf8!("a😀");
+    assert_eq!(PLAIN, OmgWtf8::from_str("a😀"));
+    assert_eq!(omgwtf8!(""), OmgWtf8::EMPTY);
+
+    let s = omgwtf8!(r"\u{DC00}a\u{e9}\u{1F600}\\u\u{d83d}");
+    assert_eq!(
+        s.as_bytes(),
+        b"\xed\xb0\x80a\xc3\xa9\xf0\x9f\x98\x80\\u\xed\xa0\xbd"
+    );
+    assert!(s.is_canonical());
+    assert_eq!(
+        omgwtf8!(r"\u{de00}\u{d83d}"),
+        &*OmgWtf8::from_wide(&[0xde00, 0xd83d])
+    );
+}
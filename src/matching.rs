@@ -1,4 +1,12 @@
-use pattern::{Haystack, Pattern, Searcher};
+use cursor;
+use memchr::{memchr, memmem};
+use pattern::{
+    DoubleEndedSearcher, EndCursor, Haystack, OwnedHaystack, Pattern, ReverseSearcher, Searcher,
+    StartCursor,
+};
+use std::ops::Range;
+use std::vec;
+use OmgWtf8;
 
 /// Extension for matching
 pub trait MatchExt: Haystack {
@@ -6,7 +14,29 @@ pub trait MatchExt: Haystack {
         pat.is_contained_in(self)
     }
 
+    fn starts_with<P: Pattern<Self>>(self, pat: P) -> bool {
+        pat.is_prefix_of(self)
+    }
+
+    fn ends_with<P: Pattern<Self>>(self, pat: P) -> bool {
+        pat.is_suffix_of(self)
+    }
+
     fn split<P: Pattern<Self>>(self, pat: P) -> Split<Self, P> {
+        self.split_with(pat, SplitOptions::new())
+    }
+
+    /// Splits by the pattern, keeping each match at the end of the piece
+    /// before it. A trailing empty piece is not returned.
+    fn split_inclusive<P: Pattern<Self>>(self, pat: P) -> Split<Self, P> {
+        let options = SplitOptions::new()
+            .inclusive(true)
+            .allow_trailing_empty(false);
+        self.split_with(pat, options)
+    }
+
+    /// Splits by the pattern, with the behavior configured by `options`.
+    fn split_with<P: Pattern<Self>>(self, pat: P, options: SplitOptions) -> Split<Self, P> {
         let start = Self::cursor_at_front(&self);
         let end = Self::cursor_at_back(&self);
         let matcher = pat.into_searcher(self);
@@ -14,39 +44,250 @@ pub trait MatchExt: Haystack {
             start,
             end,
             matcher,
-            allow_trailing_empty: true,
+            allow_trailing_empty: options.allow_trailing_empty,
+            inclusive: options.inclusive,
             finished: false,
         }
     }
 
+    /// Splits by the pattern, starting from the back.
+    ///
+    /// The pieces are those of `split` in reverse order if the searcher is a
+    /// [`DoubleEndedSearcher`]. Otherwise the matches are searched from the
+    /// back, and may differ when they can overlap.
+    ///
+    /// [`DoubleEndedSearcher`]: pattern/trait.DoubleEndedSearcher.html
+    fn rsplit<P: Pattern<Self>>(self, pat: P) -> RSplit<Self, P>
+    where
+        P::Searcher: ReverseSearcher<Self>,
+    {
+        RSplit(self.split(pat))
+    }
+
     fn find<P: Pattern<Self>>(self, pat: P) -> Option<usize> {
         let mut searcher = pat.into_searcher(self);
         let cursor = searcher.next_match()?.0;
-        unsafe { Some(Self::start_cursor_to_offset(&searcher.haystack(), cursor)) }
+        Some(Self::start_cursor_to_offset(&searcher.haystack(), cursor))
+    }
+
+    fn rfind<P: Pattern<Self>>(self, pat: P) -> Option<usize>
+    where
+        P::Searcher: ReverseSearcher<Self>,
+    {
+        let mut searcher = pat.into_searcher(self);
+        let cursor = searcher.next_match_back()?.0;
+        Some(Self::start_cursor_to_offset(&searcher.haystack(), cursor))
+    }
+
+    /// Returns the offset range of the `n`-th match of the pattern, counting
+    /// from zero.
+    fn find_nth<P: Pattern<Self>>(self, pat: P, n: usize) -> Option<Range<usize>> {
+        let mut searcher = pat.into_searcher(self);
+        for _ in 0..n {
+            searcher.next_match()?;
+        }
+        let (start, end) = searcher.next_match()?;
+        let haystack = searcher.haystack();
+        let start = Self::start_cursor_to_offset(&haystack, start);
+        Some(start..Self::end_cursor_to_offset(&haystack, end))
+    }
+
+    /// Returns an iterator over the matches of the pattern, with the offset
+    /// range of each match.
+    fn match_ranges<P: Pattern<Self>>(self, pat: P) -> MatchRanges<Self, P> {
+        MatchRanges {
+            searcher: pat.into_searcher(self),
+        }
+    }
+
+    /// Removes all matches of the pattern from both ends.
+    ///
+    /// The result spans from the end of the adjacent matches at the front to
+    /// the start of the adjacent matches at the back, or is empty if
+    /// everything is matched.
+    fn trim_matches<P: Pattern<Self>>(self, pat: P) -> Self::Piece
+    where
+        P::Searcher: DoubleEndedSearcher<Self>,
+    {
+        let mut searcher = pat.into_searcher(self);
+        let haystack = searcher.haystack();
+        let mut start = Self::cursor_at_front(&haystack);
+        let mut end = Self::cursor_at_back(&haystack);
+
+        // the first match not adjacent to the front is already consumed, so
+        // it is kept in case it also turns out to be adjacent to the back.
+        let mut inner = None;
+        while let Some((a, b)) = searcher.next_match() {
+            if Self::start_cursor_to_offset(&haystack, a)
+                != Self::start_cursor_to_offset(&haystack, start)
+            {
+                inner = Some((a, b));
+                break;
+            }
+            start = Self::end_to_start_cursor(&haystack, b);
+        }
+        let (a, b) = match inner {
+            Some(m) => m,
+            None => return Self::range_to_self(haystack, start, end),
+        };
+
+        loop {
+            match searcher.next_match_back() {
+                Some((c, d))
+                    if Self::end_cursor_to_offset(&haystack, d)
+                        == Self::end_cursor_to_offset(&haystack, end) =>
+                {
+                    end = Self::start_to_end_cursor(&haystack, c);
+                }
+                Some(_) => break,
+                None => {
+                    if Self::end_cursor_to_offset(&haystack, b)
+                        == Self::end_cursor_to_offset(&haystack, end)
+                    {
+                        end = Self::start_to_end_cursor(&haystack, a);
+                    }
+                    break;
+                }
+            }
+        }
+        Self::range_to_self(haystack, start, end)
+    }
+
+    /// Replaces all matches of the pattern with `to`.
+    fn replace<P: Pattern<Self>>(self, pat: P, to: Self::Piece) -> Self::Owned
+    where
+        Self: OwnedHaystack,
+        Self::Piece: Copy,
+    {
+        let mut result = Self::Owned::default();
+        let mut pieces = self.split(pat);
+        if let Some(first) = pieces.next() {
+            Self::push_to_owned(&mut result, first);
+        }
+        for piece in pieces {
+            Self::push_to_owned(&mut result, to);
+            Self::push_to_owned(&mut result, piece);
+        }
+        result
+    }
+
+    /// Concatenates the pieces, using `self` as the separator.
+    fn join<I: IntoIterator<Item = Self::Piece>>(self, pieces: I) -> Self::Owned
+    where
+        Self: OwnedHaystack,
+        Self::Piece: Copy,
+    {
+        let (front, back) = (Self::cursor_at_front(&self), Self::cursor_at_back(&self));
+        let separator = Self::range_to_self(self, front, back);
+        let mut result = Self::Owned::default();
+        for (i, piece) in pieces.into_iter().enumerate() {
+            if i != 0 {
+                Self::push_to_owned(&mut result, separator);
+            }
+            Self::push_to_owned(&mut result, piece);
+        }
+        result
+    }
+
+    /// Returns owned copies of all matches of the pattern.
+    fn to_owned_matches<P: Pattern<Self>>(self, pat: P) -> Vec<Self::Owned>
+    where
+        Self: OwnedHaystack,
+    {
+        let mut searcher = pat.into_searcher(self);
+        let mut matches = Vec::new();
+        while let Some((start, end)) = searcher.next_match() {
+            let mut owned = Self::Owned::default();
+            Self::push_to_owned(
+                &mut owned,
+                Self::range_to_self(searcher.haystack(), start, end),
+            );
+            matches.push(owned);
+        }
+        matches
     }
 }
 
 impl<H: Haystack> MatchExt for H {}
 
+/// An iterator over the matches of a pattern and their offset ranges.
+///
+/// This is returned by [`MatchExt::match_ranges`].
+///
+/// [`MatchExt::match_ranges`]: trait.MatchExt.html#method.match_ranges
+pub struct MatchRanges<H: Haystack, P: Pattern<H>> {
+    searcher: P::Searcher,
+}
+
+impl<H: Haystack, P: Pattern<H>> Iterator for MatchRanges<H, P> {
+    type Item = (Range<usize>, H::Piece);
+    fn next(&mut self) -> Option<(Range<usize>, H::Piece)> {
+        let (start, end) = self.searcher.next_match()?;
+        let haystack = self.searcher.haystack();
+        let range =
+            H::start_cursor_to_offset(&haystack, start)..H::end_cursor_to_offset(&haystack, end);
+        Some((range, H::range_to_self(haystack, start, end)))
+    }
+}
+
+/// Options of [`MatchExt::split_with`].
+///
+/// [`MatchExt::split_with`]: trait.MatchExt.html#method.split_with
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SplitOptions {
+    allow_trailing_empty: bool,
+    inclusive: bool,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            allow_trailing_empty: true,
+            inclusive: false,
+        }
+    }
+}
+
+impl SplitOptions {
+    /// Creates the options used by [`MatchExt::split`]: the matches are
+    /// dropped, and an empty piece after the last match is returned.
+    ///
+    /// [`MatchExt::split`]: trait.MatchExt.html#method.split
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether an empty piece after the last match is returned.
+    pub fn allow_trailing_empty(mut self, allow: bool) -> Self {
+        self.allow_trailing_empty = allow;
+        self
+    }
+
+    /// Sets whether each match is kept at the end of the piece before it.
+    pub fn inclusive(mut self, inclusive: bool) -> Self {
+        self.inclusive = inclusive;
+        self
+    }
+}
+
 pub struct Split<H: Haystack, P: Pattern<H>> {
     start: H::StartCursor,
     end: H::EndCursor,
     matcher: P::Searcher,
     allow_trailing_empty: bool,
+    inclusive: bool,
     finished: bool,
 }
 
 impl<H: Haystack, P: Pattern<H>> Split<H, P> {
-    fn get_end(&mut self) -> Option<H> {
+    fn get_end(&mut self) -> Option<H::Piece> {
         if !self.finished && (self.allow_trailing_empty || self.start < self.end) {
             self.finished = true;
-            unsafe {
-                Some(H::range_to_self(
-                    self.matcher.haystack(),
-                    self.start,
-                    self.end,
-                ))
-            }
+            Some(H::range_to_self(
+                self.matcher.haystack(),
+                self.start,
+                self.end,
+            ))
         } else {
             None
         }
@@ -54,25 +295,272 @@ impl<H: Haystack, P: Pattern<H>> Split<H, P> {
 }
 
 impl<H: Haystack, P: Pattern<H>> Iterator for Split<H, P> {
-    type Item = H;
-    fn next(&mut self) -> Option<H> {
+    type Item = H::Piece;
+    fn next(&mut self) -> Option<H::Piece> {
         if self.finished {
             return None;
         }
         match self.matcher.next_match() {
-            Some((a, b)) => unsafe {
+            Some((a, b)) => {
                 let haystack = self.matcher.haystack();
-                let a = H::start_to_end_cursor(&haystack, a);
+                let a = if self.inclusive {
+                    b
+                } else {
+                    H::start_to_end_cursor(&haystack, a)
+                };
                 let b = H::end_to_start_cursor(&haystack, b);
                 let elt = H::range_to_self(haystack, self.start, a);
                 self.start = b;
                 Some(elt)
-            },
+            }
             None => self.get_end(),
         }
     }
 }
 
+impl<H: Haystack, P: Pattern<H>> Split<H, P>
+where
+    P::Searcher: ReverseSearcher<H>,
+{
+    fn next_back_range(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        if self.finished {
+            return None;
+        }
+        match self.matcher.next_match_back() {
+            Some((a, b)) => {
+                let haystack = self.matcher.haystack();
+                let a = if self.inclusive {
+                    b
+                } else {
+                    H::start_to_end_cursor(&haystack, a)
+                };
+                let b = H::end_to_start_cursor(&haystack, b);
+                let range = (b, self.end);
+                self.end = a;
+                Some(range)
+            }
+            None => {
+                self.finished = true;
+                Some((self.start, self.end))
+            }
+        }
+    }
+
+    fn next_back_piece(&mut self) -> Option<H::Piece> {
+        let mut range = self.next_back_range()?;
+        if !self.allow_trailing_empty {
+            // the trailing piece is skipped if it is empty.
+            self.allow_trailing_empty = true;
+            if !(range.0 < range.1) {
+                range = self.next_back_range()?;
+            }
+        }
+        Some(H::range_to_self(self.matcher.haystack(), range.0, range.1))
+    }
+}
+
+impl<H: Haystack, P: Pattern<H>> DoubleEndedIterator for Split<H, P>
+where
+    P::Searcher: DoubleEndedSearcher<H>,
+{
+    fn next_back(&mut self) -> Option<H::Piece> {
+        self.next_back_piece()
+    }
+}
+
+/// An iterator over the pieces split by a pattern, starting from the back.
+///
+/// This is returned by [`MatchExt::rsplit`].
+///
+/// [`MatchExt::rsplit`]: trait.MatchExt.html#method.rsplit
+pub struct RSplit<H: Haystack, P: Pattern<H>>(Split<H, P>);
+
+impl<H: Haystack, P: Pattern<H>> Iterator for RSplit<H, P>
+where
+    P::Searcher: ReverseSearcher<H>,
+{
+    type Item = H::Piece;
+    fn next(&mut self) -> Option<H::Piece> {
+        self.0.next_back_piece()
+    }
+}
+
+impl<H: Haystack, P: Pattern<H>> DoubleEndedIterator for RSplit<H, P>
+where
+    P::Searcher: DoubleEndedSearcher<H>,
+{
+    fn next_back(&mut self) -> Option<H::Piece> {
+        self.0.next()
+    }
+}
+
+impl OmgWtf8 {
+    /// Returns the byte offset of the first occurrence of the ASCII byte.
+    ///
+    /// Non-ASCII bytes are only parts of encoded characters, so they are
+    /// never found.
+    pub fn find_byte(&self, byte: u8) -> Option<usize> {
+        if byte.is_ascii() {
+            memchr(byte, &self.0)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the byte offset of the first occurrence of the character.
+    ///
+    /// This is the same as [`find`](trait.MatchExt.html#method.find) with
+    /// the character as the needle, without compiling a searcher.
+    pub fn find_char(&self, c: char) -> Option<usize> {
+        let mut buf = [0; 4];
+        let encoded = c.encode_utf8(&mut buf).as_bytes();
+        if encoded.len() == 1 {
+            memchr(encoded[0], &self.0)
+        } else {
+            // a surrogate half split at either edge never holds all the
+            // bytes of a character, so every occurrence of the bytes is the
+            // character itself.
+            memmem::find(&self.0, encoded)
+        }
+    }
+
+    /// Returns whether the UTF-16 needle is contained in this string.
+    ///
+    /// The code units are compared in place, so the needle, which may
+    /// contain unpaired surrogates, is never converted or allocated. This
+    /// takes time proportional to the product of the lengths, and is meant
+    /// for short needles.
+    pub fn contains_wide(&self, needle: &[u16]) -> bool {
+        let mut units = self.encode_wide();
+        loop {
+            if units.clone().take(needle.len()).eq(needle.iter().cloned()) {
+                return true;
+            }
+            if units.next().is_none() {
+                return false;
+            }
+        }
+    }
+
+    /// Returns the offset of the first match of the pattern, counted in
+    /// UTF-16 code units.
+    ///
+    /// A match starting with the low surrogate half of a supplementary
+    /// character is reported at the offset of that code unit.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("😀a😑a");
+    /// assert_eq!(s.find_wide(OmgWtf8::from_str("a")), Some(2));
+    /// ```
+    pub fn find_wide<'h, P: Pattern<&'h OmgWtf8>>(&'h self, pat: P) -> Option<usize> {
+        let index = MatchExt::find(self, pat)?;
+        Some(self[..index].len_wide())
+    }
+
+    /// Returns an iterator over the offset ranges of the matches of the
+    /// pattern, counted in UTF-16 code units.
+    pub fn match_ranges_wide<'h, P: Pattern<&'h OmgWtf8>>(
+        &'h self,
+        pat: P,
+    ) -> MatchRangesWide<'h, P> {
+        MatchRangesWide {
+            matches: self.match_ranges(pat),
+            haystack: self,
+            offset: 0,
+            wide_offset: 0,
+        }
+    }
+
+    /// Splits this string by the separator into disjoint mutable pieces.
+    ///
+    /// The pieces are the same as [`split`](trait.MatchExt.html#method.split)
+    /// would give.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a match starts or ends in the middle of a surrogate pair,
+    /// since the pieces on both sides would share the bytes of the 4-byte
+    /// sequence.
+    pub fn split_mut<'h>(&'h mut self, separator: &OmgWtf8) -> SplitMut<'h> {
+        let mut cuts = Vec::new();
+        {
+            let haystack: &OmgWtf8 = self;
+            let mut searcher = separator.into_searcher(haystack);
+            while let Some((a, b)) = searcher.next_match() {
+                cuts.push((
+                    Haystack::start_to_end_cursor(&haystack, a),
+                    Haystack::end_to_start_cursor(&haystack, b),
+                ));
+            }
+        }
+        SplitMut {
+            rest: Some(self),
+            cuts: cuts.into_iter(),
+            offset: 0,
+        }
+    }
+}
+
+/// An iterator over the UTF-16 offset ranges of the matches of a pattern.
+///
+/// This is returned by [`OmgWtf8::match_ranges_wide`].
+///
+/// [`OmgWtf8::match_ranges_wide`]: struct.OmgWtf8.html#method.match_ranges_wide
+pub struct MatchRangesWide<'h, P: Pattern<&'h OmgWtf8>> {
+    matches: MatchRanges<&'h OmgWtf8, P>,
+    haystack: &'h OmgWtf8,
+    /// The byte offset of the previous match, and the same position counted
+    /// in UTF-16 code units, so the haystack is only measured once.
+    offset: usize,
+    wide_offset: usize,
+}
+
+impl<'h, P: Pattern<&'h OmgWtf8>> Iterator for MatchRangesWide<'h, P> {
+    type Item = Range<usize>;
+    fn next(&mut self) -> Option<Range<usize>> {
+        let (range, piece) = self.matches.next()?;
+        // a slice starting from the middle of a 4-byte sequence begins with
+        // the low surrogate, so the code units on both sides add up.
+        self.wide_offset += self.haystack[self.offset..range.start].len_wide();
+        self.offset = range.start;
+        Some(self.wide_offset..self.wide_offset + piece.len_wide())
+    }
+}
+
+/// An iterator over the mutable pieces of a string separated by a needle.
+///
+/// This is returned by [`OmgWtf8::split_mut`].
+///
+/// [`OmgWtf8::split_mut`]: struct.OmgWtf8.html#method.split_mut
+pub struct SplitMut<'h> {
+    rest: Option<&'h mut OmgWtf8>,
+    /// The end of each piece and the start of the next one, as positions in
+    /// the whole haystack.
+    cuts: vec::IntoIter<(EndCursor, StartCursor)>,
+    /// The position of `rest` in the whole haystack.
+    offset: usize,
+}
+
+impl<'h> Iterator for SplitMut<'h> {
+    type Item = &'h mut OmgWtf8;
+    fn next(&mut self) -> Option<&'h mut OmgWtf8> {
+        let rest = self.rest.take()?;
+        match self.cuts.next() {
+            Some((end, start)) => {
+                let end = EndCursor::new(end.pos() - self.offset);
+                let start = StartCursor::new(start.pos() - self.offset);
+                let (piece, rest) = cursor::split_mut(rest, end, start);
+                self.offset += start.pos();
+                self.rest = Some(rest);
+                Some(piece)
+            }
+            None => Some(rest),
+        }
+    }
+}
+
 #[test]
 fn test_slice_pattern_api() {
     let p = &[1, 2, 3, 4, 5, 6][..];
@@ -86,11 +574,206 @@ fn test_slice_pattern_api() {
     assert_eq!(p.find(&6), Some(5));
     assert_eq!(p.find(&10), None);
 
+    let q = &[1, 2, 1, 2][..];
+    assert_eq!(q.rfind(&1), Some(2));
+    assert_eq!(q.rfind(&2), Some(3));
+    assert_eq!(q.rfind(&3), None);
+
+    assert!(MatchExt::starts_with(q, &1));
+    assert!(!MatchExt::starts_with(q, &2));
+    assert!(MatchExt::ends_with(q, &2));
+    assert!(!MatchExt::ends_with(q, &1));
+    assert!(!MatchExt::starts_with(&[][..], &1));
+
+    assert_eq!(
+        MatchExt::replace(q, &1, &[5, 6][..]),
+        vec![5, 6, 2, 5, 6, 2]
+    );
+    assert_eq!(
+        MatchExt::join(&[0][..], vec![&q[..2], &q[2..]]),
+        vec![1, 2, 0, 1, 2]
+    );
+    assert_eq!(MatchExt::to_owned_matches(q, &2), vec![vec![2], vec![2]]);
+
+    // searching does not require the elements to be `Clone`.
+    #[derive(PartialEq)]
+    struct NoClone(u8);
+    let r = &[NoClone(1), NoClone(2)][..];
+    assert_eq!(MatchExt::find(r, &NoClone(2)), Some(1));
+
+    assert_eq!(MatchExt::find_nth(q, &2, 0), Some(1..2));
+    assert_eq!(MatchExt::find_nth(q, &2, 1), Some(3..4));
+    assert_eq!(MatchExt::find_nth(q, &2, 2), None);
+    assert_eq!(
+        MatchExt::match_ranges(q, &2).collect::<Vec<_>>(),
+        vec![(1..2, &[2][..]), (3..4, &[2][..])]
+    );
+
     let q = &[1, 2, 3, 4, 1, 2, 4, 1, 5, 4, 4, 4, 7][..];
     assert_eq!(
         MatchExt::split(q, &4).collect::<Vec<_>>(),
         vec![&[1, 2, 3][..], &[1, 2][..], &[1, 5][..], &[], &[], &[7][..]]
     );
+    assert_eq!(
+        MatchExt::split_inclusive(q, &4).collect::<Vec<_>>(),
+        vec![
+            &[1, 2, 3, 4][..],
+            &[1, 2, 4][..],
+            &[1, 5, 4][..],
+            &[4][..],
+            &[4][..],
+            &[7][..],
+        ]
+    );
+    let q = &[1, 4, 4][..];
+    assert_eq!(
+        MatchExt::split_inclusive(q, &4).collect::<Vec<_>>(),
+        vec![&[1, 4][..], &[4][..]]
+    );
+    let options = SplitOptions::new().allow_trailing_empty(false);
+    assert_eq!(
+        MatchExt::split_with(q, &4, options).collect::<Vec<_>>(),
+        vec![&[1][..], &[][..]]
+    );
+    let options = options.inclusive(true).allow_trailing_empty(true);
+    assert_eq!(
+        MatchExt::split_with(q, &4, options).collect::<Vec<_>>(),
+        vec![&[1, 4][..], &[4][..], &[][..]]
+    );
+}
+
+#[test]
+fn test_split_rev() {
+    use CharSet;
+
+    let q = &[1, 2, 3, 4, 1, 2, 4, 1, 5, 4, 4, 4, 7][..];
+    let forward = MatchExt::split(q, &4).collect::<Vec<_>>();
+    let mut backward = MatchExt::split(q, &4).rev().collect::<Vec<_>>();
+    assert_eq!(MatchExt::rsplit(q, &4).collect::<Vec<_>>(), backward);
+    backward.reverse();
+    assert_eq!(backward, forward);
+    assert_eq!(MatchExt::rsplit(q, &4).rev().collect::<Vec<_>>(), forward);
+
+    let mut pieces = MatchExt::split(q, &4);
+    assert_eq!(pieces.next(), Some(&[1, 2, 3][..]));
+    assert_eq!(pieces.next_back(), Some(&[7][..]));
+    assert_eq!(pieces.next(), Some(&[1, 2][..]));
+    assert_eq!(pieces.next_back(), Some(&[][..]));
+    assert_eq!(pieces.collect::<Vec<_>>(), vec![&[1, 5][..], &[]]);
+
+    let q = &[1, 4, 4][..];
+    assert_eq!(
+        MatchExt::split_inclusive(q, &4).rev().collect::<Vec<_>>(),
+        vec![&[4][..], &[1, 4][..]]
+    );
+    let options = SplitOptions::new().allow_trailing_empty(false);
+    assert_eq!(
+        MatchExt::split_with(q, &4, options)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![&[][..], &[1][..]]
+    );
+    assert_eq!(MatchExt::split(&[][..], &4).rev().count(), 1);
+    assert_eq!(MatchExt::split_inclusive(&[][..], &4).rev().count(), 0);
+
+    fn wide(pieces: Vec<&OmgWtf8>) -> Vec<Vec<u16>> {
+        pieces.iter().map(|s| s.encode_wide().collect()).collect()
+    }
+
+    let set = CharSet::new(&['a', '😀']);
+    let s = OmgWtf8::from_wide(&[0xde00, 0x61, 0x62, 0xd83d, 0xde00, 0x63, 0xd83d]);
+    let expected = vec![vec![0xde00], vec![0x62], vec![0x63, 0xd83d]];
+    assert_eq!(wide(s.split(&set).collect()), expected);
+    let mut backward = wide(s.split(&set).rev().collect());
+    assert_eq!(wide(s.rsplit(&set).collect()), backward);
+    backward.reverse();
+    assert_eq!(backward, expected);
+
+    // substring matches may overlap, so they are searched from the back.
+    let s = OmgWtf8::from_str("aaa");
+    assert_eq!(s.rsplit(omgwtf8!("aa")).collect::<Vec<_>>(), vec!["", "a"]);
+
+    let s = OmgWtf8::from_str("😱a😱");
+    let needle = OmgWtf8::from_wide(&[0xde31]);
+    assert_eq!(
+        wide(s.rsplit(&*needle).collect()),
+        vec![vec![], vec![0x61, 0xd83d], vec![0xd83d]]
+    );
+}
+
+#[test]
+fn test_trim_matches() {
+    use CharSet;
+
+    let q = &[4, 4, 1, 4, 2, 4, 4][..];
+    assert_eq!(MatchExt::trim_matches(q, &4), &[1, 4, 2][..]);
+    assert_eq!(MatchExt::trim_matches(&q[2..3], &4), &[1][..]);
+    assert!(MatchExt::trim_matches(&q[..2], &4).is_empty());
+    assert!(MatchExt::trim_matches(&q[..0], &4).is_empty());
+    assert_eq!(MatchExt::trim_matches(q, &5), q);
+
+    let spaces = CharSet::new(&[' ', '\t']);
+    let s = OmgWtf8::from_str(" \t a😀 b\t");
+    assert_eq!(s.trim_matches(&spaces), "a😀 b");
+    assert_eq!(OmgWtf8::from_str(" \t ").trim_matches(&spaces), "");
+
+    // halves of split surrogate pairs are never trimmed.
+    let s = OmgWtf8::from_str("😀 😀");
+    assert_eq!(s[2..7].trim_matches(&spaces), &s[2..7]);
+    assert_eq!(s[2..5].trim_matches(&spaces), &s[2..4]);
+    assert_eq!(s[4..7].trim_matches(&spaces), &s[5..7]);
+}
+
+#[test]
+fn test_trim_matches_without_rejects() {
+    use CharSet;
+
+    // trimming only needs to find matches, so it works with searchers which
+    // cannot walk through the haystack with rejects.
+    struct MatchOnly<S>(S);
+    impl<H: Haystack, S: Searcher<H>> Searcher<H> for MatchOnly<S> {
+        fn haystack(&self) -> H {
+            self.0.haystack()
+        }
+        fn next_match(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+            self.0.next_match()
+        }
+        fn next_reject(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+            panic!("next_reject called");
+        }
+    }
+    impl<H: Haystack, S: ReverseSearcher<H>> ReverseSearcher<H> for MatchOnly<S> {
+        fn next_match_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+            self.0.next_match_back()
+        }
+        fn next_reject_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+            panic!("next_reject_back called");
+        }
+    }
+    impl<H: Haystack, S: DoubleEndedSearcher<H>> DoubleEndedSearcher<H> for MatchOnly<S> {}
+
+    struct MatchOnlyPattern<P>(P);
+    impl<H: Haystack, P: Pattern<H>> Pattern<H> for MatchOnlyPattern<P> {
+        type Searcher = MatchOnly<P::Searcher>;
+        fn into_searcher(self, haystack: H) -> Self::Searcher {
+            MatchOnly(self.0.into_searcher(haystack))
+        }
+    }
+
+    let q = &[4, 4, 1, 4, 2, 4, 4][..];
+    let trim = |hs: &'static [i32]| MatchExt::trim_matches(hs, MatchOnlyPattern(&4));
+    assert_eq!(trim(q), &[1, 4, 2][..]);
+    assert_eq!(trim(&q[1..4]), &[1][..]);
+    assert_eq!(trim(&q[2..4]), &[1][..]);
+    assert_eq!(trim(&q[1..3]), &[1][..]);
+    assert_eq!(trim(&q[2..3]), &[1][..]);
+    assert!(trim(&q[..2]).is_empty());
+    assert!(trim(&q[..0]).is_empty());
+
+    let spaces = CharSet::new(&[' ', '\t']);
+    let s = OmgWtf8::from_str(" \t a😀 b\t");
+    assert_eq!(s.trim_matches(MatchOnlyPattern(&spaces)), "a😀 b");
+    assert_eq!(s[..3].trim_matches(MatchOnlyPattern(&spaces)), "");
 }
 
 #[test]
@@ -114,4 +797,189 @@ fn test_ow8_pattern_api() {
     assert_eq!(x.find(&*OmgWtf8::from_wide(&[0xde00])), Some(2));
     assert_eq!(x.find(OmgWtf8::from_str("B")), Some(9));
     assert_eq!(x.find(&*OmgWtf8::from_wide(&[0xde55])), None);
+
+    assert_eq!(x.find_nth(&*y, 0), Some(0..2));
+    assert_eq!(x.find_nth(&*y, 1), Some(5..7));
+    assert_eq!(x.find_nth(&*y, 4), Some(18..20));
+    assert_eq!(x.find_nth(&*y, 5), None);
+    let ranges = x.match_ranges(OmgWtf8::from_str("😑B")).collect::<Vec<_>>();
+    assert_eq!(ranges, vec![(5..10, &x[5..10])]);
+    let ranges = x.match_ranges(&*y).collect::<Vec<_>>();
+    assert_eq!(ranges.len(), 5);
+    assert_eq!(ranges[1], (5..7, &x[5..7]));
+    assert_eq!(*ranges[1].1, *y);
+
+    assert_eq!(x.rfind(&*y), Some(18));
+    assert_eq!(x.rfind(&*OmgWtf8::from_wide(&[0xde00])), Some(2));
+    assert_eq!(x.rfind(OmgWtf8::from_str("A")), Some(4));
+    assert_eq!(x.rfind(&*OmgWtf8::from_wide(&[0xde55])), None);
+
+    assert!(x.starts_with(&*y));
+    assert!(x.starts_with(OmgWtf8::from_str("😀A")));
+    assert!(x.starts_with(OmgWtf8::EMPTY));
+    assert!(!x.starts_with(&*OmgWtf8::from_wide(&[0xde00])));
+    assert!(x[2..].starts_with(&*OmgWtf8::from_wide(&[0xde00, 0x41])));
+    assert!(x.ends_with(OmgWtf8::from_str("😳🙄")));
+    assert!(x.ends_with(&*OmgWtf8::from_wide(&[0xde44])));
+    assert!(!x.ends_with(&*y));
+    assert!(x[..20].ends_with(&*OmgWtf8::from_wide(&[0xde33, 0xd83d])));
+    assert!(!x[..20].ends_with(x));
+
+    assert_eq!(
+        *x.replace(&*y, OmgWtf8::from_str("-")),
+        *OmgWtf8::from_wide(&[
+            0x2d, 0xde00, 0x41, 0x2d, 0xde11, 0x42, 0x2d, 0xde22, 0x2d, 0xde33, 0x2d, 0xde44
+        ])
+    );
+    let z = OmgWtf8::from_wide(&[0xd83d, 0x41, 0xde00]);
+    assert_eq!(
+        z.split_inclusive(OmgWtf8::from_str("A"))
+            .collect::<Vec<_>>(),
+        &[&z[..4], &z[4..]]
+    );
+    // removing the match may complete a surrogate pair.
+    assert_eq!(z.replace(OmgWtf8::from_str("A"), OmgWtf8::EMPTY), "😀");
+    assert_eq!(OmgWtf8::EMPTY.join(vec![&x[..2], &x[2..4]]), "😀");
+    assert_eq!(
+        *OmgWtf8::from_str("|").join(vec![&x[..2], &x[2..4]]),
+        *OmgWtf8::from_wide(&[0xd83d, 0x7c, 0xde00])
+    );
+    let matches = x.to_owned_matches(&*y);
+    assert_eq!(matches.len(), 5);
+    assert_eq!(*matches[0], *OmgWtf8::from_wide(&[0xd83d]));
+
+    // consecutive matches sharing a 4-byte sequence.
+    let x = OmgWtf8::from_str("😱😱😱");
+    let y = OmgWtf8::from_wide(&[0xde31, 0xd83d]);
+    assert_eq!(
+        x.split(&*y).collect::<Vec<_>>(),
+        &[&x[..2], OmgWtf8::EMPTY, &x[10..]]
+    );
+    assert_eq!(
+        x.split_inclusive(&*y).collect::<Vec<_>>(),
+        &[&x[..6], &x[6..10], &x[10..]]
+    );
+}
+
+#[test]
+fn test_ow8_empty_needle() {
+    // an empty needle matches at every code point boundary, like `str`.
+    let e = OmgWtf8::from_str("a😀b");
+    let ranges = e
+        .match_ranges(OmgWtf8::EMPTY)
+        .map(|(r, _)| r)
+        .collect::<Vec<_>>();
+    assert_eq!(ranges, vec![0..0, 1..1, 5..5, 6..6]);
+    assert_eq!(
+        e.split(OmgWtf8::EMPTY).collect::<Vec<_>>(),
+        &["", "a", "😀", "b", ""]
+    );
+    assert_eq!(
+        e.rsplit(OmgWtf8::EMPTY).collect::<Vec<_>>(),
+        &["", "b", "😀", "a", ""]
+    );
+    assert_eq!(e.rfind(OmgWtf8::EMPTY), Some(6));
+    assert_eq!(
+        *e.replace(OmgWtf8::EMPTY, OmgWtf8::from_str("-")),
+        *OmgWtf8::from_str("-a-😀-b-")
+    );
+
+    // split surrogate pairs at the edges count as one code point each.
+    let x = OmgWtf8::from_str("😀a😀");
+    let y = &x[2..7];
+    let ranges = y.match_ranges(OmgWtf8::EMPTY).count();
+    assert_eq!(ranges, y.chars().count() + 1);
+    assert_eq!(y.rfind(OmgWtf8::EMPTY), Some(y.len()));
+}
+
+#[test]
+fn test_ow8_find_byte_char() {
+    let s = OmgWtf8::from_str("a😀b,c\u{d7ff}😀,");
+    assert_eq!(s.find_byte(b','), Some(6));
+    assert_eq!(s.find_byte(b'd'), None);
+    assert_eq!(s.find_byte(0x9f), None);
+    assert_eq!(s.find_char(','), Some(6));
+    assert_eq!(s.find_char('\u{d7ff}'), Some(8));
+    assert_eq!(s.find_char('😀'), Some(1));
+    assert_eq!(s[3..].find_char('😀'), Some(9));
+    assert_eq!(s.find_char('😁'), None);
+    for c in &['a', 'b', ',', '\u{d7ff}', '😀', '😁'] {
+        let needle = c.to_string();
+        assert_eq!(s.find_char(*c), s.find(OmgWtf8::from_str(&needle)));
+    }
+}
+
+#[test]
+fn test_ow8_contains_wide() {
+    let s = OmgWtf8::from_str("a😀b😑");
+    assert!(s.contains_wide(&[]));
+    assert!(s.contains_wide(&[0x61]));
+    assert!(s.contains_wide(&[0xd83d, 0xde00, 0x62]));
+    assert!(s.contains_wide(&[0xde00, 0x62, 0xd83d]));
+    assert!(s.contains_wide(&[0xde11]));
+    assert!(!s.contains_wide(&[0x61, 0xde00]));
+    assert!(!s.contains_wide(&[0xde11, 0x61]));
+    assert!(s[3..].contains_wide(&[0xde00]));
+    assert!(!s[3..].contains_wide(&[0xd83d, 0xde00]));
+    assert!(!OmgWtf8::EMPTY.contains_wide(&[0x61]));
+}
+
+#[test]
+fn test_ow8_find_wide() {
+    use CharSet;
+
+    let s = OmgWtf8::from_str("😀a😑a");
+    assert_eq!(s.find_wide(omgwtf8!("a")), Some(2));
+    assert_eq!(s.find_wide(omgwtf8!("b")), None);
+    assert_eq!(s.find_wide(omgwtf8!(r"\u{de11}a")), Some(4));
+    assert_eq!(s[2..].find_wide(omgwtf8!("a")), Some(1));
+    assert_eq!(
+        s.match_ranges_wide(omgwtf8!("a")).collect::<Vec<_>>(),
+        vec![2..3, 5..6]
+    );
+    let ranges = s.match_ranges_wide(omgwtf8!(r"a\u{d83d}"));
+    assert_eq!(ranges.collect::<Vec<_>>(), vec![2..4]);
+    let set = CharSet::new(&['a', '😀', '😑']);
+    let ranges = s[2..].match_ranges_wide(&set);
+    assert_eq!(ranges.collect::<Vec<_>>(), vec![1..2, 2..4, 4..5]);
+}
+
+#[test]
+fn test_ow8_split_mut() {
+    use OmgWtf8Buf;
+
+    let mut s = String::from("ab,😀c,,d😀");
+    for (i, piece) in OmgWtf8::from_mut_str(&mut s)
+        .split_mut(omgwtf8!(","))
+        .enumerate()
+    {
+        if i != 1 {
+            piece.make_ascii_uppercase();
+        }
+    }
+    assert_eq!(s, "AB,😀c,,D😀");
+
+    let mut buf = OmgWtf8Buf::from("x😀y😀");
+    for piece in buf.split_mut(omgwtf8!("😀")) {
+        piece.make_ascii_uppercase();
+    }
+    assert_eq!(buf, "X😀Y😀");
+
+    let mut boxed = OmgWtf8::from_wide(&[0xdc00, 0x2c, 0x61, 0x2c]);
+    assert_eq!(
+        boxed
+            .split_mut(omgwtf8!(","))
+            .map(|s| &*s)
+            .collect::<Vec<_>>(),
+        &[&*OmgWtf8::from_wide(&[0xdc00]), omgwtf8!("a"), omgwtf8!("")]
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot split a surrogate pair in a mutable haystack")]
+fn test_ow8_split_mut_surrogate_pair() {
+    let mut s = String::from("a😀b");
+    OmgWtf8::from_mut_str(&mut s)
+        .split_mut(&OmgWtf8::from_wide(&[0xd83d]))
+        .count();
 }
@@ -0,0 +1,110 @@
+//! Strings with cached measurements.
+
+use std::ops::Deref;
+use {OmgWtf8, OmgWtf8Buf};
+
+/// An owned OMG-WTF-8 string which remembers its length in UTF-16 code units
+/// and whether it contains unpaired surrogates.
+///
+/// Both are computed once at construction, so querying them repeatedly, e.g.
+/// to size buffers for Windows APIs, does not rescan the bytes. The content
+/// can only be read, so the cached values never go stale.
+///
+/// ```
+/// use omgwtf8::{OmgWtf8, OmgWtf8Measured};
+///
+/// let s = OmgWtf8Measured::from(OmgWtf8::from_str("a😀"));
+/// assert_eq!(s.len_wide(), 3);
+/// assert!(!s.has_surrogates());
+/// assert_eq!(&*s, "a😀");
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+pub struct OmgWtf8Measured {
+    buf: OmgWtf8Buf,
+    len_wide: usize,
+    has_surrogates: bool,
+}
+
+impl OmgWtf8Measured {
+    /// Measures the string.
+    pub fn new(buf: OmgWtf8Buf) -> Self {
+        OmgWtf8Measured {
+            len_wide: buf.len_wide(),
+            has_surrogates: buf.has_unpaired_surrogates(),
+            buf,
+        }
+    }
+
+    /// Returns the length of this string in UTF-16 code units.
+    pub fn len_wide(&self) -> usize {
+        self.len_wide
+    }
+
+    /// Returns whether this string contains unpaired surrogates, i.e. whether
+    /// it is not valid UTF-8.
+    pub fn has_surrogates(&self) -> bool {
+        self.has_surrogates
+    }
+
+    /// Returns the string as a slice.
+    pub fn as_omgwtf8(&self) -> &OmgWtf8 {
+        &self.buf
+    }
+
+    /// Discards the measurements and returns the string.
+    pub fn into_inner(self) -> OmgWtf8Buf {
+        self.buf
+    }
+}
+
+impl Deref for OmgWtf8Measured {
+    type Target = OmgWtf8;
+    fn deref(&self) -> &OmgWtf8 {
+        &self.buf
+    }
+}
+
+impl AsRef<OmgWtf8> for OmgWtf8Measured {
+    fn as_ref(&self) -> &OmgWtf8 {
+        &self.buf
+    }
+}
+
+impl From<OmgWtf8Buf> for OmgWtf8Measured {
+    fn from(buf: OmgWtf8Buf) -> Self {
+        Self::new(buf)
+    }
+}
+
+impl<'a> From<&'a OmgWtf8> for OmgWtf8Measured {
+    fn from(s: &'a OmgWtf8) -> Self {
+        Self::new(s.to_owned())
+    }
+}
+
+impl From<OmgWtf8Measured> for OmgWtf8Buf {
+    fn from(s: OmgWtf8Measured) -> Self {
+        s.buf
+    }
+}
+
+#[test]
+fn test_measured() {
+    let s = OmgWtf8::from_str("😀a😑");
+    let m = OmgWtf8Measured::from(&s[2..]);
+    assert_eq!(m.len_wide(), 4);
+    assert!(m.has_surrogates());
+    assert_eq!(m.as_omgwtf8(), &s[2..]);
+    assert_eq!(
+        *m.into_inner(),
+        *OmgWtf8::from_wide(&[0xde00, 0x61, 0xd83d, 0xde11])
+    );
+
+    let m = OmgWtf8Measured::new(OmgWtf8Buf::from("😀a"));
+    assert_eq!(m.len_wide(), 3);
+    assert!(!m.has_surrogates());
+
+    let m = OmgWtf8Measured::default();
+    assert_eq!(m.len_wide(), 0);
+    assert!(!m.has_surrogates());
+}
@@ -0,0 +1,95 @@
+//! Java's Modified UTF-8.
+//!
+//! Modified UTF-8 (MUTF-8) is used by JNI and the class file format. Every
+//! UTF-16 code unit is encoded separately like CESU-8, so a supplementary
+//! character takes two 3-byte sequences, and U+0000 is encoded as `c0 80` so
+//! the result never contains a NUL byte.
+
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8 {
+    /// Converts this string to Modified UTF-8.
+    ///
+    /// Unpaired surrogates are encoded like any other code unit.
+    pub fn to_mutf8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        for c in self.encode_wide() {
+            match c {
+                0 => bytes.extend_from_slice(&[0xc0, 0x80]),
+                0x01..=0x7f => bytes.push(c as u8),
+                0x80..=0x7ff => {
+                    bytes.push((c >> 6 | 0xc0) as u8);
+                    bytes.push((c & 0x3f | 0x80) as u8);
+                }
+                _ => {
+                    bytes.push((c >> 12 | 0xe0) as u8);
+                    bytes.push((c >> 6 & 0x3f | 0x80) as u8);
+                    bytes.push((c & 0x3f | 0x80) as u8);
+                }
+            }
+        }
+        bytes
+    }
+}
+
+impl OmgWtf8Buf {
+    /// Converts a Modified UTF-8 byte string to OMG-WTF-8, or returns `None`
+    /// if the input is not well-formed.
+    ///
+    /// Encoded surrogate pairs are merged into supplementary characters,
+    /// while unpaired surrogates are preserved. NUL bytes, 4-byte sequences
+    /// and overlong encodings other than `c0 80` are rejected.
+    pub fn from_mutf8(bytes: &[u8]) -> Option<Self> {
+        let mut buf = OmgWtf8Buf::with_capacity(bytes.len());
+        let cont = |i: usize| match bytes.get(i) {
+            Some(&b @ 0x80..=0xbf) => Some(b as u16 & 0x3f),
+            _ => None,
+        };
+        let mut i = 0;
+        while i < bytes.len() {
+            let lead = bytes[i] as u16;
+            let (c, len) = match (lead, bytes.get(i + 1)) {
+                (0x01..=0x7f, _) => (lead, 1),
+                (0xc0, Some(&0x80)) => (0, 2),
+                (0xc2..=0xdf, _) => ((lead & 0x1f) << 6 | cont(i + 1)?, 2),
+                (0xe0, Some(&(0xa0..=0xbf))) | (0xe1..=0xef, _) => {
+                    ((lead & 0x0f) << 12 | cont(i + 1)? << 6 | cont(i + 2)?, 3)
+                }
+                _ => return None,
+            };
+            buf.push_code_unit(c);
+            i += len;
+        }
+        Some(buf)
+    }
+}
+
+#[test]
+fn test_mutf8() {
+    let s = OmgWtf8::from_str("a\0\u{e9}\u{6e2c}😀");
+    let mutf8 = s.to_mutf8();
+    assert_eq!(
+        mutf8,
+        b"a\xc0\x80\xc3\xa9\xe6\xb8\xac\xed\xa0\xbd\xed\xb8\x80"
+    );
+    let buf = OmgWtf8Buf::from_mutf8(&mutf8).unwrap();
+    assert_eq!(buf, "a\0\u{e9}\u{6e2c}😀");
+
+    let lone = OmgWtf8::from_wide(&[0xdc00, 0x41, 0xd800]);
+    assert_eq!(lone.to_mutf8(), b"\xed\xb0\x80A\xed\xa0\x80");
+    assert_eq!(
+        &*OmgWtf8Buf::from_mutf8(b"\xed\xb0\x80A\xed\xa0\x80").unwrap(),
+        &*lone
+    );
+    assert_eq!(
+        s[..9].to_mutf8(),
+        b"a\xc0\x80\xc3\xa9\xe6\xb8\xac\xed\xa0\xbd"
+    );
+
+    assert_eq!(OmgWtf8Buf::from_mutf8(b"a\0"), None);
+    assert_eq!(OmgWtf8Buf::from_mutf8(b"\xf0\x9f\x98\x80"), None);
+    assert_eq!(OmgWtf8Buf::from_mutf8(b"\xc1\x81"), None);
+    assert_eq!(OmgWtf8Buf::from_mutf8(b"\xe0\x81\x81"), None);
+    assert_eq!(OmgWtf8Buf::from_mutf8(b"\xe6\xb8"), None);
+    assert_eq!(OmgWtf8Buf::from_mutf8(b"\x80"), None);
+}
@@ -0,0 +1,52 @@
+//! Unicode normalization.
+
+use unicode_normalization::UnicodeNormalization;
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8 {
+    /// Returns the Normalization Form C (canonical composition) of this
+    /// string.
+    ///
+    /// Every run of valid Unicode scalar values is normalized separately, and
+    /// unpaired surrogates are kept unchanged, so no information is lost
+    /// besides the normalization itself.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_wide(&[0x65, 0x301, 0xdc00]);
+    /// assert_eq!(*s.nfc(), *OmgWtf8::from_wide(&[0xe9, 0xdc00]));
+    /// ```
+    pub fn nfc(&self) -> OmgWtf8Buf {
+        self.map_valid_runs(|s| s.nfc().collect())
+    }
+
+    /// Returns the Normalization Form D (canonical decomposition) of this
+    /// string.
+    ///
+    /// Like [`nfc`](#method.nfc), unpaired surrogates are kept unchanged.
+    pub fn nfd(&self) -> OmgWtf8Buf {
+        self.map_valid_runs(|s| s.nfd().collect())
+    }
+}
+
+#[test]
+fn test_nfc_nfd() {
+    let s = OmgWtf8::from_str("Cafe\u{301} \u{e9}😀");
+    assert_eq!(s.nfc(), "Caf\u{e9} \u{e9}😀");
+    assert_eq!(s.nfd(), "Cafe\u{301} e\u{301}😀");
+    assert_eq!(s.nfc(), OmgWtf8::from_str("Caf\u{e9} e\u{301}😀").nfc());
+
+    // a combining mark after an unpaired surrogate is not composed.
+    let s = OmgWtf8::from_wide(&[0xdc00, 0x301, 0x65, 0xd800, 0x301, 0xc5]);
+    assert_eq!(*s.nfc(), *s);
+    assert_eq!(
+        *s.nfd(),
+        *OmgWtf8::from_wide(&[0xdc00, 0x301, 0x65, 0xd800, 0x301, 0x41, 0x30a])
+    );
+
+    let s = OmgWtf8::from_str("😀e\u{301}😀");
+    let t = s[2..9].nfc();
+    assert!(t.is_canonical());
+    assert_eq!(*t, *OmgWtf8::from_wide(&[0xde00, 0xe9, 0xd83d]));
+}
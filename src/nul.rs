@@ -0,0 +1,83 @@
+//! Checks for interior NULs.
+
+use std::error::Error;
+use std::fmt;
+use OmgWtf8;
+
+/// An error indicating that a string passed to a NUL-terminated API contains
+/// an interior NUL.
+///
+/// This is returned by [`OmgWtf8::to_wide_nul_checked`].
+///
+/// [`OmgWtf8::to_wide_nul_checked`]: struct.OmgWtf8.html#method.to_wide_nul_checked
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NulError {
+    position: usize,
+    wide: Vec<u16>,
+}
+
+impl NulError {
+    /// Returns the position of the first NUL, in UTF-16 code units.
+    pub fn nul_position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the UTF-16 code units of the string, without the terminating
+    /// NUL.
+    pub fn into_vec(self) -> Vec<u16> {
+        self.wide
+    }
+}
+
+impl fmt::Display for NulError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "nul code unit found in provided data at position: {}",
+            self.position
+        )
+    }
+}
+
+impl Error for NulError {}
+
+impl OmgWtf8 {
+    /// Returns whether this string contains a NUL (U+0000).
+    pub fn contains_nul(&self) -> bool {
+        self.0.contains(&0)
+    }
+
+    /// Converts this string to NUL-terminated UTF-16, suitable for passing to
+    /// Windows APIs.
+    ///
+    /// Returns an error with the position of the NUL if this string contains
+    /// an interior NUL, since the API would otherwise silently truncate the
+    /// string.
+    pub fn to_wide_nul_checked(&self) -> Result<Vec<u16>, NulError> {
+        let mut wide = Vec::with_capacity(self.len() + 1);
+        wide.extend(self.encode_wide());
+        if let Some(position) = wide.iter().position(|&c| c == 0) {
+            return Err(NulError { position, wide });
+        }
+        wide.push(0);
+        Ok(wide)
+    }
+}
+
+#[test]
+fn test_nul() {
+    let s = OmgWtf8::from_str("😀a");
+    assert!(!s.contains_nul());
+    assert_eq!(s[2..].to_wide_nul_checked(), Ok(vec![0xde00, 0x61, 0]));
+
+    let s = OmgWtf8::from_str("😀\0a");
+    assert!(s.contains_nul());
+    assert!(!s[..2].contains_nul());
+    let err = s.to_wide_nul_checked().unwrap_err();
+    assert_eq!(err.nul_position(), 2);
+    assert_eq!(
+        err.to_string(),
+        "nul code unit found in provided data at position: 2"
+    );
+    assert_eq!(err.into_vec(), vec![0xd83d, 0xde00, 0, 0x61]);
+}
@@ -0,0 +1,245 @@
+//! Searching in `OsStr` on Windows.
+//!
+//! On Windows, the encoded bytes of an `OsStr` are WTF-8, which is always
+//! canonical OMG-WTF-8. An `OsStr` is therefore a haystack searched in place
+//! through its OMG-WTF-8 view, and the pieces cut from it are `&OmgWtf8`
+//! rather than `&OsStr`: an `OsStr` cannot hold half of a surrogate pair, and
+//! std does not allow cutting its encoded bytes between two surrogates.
+//!
+//! std only documents the encoded bytes as an unspecified, self-synchronizing
+//! superset of UTF-8, so the WTF-8 representation is validated when creating
+//! the view. Likewise, an `OsStr` needle is searched in an OMG-WTF-8 haystack
+//! through its view.
+
+use pattern::{EndCursor, OmgWtf8Searcher, Pattern, ReverseSearcher, Searcher, StartCursor};
+use std::ffi::OsStr;
+use OmgWtf8;
+
+impl OmgWtf8 {
+    /// Views the encoded bytes of an `OsStr` as OMG-WTF-8 without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes are not well-formed OMG-WTF-8, which is never the
+    /// case with the WTF-8 used by std on Windows.
+    pub fn from_os_str(s: &OsStr) -> &Self {
+        OmgWtf8::from_bytes(s.as_encoded_bytes())
+            .expect("the encoded bytes of an OsStr are not WTF-8")
+    }
+}
+
+impl<'a> From<&'a OsStr> for &'a OmgWtf8 {
+    fn from(s: &'a OsStr) -> Self {
+        OmgWtf8::from_os_str(s)
+    }
+}
+
+/// The needle is searched through its OMG-WTF-8 view, which finds the same
+/// matches as converting it through `OsStrExt::encode_wide`.
+impl<'p, 'h> Pattern<&'h OmgWtf8> for &'p OsStr {
+    type Searcher = OmgWtf8Searcher<'p, 'h>;
+
+    fn into_searcher(self, haystack: &'h OmgWtf8) -> OmgWtf8Searcher<'p, 'h> {
+        OmgWtf8::from_os_str(self).into_searcher(haystack)
+    }
+
+    fn is_prefix_of(self, haystack: &'h OmgWtf8) -> bool {
+        OmgWtf8::from_os_str(self).is_prefix_of(haystack)
+    }
+
+    fn is_suffix_of(self, haystack: &'h OmgWtf8) -> bool {
+        OmgWtf8::from_os_str(self).is_suffix_of(haystack)
+    }
+}
+
+/// Searcher for an OMG-WTF-8 needle in an `OsStr`.
+pub struct OsStrSearcher<'p, 'h> {
+    haystack: &'h OsStr,
+    inner: OmgWtf8Searcher<'p, 'h>,
+}
+
+impl<'p, 'h> Pattern<&'h OsStr> for &'p OmgWtf8 {
+    type Searcher = OsStrSearcher<'p, 'h>;
+
+    fn into_searcher(self, haystack: &'h OsStr) -> OsStrSearcher<'p, 'h> {
+        OsStrSearcher {
+            haystack,
+            inner: self.into_searcher(OmgWtf8::from_os_str(haystack)),
+        }
+    }
+
+    fn is_prefix_of(self, haystack: &'h OsStr) -> bool {
+        self.is_prefix_of(OmgWtf8::from_os_str(haystack))
+    }
+
+    fn is_suffix_of(self, haystack: &'h OsStr) -> bool {
+        self.is_suffix_of(OmgWtf8::from_os_str(haystack))
+    }
+}
+
+impl<'p, 'h> Pattern<&'h OsStr> for &'p OsStr {
+    type Searcher = OsStrSearcher<'p, 'h>;
+
+    fn into_searcher(self, haystack: &'h OsStr) -> OsStrSearcher<'p, 'h> {
+        OmgWtf8::from_os_str(self).into_searcher(haystack)
+    }
+
+    fn is_prefix_of(self, haystack: &'h OsStr) -> bool {
+        OmgWtf8::from_os_str(self).is_prefix_of(haystack)
+    }
+
+    fn is_suffix_of(self, haystack: &'h OsStr) -> bool {
+        OmgWtf8::from_os_str(self).is_suffix_of(haystack)
+    }
+}
+
+impl<'p, 'h> Searcher<&'h OsStr> for OsStrSearcher<'p, 'h> {
+    fn haystack(&self) -> &'h OsStr {
+        self.haystack
+    }
+
+    fn next_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        self.inner.next_match()
+    }
+
+    fn next_reject(&mut self) -> Option<(StartCursor, EndCursor)> {
+        self.inner.next_reject()
+    }
+}
+
+impl<'p, 'h> ReverseSearcher<&'h OsStr> for OsStrSearcher<'p, 'h> {
+    fn next_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        self.inner.next_match_back()
+    }
+
+    fn next_reject_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        self.inner.next_reject_back()
+    }
+}
+
+#[test]
+fn test_os_str_as_haystack() {
+    use matching::MatchExt;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    let s = OsString::from_wide(&[0x61, 0xd83d, 0xde00, 0x2f, 0xdc00, 0x2f, 0x62]);
+    let s = s.as_os_str();
+    assert_eq!(s.find(omgwtf8!("/")), Some(5));
+    assert_eq!(s.find(OsStr::new("/")), Some(5));
+    assert_eq!(s.rfind(omgwtf8!("/")), Some(9));
+    assert!(s.contains(omgwtf8!(r"\u{dc00}")));
+    assert!(s.starts_with(omgwtf8!(r"a\u{d83d}")));
+    assert!(s.ends_with(OsStr::new("b")));
+    assert_eq!(
+        s.split(OsStr::new("/")).collect::<Vec<_>>(),
+        vec![omgwtf8!("a😀"), omgwtf8!(r"\u{dc00}"), omgwtf8!("b")]
+    );
+    assert_eq!(
+        s.rsplit(omgwtf8!("/")).collect::<Vec<_>>(),
+        vec![omgwtf8!("b"), omgwtf8!(r"\u{dc00}"), omgwtf8!("a😀")]
+    );
+    assert_eq!(
+        *s.replace(omgwtf8!("/"), OmgWtf8::from_str("\\")),
+        *OmgWtf8::from_wide(&[0x61, 0xd83d, 0xde00, 0x5c, 0xdc00, 0x5c, 0x62])
+    );
+
+    // the pieces may hold halves of a surrogate pair.
+    let s = OsStr::new("a😀b");
+    assert_eq!(
+        s.split(omgwtf8!(r"\u{d83d}")).collect::<Vec<_>>(),
+        vec![omgwtf8!("a"), omgwtf8!(r"\u{de00}b")]
+    );
+    assert_eq!(
+        s.match_ranges(omgwtf8!(r"\u{de00}")).collect::<Vec<_>>(),
+        vec![(3..5, omgwtf8!(r"\u{de00}"))]
+    );
+}
+
+#[test]
+fn test_os_str_haystack() {
+    use matching::MatchExt;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    let s = OsString::from_wide(&[0x61, 0xd83d, 0xde00, 0x2f, 0xdc00, 0x2f, 0x62]);
+    let s = OmgWtf8::from_os_str(&s);
+    assert_eq!(s.find(omgwtf8!("/")), Some(5));
+    assert_eq!(s.find(omgwtf8!(r"\u{de00}")), Some(3));
+    assert_eq!(s.rfind(omgwtf8!("/")), Some(9));
+    assert!(s.contains(omgwtf8!(r"\u{dc00}")));
+    assert!(s.starts_with(omgwtf8!(r"a\u{d83d}")));
+    assert!(s.ends_with(omgwtf8!("b")));
+
+    let pieces = s.split(omgwtf8!("/")).collect::<Vec<_>>();
+    assert_eq!(
+        pieces,
+        vec![omgwtf8!("a😀"), omgwtf8!(r"\u{dc00}"), omgwtf8!("b")]
+    );
+    assert_eq!(
+        *s.replace(omgwtf8!("/"), OmgWtf8::from_str("\\")),
+        *OmgWtf8::from_wide(&[0x61, 0xd83d, 0xde00, 0x5c, 0xdc00, 0x5c, 0x62])
+    );
+}
+
+#[test]
+fn test_os_str_split_surrogates() {
+    use matching::MatchExt;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    // the pieces hold halves of a surrogate pair.
+    let s = OmgWtf8::from_os_str(OsStr::new("a😀b"));
+    assert_eq!(
+        s.split(omgwtf8!(r"\u{d83d}")).collect::<Vec<_>>(),
+        vec![omgwtf8!("a"), omgwtf8!(r"\u{de00}b")]
+    );
+
+    // the pieces are cut between two surrogates.
+    let s = OsString::from_wide(&[0xdc00, 0xdc01, 0x61]);
+    let s = <&OmgWtf8>::from(s.as_os_str());
+    assert_eq!(
+        s.split(omgwtf8!(r"\u{dc01}")).collect::<Vec<_>>(),
+        vec![omgwtf8!(r"\u{dc00}"), omgwtf8!("a")]
+    );
+}
+
+#[test]
+fn test_os_str_needle() {
+    use matching::MatchExt;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    let haystack = OmgWtf8::from_str("a😀b😀");
+    let needle = OsString::from_wide(&[0xde00, 0x62]);
+    assert_eq!(haystack.find(needle.as_os_str()), Some(3));
+    assert_eq!(haystack.rfind(OsStr::new("😀")), Some(6));
+    assert!(haystack.starts_with(OsStr::new("a")));
+    assert!(haystack[..8].ends_with(OsString::from_wide(&[0xd83d]).as_os_str()));
+    assert!(!haystack.contains(OsStr::new("c")));
+    assert_eq!(
+        haystack.split(OsStr::new("b")).collect::<Vec<_>>(),
+        vec![&haystack[..5], &haystack[6..]]
+    );
+}
+
+#[test]
+fn test_os_str_needle_matches_wide() {
+    use matching::MatchExt;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let haystack = OmgWtf8::from_wide(&[0xdc00, 0x61, 0xd83d, 0xde00, 0xdc00, 0xd800]);
+    for wide in &[&[0xdc00][..], &[0xde00, 0xdc00], &[0x61, 0xd83d], &[0xd800]] {
+        let needle = OsString::from_wide(wide);
+        let converted = OmgWtf8::from_wide(&needle.encode_wide().collect::<Vec<_>>());
+        assert_eq!(
+            haystack
+                .match_ranges(needle.as_os_str())
+                .collect::<Vec<_>>(),
+            haystack.match_ranges(&*converted).collect::<Vec<_>>(),
+            "{:?}",
+            wide
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Windows path manipulation.
+//!
+//! These functions parse Windows paths, where both `\` and `/` are
+//! separators. Since the separators and drive prefixes are all ASCII, the
+//! path is only ever split next to an ASCII byte, so a surrogate half at
+//! either end of the path is never corrupted.
+
+use OmgWtf8;
+
+fn is_separator(b: u8) -> bool {
+    b == b'\\' || b == b'/'
+}
+
+/// Returns the byte length of the component starting at `start`, i.e. the
+/// distance to the next separator or the end.
+fn component_len(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| is_separator(b))
+        .unwrap_or(bytes.len() - start)
+}
+
+fn slice(path: &OmgWtf8, start: usize, end: usize) -> &OmgWtf8 {
+    unsafe { OmgWtf8::from_bytes_unchecked(&path.0[start..end]) }
+}
+
+/// Splits the path into the prefix and the rest.
+///
+/// The prefix is a drive letter like `C:`, a UNC share like
+/// `\\server\share`, or a device or verbatim prefix like `\\?\C:` and
+/// `\\?\UNC\server\share`. If the path has no prefix, the first part is
+/// empty.
+///
+/// ```
+/// use omgwtf8::{path, OmgWtf8};
+///
+/// let (drive, rest) = path::split_drive(OmgWtf8::from_str(r"C:\Windows"));
+/// assert_eq!(drive, "C:");
+/// assert_eq!(rest, r"\Windows");
+/// let (share, rest) = path::split_drive(OmgWtf8::from_str("//server/share/file"));
+/// assert_eq!(share, "//server/share");
+/// assert_eq!(rest, "/file");
+/// ```
+pub fn split_drive(path: &OmgWtf8) -> (&OmgWtf8, &OmgWtf8) {
+    let bytes = &path.0;
+    let len = match *bytes {
+        [a, b, c, d, ..]
+            if is_separator(a)
+                && is_separator(b)
+                && (c == b'?' || c == b'.')
+                && is_separator(d) =>
+        {
+            let unc = bytes[4..].len() >= 4
+                && bytes[4..7].eq_ignore_ascii_case(b"UNC")
+                && is_separator(bytes[7]);
+            if unc {
+                unc_len(bytes, 8)
+            } else {
+                4 + component_len(bytes, 4)
+            }
+        }
+        [a, b, c, ..] if is_separator(a) && is_separator(b) && !is_separator(c) => {
+            unc_len(bytes, 2)
+        }
+        [d, b':', ..] if d.is_ascii_alphabetic() => 2,
+        _ => 0,
+    };
+    (slice(path, 0, len), slice(path, len, bytes.len()))
+}
+
+/// Returns the end of a `server\share` prefix starting at `start`.
+fn unc_len(bytes: &[u8], start: usize) -> usize {
+    let server_end = start + component_len(bytes, start);
+    if server_end == bytes.len() {
+        server_end
+    } else {
+        server_end + 1 + component_len(bytes, server_end + 1)
+    }
+}
+
+/// A component of a path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Component<'a> {
+    /// The drive or share prefix, e.g. `C:` or `\\server\share`.
+    Prefix(&'a OmgWtf8),
+    /// The root directory, i.e. the separator after the prefix.
+    RootDir,
+    /// A `.` at the start of a relative path.
+    CurDir,
+    /// A `..` component.
+    ParentDir,
+    /// A normal component, e.g. a directory or file name.
+    Normal(&'a OmgWtf8),
+}
+
+/// Returns an iterator over the components of the path.
+///
+/// Repeated separators and `.` components other than at the start of a
+/// relative path are skipped, like `std::path::Path::components`.
+///
+/// ```
+/// use omgwtf8::path::{self, Component};
+/// use omgwtf8::OmgWtf8;
+///
+/// let components = path::components(OmgWtf8::from_str(r"C:\a//..\b\."))
+///     .collect::<Vec<_>>();
+/// assert_eq!(components, vec![
+///     Component::Prefix(OmgWtf8::from_str("C:")),
+///     Component::RootDir,
+///     Component::Normal(OmgWtf8::from_str("a")),
+///     Component::ParentDir,
+///     Component::Normal(OmgWtf8::from_str("b")),
+/// ]);
+/// ```
+pub fn components(path: &OmgWtf8) -> Components<'_> {
+    let (prefix, rest) = split_drive(path);
+    let has_root = rest.0.first().is_some_and(|&b| is_separator(b));
+    Components {
+        prefix: if prefix.is_empty() {
+            None
+        } else {
+            Some(prefix)
+        },
+        has_root,
+        at_start: prefix.is_empty() && !has_root,
+        rest,
+    }
+}
+
+/// An iterator over the components of a path.
+///
+/// This is returned by [`components`].
+///
+/// [`components`]: fn.components.html
+#[derive(Clone, Debug)]
+pub struct Components<'a> {
+    prefix: Option<&'a OmgWtf8>,
+    has_root: bool,
+    at_start: bool,
+    rest: &'a OmgWtf8,
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = Component<'a>;
+    fn next(&mut self) -> Option<Component<'a>> {
+        if let Some(prefix) = self.prefix.take() {
+            return Some(Component::Prefix(prefix));
+        }
+        if self.has_root {
+            self.has_root = false;
+            return Some(Component::RootDir);
+        }
+        loop {
+            let bytes = &self.rest.0;
+            let start = bytes.iter().position(|&b| !is_separator(b))?;
+            let end = start + component_len(bytes, start);
+            let component = slice(self.rest, start, end);
+            self.rest = slice(self.rest, end, bytes.len());
+            let at_start = self.at_start;
+            self.at_start = false;
+            match component.as_bytes() {
+                b"." if at_start => return Some(Component::CurDir),
+                b"." => {}
+                b".." => return Some(Component::ParentDir),
+                _ => return Some(Component::Normal(component)),
+            }
+        }
+    }
+}
+
+/// Returns the final component of the path if it is a normal component.
+///
+/// Returns `None` if the path ends with `..` or has no normal components.
+pub fn file_name(path: &OmgWtf8) -> Option<&OmgWtf8> {
+    match components(path).last()? {
+        Component::Normal(name) => Some(name),
+        _ => None,
+    }
+}
+
+/// Splits the file name into the stem and the extension at the last `.`.
+///
+/// A leading `.` does not start an extension, so `.gitignore` has no
+/// extension.
+fn split_extension(path: &OmgWtf8) -> Option<(&OmgWtf8, Option<&OmgWtf8>)> {
+    let name = file_name(path)?;
+    let bytes = &name.0;
+    Some(match bytes.iter().rposition(|&b| b == b'.') {
+        Some(0) | None => (name, None),
+        Some(dot) => (slice(name, 0, dot), Some(slice(name, dot + 1, bytes.len()))),
+    })
+}
+
+/// Returns the file name without the extension.
+///
+/// ```
+/// use omgwtf8::{path, OmgWtf8};
+///
+/// let p = OmgWtf8::from_str(r"C:\archive.tar.gz");
+/// assert_eq!(path::file_stem(p).unwrap(), "archive.tar");
+/// assert_eq!(path::extension(p).unwrap(), "gz");
+/// ```
+pub fn file_stem(path: &OmgWtf8) -> Option<&OmgWtf8> {
+    split_extension(path).map(|(stem, _)| stem)
+}
+
+/// Returns the extension of the file name, i.e. the part after the last
+/// `.`, if any.
+pub fn extension(path: &OmgWtf8) -> Option<&OmgWtf8> {
+    split_extension(path).and_then(|(_, extension)| extension)
+}
+
+#[test]
+fn test_split_drive() {
+    fn split(path: &str) -> (&str, &str) {
+        let (prefix, rest) = split_drive(OmgWtf8::from_str(path));
+        (prefix.to_str().unwrap(), rest.to_str().unwrap())
+    }
+
+    assert_eq!(split(r"C:\a"), ("C:", r"\a"));
+    assert_eq!(split("c:a"), ("c:", "a"));
+    assert_eq!(split(r"\a"), ("", r"\a"));
+    assert_eq!(split("a:b:c"), ("a:", "b:c"));
+    assert_eq!(split("1:"), ("", "1:"));
+    assert_eq!(split(r"\\server\share\a"), (r"\\server\share", r"\a"));
+    assert_eq!(split(r"\\server"), (r"\\server", ""));
+    assert_eq!(split(r"\\\a"), ("", r"\\\a"));
+    assert_eq!(split(r"\\?\C:\a"), (r"\\?\C:", r"\a"));
+    assert_eq!(split(r"\\.\COM1"), (r"\\.\COM1", ""));
+    assert_eq!(
+        split(r"\\?\unc\server\share\a"),
+        (r"\\?\unc\server\share", r"\a")
+    );
+}
+
+#[test]
+fn test_components() {
+    use self::Component::*;
+
+    let s = OmgWtf8::from_str(r"😀\.\b\..\😑");
+    let parts = components(&s[2..14]).collect::<Vec<_>>();
+    assert_eq!(
+        parts,
+        vec![
+            Normal(&s[2..4]),
+            Normal(OmgWtf8::from_str("b")),
+            ParentDir,
+            Normal(&s[12..14]),
+        ]
+    );
+    assert_eq!(parts[0], Normal(&OmgWtf8::from_wide(&[0xde00])));
+    assert_eq!(file_name(&s[2..14]), Some(&s[12..14]));
+
+    let parts = components(OmgWtf8::from_str(r"\\server\share")).collect::<Vec<_>>();
+    assert_eq!(parts, vec![Prefix(OmgWtf8::from_str(r"\\server\share"))]);
+    assert!(components(OmgWtf8::EMPTY).next().is_none());
+    let parts = components(OmgWtf8::from_str("./.")).collect::<Vec<_>>();
+    assert_eq!(parts, vec![CurDir]);
+    assert_eq!(
+        file_name(OmgWtf8::from_str("a/b/")),
+        Some(OmgWtf8::from_str("b"))
+    );
+    assert_eq!(file_name(OmgWtf8::from_str("a/..")), None);
+    assert_eq!(file_name(OmgWtf8::from_str(r"C:\")), None);
+}
+
+#[test]
+fn test_file_stem_extension() {
+    fn parts(path: &str) -> (Option<&str>, Option<&str>) {
+        let path = OmgWtf8::from_str(path);
+        (
+            file_stem(path).map(|s| s.to_str().unwrap()),
+            extension(path).map(|s| s.to_str().unwrap()),
+        )
+    }
+
+    assert_eq!(parts("a/b.txt"), (Some("b"), Some("txt")));
+    assert_eq!(parts("b.tar.gz"), (Some("b.tar"), Some("gz")));
+    assert_eq!(parts("b."), (Some("b"), Some("")));
+    assert_eq!(parts(".gitignore"), (Some(".gitignore"), None));
+    assert_eq!(parts("a.d/b"), (Some("b"), None));
+    assert_eq!(parts("a/.."), (None, None));
+    assert_eq!(parts(""), (None, None));
+
+    let s = OmgWtf8::from_str("😀.😑");
+    assert_eq!(file_stem(&s[2..7]), Some(&s[2..4]));
+    assert_eq!(extension(&s[2..7]), Some(&s[5..7]));
+}
@@ -12,32 +12,73 @@
 //! This module extends up on “Pattern 1.5”, except:
 //!
 //! 1. The trait `SearchPtrs` is renamed to `Haystack` (similar to 2.0’s
-//!     `PatternHaystack`)
+//!    `PatternHaystack`)
 //! 2. The associated type `Cursor` is split into `StartCursor` and `EndCursor`
-//!     for extra type safety when working with OMG-WTF-8 strings.
+//!    for extra type safety when working with OMG-WTF-8 strings.
 //! 3. The associated type `Haystack` is removed, assuming it is always
-//!     `(Self::StartCursor, Self::EndCursor)`.
+//!    `(Self::StartCursor, Self::EndCursor)`.
+//! 4. Cursors are opaque offsets instead of raw pointers. Every conversion
+//!    validates the cursor against the haystack, so the `Haystack` methods
+//!    are safe to call.
 //!
-//! This module does not provide details like `next_reject` or
-//! `ReverseSearcher`. They are expected to be implemented similarly.
+//! As in 1.5, the marker trait `DoubleEndedSearcher` is implemented by
+//! searchers which find the same matches from either end, allowing splits to
+//! be iterated from both ends.
 //!
 //! [description]: https://github.com/rust-lang/rfcs/pull/1309#issuecomment-214030263
 
-use std::mem::size_of;
-use std::cmp::max;
 use std::fmt::Write;
-use std::slice::from_raw_parts;
 use OmgWtf8;
+use cursor::{byte_end_cursor, byte_start_cursor, is_split_low};
+use edge_search::{find_surrogate, rfind_surrogate, Horspool, MIN_MIDDLE_LEN};
+use memchr::memmem;
 use regex::bytes::{Regex, RegexBuilder};
 
+pub use char_set::CharSetSearcher;
+pub use cursor::{EndCursor, StartCursor};
+#[cfg(windows)]
+pub use os_str_impls::OsStrSearcher;
+
 pub trait Pattern<H: Haystack>: Sized {
     type Searcher: Searcher<H>;
 
     fn into_searcher(self, haystack: H) -> Self::Searcher;
 
-    // fn is_prefix_of(self, haystack: H) -> bool;
-    // fn is_suffix_of(self, haystack: H) -> bool;
+    /// Checks whether the pattern matches at the front of the haystack.
+    ///
+    /// The default implementation checks where the first match starts.
+    #[allow(clippy::wrong_self_convention)]
+    fn is_prefix_of(self, haystack: H) -> bool {
+        let mut searcher = self.into_searcher(haystack);
+        let haystack = searcher.haystack();
+        let front = H::start_cursor_to_offset(&haystack, H::cursor_at_front(&haystack));
+        match searcher.next_match() {
+            Some((start, _)) => H::start_cursor_to_offset(&haystack, start) == front,
+            None => false,
+        }
+    }
+
+    /// Checks whether the pattern matches at the back of the haystack.
+    ///
+    /// The default implementation checks where the last match from the front
+    /// ends. A match overlapping the previous one is never found this way, so
+    /// patterns whose matches may overlap should override it.
+    #[allow(clippy::wrong_self_convention)]
+    fn is_suffix_of(self, haystack: H) -> bool {
+        let mut searcher = self.into_searcher(haystack);
+        let haystack = searcher.haystack();
+        let back = H::end_cursor_to_offset(&haystack, H::cursor_at_back(&haystack));
+        let mut last = None;
+        while let Some((_, end)) = searcher.next_match() {
+            last = Some(end);
+        }
+        match last {
+            Some(end) => H::end_cursor_to_offset(&haystack, end) == back,
+            None => false,
+        }
+    }
 
+    #[allow(clippy::wrong_self_convention)]
     fn is_contained_in(self, haystack: H) -> bool {
         self.into_searcher(haystack).next_match().is_some()
     }
@@ -46,27 +87,146 @@ pub trait Pattern<H: Haystack>: Sized {
 pub trait Searcher<H: Haystack> {
     fn haystack(&self) -> H;
     fn next_match(&mut self) -> Option<(H::StartCursor, H::EndCursor)>;
-    // fn next_reject(&mut self) -> Option<(H::StartCursor, H::EndCursor)>;
+
+    /// Finds the next non-empty region which is not part of a match.
+    ///
+    /// Matches before the region are skipped, but the match following it is
+    /// left for the next call, so calls to `next_match` and `next_reject` can
+    /// be mixed to walk through the haystack.
+    ///
+    /// The default implementation is built on `next_match`, which cannot put
+    /// a match back. It only works on a searcher which has not been advanced:
+    /// it returns the region from the front to the first match not starting
+    /// there, and consumes that match. Searchers should override it to
+    /// support walking through the haystack.
+    fn next_reject(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        let haystack = self.haystack();
+        let offset = |cur| H::start_cursor_to_offset(&haystack, cur);
+        let mut start = H::cursor_at_front(&haystack);
+        while let Some((a, b)) = self.next_match() {
+            if offset(a) > offset(start) {
+                return Some((start, H::start_to_end_cursor(&haystack, a)));
+            }
+            start = H::end_to_start_cursor(&haystack, b);
+        }
+        let end = H::cursor_at_back(&haystack);
+        if H::end_cursor_to_offset(&haystack, end) > offset(start) {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
 }
 
+/// A searcher which can also find matches starting from the back.
+///
+/// The front and back share the same unsearched region, so a region matched
+/// from one end is never reported from the other.
+pub trait ReverseSearcher<H: Haystack>: Searcher<H> {
+    fn next_match_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)>;
+
+    /// Finds the next non-empty region from the back which is not part of a
+    /// match, the reverse of `next_reject`.
+    ///
+    /// Like `next_reject`, the default implementation is built on
+    /// `next_match_back` and only works on a searcher which has not been
+    /// advanced.
+    fn next_reject_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        let haystack = self.haystack();
+        let offset = |cur| H::end_cursor_to_offset(&haystack, cur);
+        let mut end = H::cursor_at_back(&haystack);
+        while let Some((a, b)) = self.next_match_back() {
+            if offset(b) < offset(end) {
+                return Some((H::end_to_start_cursor(&haystack, b), end));
+            }
+            end = H::start_to_end_cursor(&haystack, a);
+        }
+        let start = H::cursor_at_front(&haystack);
+        if offset(end) > H::start_cursor_to_offset(&haystack, start) {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
+}
+
+/// A reverse searcher whose matches from the back are the same as those from
+/// the front, in reverse order.
+///
+/// This holds when two matches can never overlap, e.g. when matching a
+/// single element or character. A substring searcher is not double-ended,
+/// since searching `"aa"` in `"aaa"` matches `0..2` from the front but `1..3`
+/// from the back.
+pub trait DoubleEndedSearcher<H: Haystack>: ReverseSearcher<H> {}
+
 // Haystack should be implemented for slice references: `&[T]`, `&str`,
 // `&mut [T]` etc.
 pub trait Haystack: Sized {
-    /// If the haystack is treated as an `&[T]` slice, the cursor’s type should
-    /// be `*const T`.
+    /// The slice and OMG-WTF-8 haystacks in this crate use [`StartCursor`]
+    /// and [`EndCursor`].
+    ///
+    /// [`StartCursor`]: struct.StartCursor.html
+    /// [`EndCursor`]: struct.EndCursor.html
     type StartCursor: Copy + PartialOrd<Self::EndCursor>;
     type EndCursor: Copy + PartialOrd<Self::StartCursor>;
 
+    /// The type of the pieces cut from the haystack.
+    ///
+    /// This is `Self` for every haystack in this crate except `&OsStr` on
+    /// Windows, whose pieces are `&OmgWtf8` since they may start or end with
+    /// half of a surrogate pair.
+    type Piece;
+
     fn cursor_at_front(hs: &Self) -> Self::StartCursor;
     fn cursor_at_back(hs: &Self) -> Self::EndCursor;
 
-    unsafe fn start_to_end_cursor(hs: &Self, cur: Self::StartCursor) -> Self::EndCursor;
-    unsafe fn end_to_start_cursor(hs: &Self, cur: Self::EndCursor) -> Self::StartCursor;
+    /// Converts a start cursor into an end cursor pointing at the same
+    /// position.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the cursor is not obtained from this haystack.
+    fn start_to_end_cursor(hs: &Self, cur: Self::StartCursor) -> Self::EndCursor;
+
+    /// Converts an end cursor into a start cursor pointing at the same
+    /// position.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the cursor is not obtained from this haystack.
+    fn end_to_start_cursor(hs: &Self, cur: Self::EndCursor) -> Self::StartCursor;
+
+    /// Obtains the index of a start cursor relative to the front.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the cursor is not obtained from this haystack.
+    fn start_cursor_to_offset(hs: &Self, cur: Self::StartCursor) -> usize;
+
+    /// Obtains the index of an end cursor relative to the front.
+    ///
+    /// # Panics
+    ///
+    /// May panic if the cursor is not obtained from this haystack.
+    fn end_cursor_to_offset(hs: &Self, cur: Self::EndCursor) -> usize;
 
-    unsafe fn start_cursor_to_offset(hs: &Self, cur: Self::StartCursor) -> usize;
-    unsafe fn end_cursor_to_offset(hs: &Self, cur: Self::EndCursor) -> usize;
+    /// Extracts the piece of the haystack between the two cursors.
+    ///
+    /// # Panics
+    ///
+    /// May panic if either cursor is not obtained from this haystack, or if
+    /// `start` is after `end`.
+    fn range_to_self(hs: Self, start: Self::StartCursor, end: Self::EndCursor) -> Self::Piece;
+}
+
+/// A haystack with an owned counterpart, used by algorithms producing new
+/// haystacks.
+pub trait OwnedHaystack: Haystack {
+    /// The owned counterpart of the haystack, e.g. `Vec<T>` for `&[T]`.
+    type Owned: Default;
 
-    unsafe fn range_to_self(hs: Self, start: Self::StartCursor, end: Self::EndCursor) -> Self;
+    /// Appends a piece of the haystack to an owned value.
+    fn push_to_owned(owned: &mut Self::Owned, hs: Self::Piece);
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -75,8 +235,8 @@ pub trait Haystack: Sized {
 pub struct SliceElemSearcher<'p, 'h, T: PartialEq + 'p + 'h> {
     haystack: &'h [T],
     elem: &'p T,
-    begin: *const T,
-    end: *const T,
+    begin: usize,
+    end: usize,
 }
 
 impl<'p, 'h, T: PartialEq + 'p + 'h> Searcher<&'h [T]> for SliceElemSearcher<'p, 'h, T> {
@@ -84,107 +244,119 @@ impl<'p, 'h, T: PartialEq + 'p + 'h> Searcher<&'h [T]> for SliceElemSearcher<'p,
         self.haystack
     }
 
-    fn next_match(&mut self) -> Option<(*const T, *const T)> {
-        unsafe {
-            while self.begin != self.end {
-                let cur = self.begin;
-                self.begin = cur.offset(1);
-                if *cur == *self.elem {
-                    return Some((cur, self.begin));
-                }
+    fn next_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        while self.begin != self.end {
+            let cur = self.begin;
+            self.begin += 1;
+            if self.haystack[cur] == *self.elem {
+                return Some((StartCursor::new(cur), EndCursor::new(self.begin)));
             }
-            None
         }
+        None
     }
 
-    // fn next_reject(&mut self) -> Option<(*const T, *const T)> {
-    //     unsafe {
-    //         while self.begin != self.end {
-    //             let cur = self.begin;
-    //             self.begin = cur.offset(1);
-    //             if *cur != *self.elem {
-    //                 return Some((cur, self.begin));
-    //             }
-    //         }
-    //         None
-    //     }
-    // }
-}
-
-impl<'h, T> Haystack for &'h [T] {
-    type StartCursor = *const T;
-    type EndCursor = *const T;
-
-    fn cursor_at_front(hs: &Self) -> Self::StartCursor {
-        hs.as_ptr()
-    }
-
-    fn cursor_at_back(hs: &Self) -> Self::EndCursor {
-        let ptr = hs.as_ptr();
-        if size_of::<T>() == 0 {
-            (ptr as usize + hs.len()) as *const T
+    fn next_reject(&mut self) -> Option<(StartCursor, EndCursor)> {
+        let haystack = &self.haystack[..self.end];
+        let mut cur = self.begin;
+        while cur != self.end && haystack[cur] == *self.elem {
+            cur += 1;
+        }
+        let start = cur;
+        while cur != self.end && haystack[cur] != *self.elem {
+            cur += 1;
+        }
+        self.begin = cur;
+        if start != cur {
+            Some((StartCursor::new(start), EndCursor::new(cur)))
         } else {
-            unsafe { ptr.offset(hs.len() as isize) }
+            None
         }
     }
+}
 
-    unsafe fn start_to_end_cursor(_: &Self, cur: Self::StartCursor) -> Self::EndCursor {
-        cur
-    }
-
-    unsafe fn end_to_start_cursor(_: &Self, cur: Self::EndCursor) -> Self::StartCursor {
-        cur
-    }
-
-    unsafe fn start_cursor_to_offset(hs: &Self, cur: Self::StartCursor) -> usize {
-        let size = max(size_of::<T>(), 1);
-        let ptr = hs.as_ptr();
-        (cur as usize - ptr as usize) / size
-    }
-
-    unsafe fn end_cursor_to_offset(hs: &Self, cur: Self::EndCursor) -> usize {
-        Self::start_cursor_to_offset(hs, cur)
+impl<'p, 'h, T: PartialEq + 'p + 'h> ReverseSearcher<&'h [T]> for SliceElemSearcher<'p, 'h, T> {
+    fn next_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        while self.begin != self.end {
+            let cur = self.end;
+            self.end -= 1;
+            if self.haystack[self.end] == *self.elem {
+                return Some((StartCursor::new(self.end), EndCursor::new(cur)));
+            }
+        }
+        None
     }
 
-    unsafe fn range_to_self(hs: Self, start: Self::StartCursor, end: Self::EndCursor) -> Self {
-        let start = Self::start_cursor_to_offset(&hs, start);
-        let end = Self::end_cursor_to_offset(&hs, end);
-        hs.get_unchecked(start..end)
+    fn next_reject_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        let haystack = &self.haystack[self.begin..];
+        let mut cur = self.end - self.begin;
+        while cur != 0 && haystack[cur - 1] == *self.elem {
+            cur -= 1;
+        }
+        let end = cur;
+        while cur != 0 && haystack[cur - 1] != *self.elem {
+            cur -= 1;
+        }
+        self.end = self.begin + cur;
+        if end != cur {
+            Some((StartCursor::new(self.end), EndCursor::new(self.begin + end)))
+        } else {
+            None
+        }
     }
 }
 
+impl<'p, 'h, T: PartialEq + 'p + 'h> DoubleEndedSearcher<&'h [T]> for SliceElemSearcher<'p, 'h, T> {}
+
 impl<'p, 'h, T: PartialEq + 'h + 'p> Pattern<&'h [T]> for &'p T {
     type Searcher = SliceElemSearcher<'p, 'h, T>;
 
     fn into_searcher(self, haystack: &'h [T]) -> Self::Searcher {
         SliceElemSearcher {
             haystack,
-            begin: Haystack::cursor_at_front(&haystack),
-            end: Haystack::cursor_at_back(&haystack),
+            begin: 0,
+            end: haystack.len(),
             elem: self,
         }
     }
 
-    // fn is_prefix_of(self, haystack: &'h [T]) -> bool {
-    //     haystack.first() == Some(self)
-    // }
-    // fn is_suffix_of(self, haystack: &'h [T]) -> bool {
-    //     haystack.last() == Some(self)
-    // }
+    fn is_prefix_of(self, haystack: &'h [T]) -> bool {
+        haystack.first() == Some(self)
+    }
+    fn is_suffix_of(self, haystack: &'h [T]) -> bool {
+        haystack.last() == Some(self)
+    }
 }
 
 //--------------------------------------------------------------------------------------------------
 
 /// Searcher for an OMG-WTF-8 substring
-
-pub struct OmgWtf8Searcher<'h> {
+pub struct OmgWtf8Searcher<'p, 'h> {
     haystack: &'h OmgWtf8,
-    pattern: Regex,
-    begin: *const u8,
-    end: *const u8,
+    needle: &'p OmgWtf8,
+    /// The matcher of the needle, built on the first search.
+    matcher: Option<Matcher<'p>>,
+    /// The pattern anchored at the start, compiled on the first search from
+    /// the back.
+    anchored: Option<Regex>,
+    begin: usize,
+    end: usize,
     finished: bool,
 }
 
+enum Matcher<'p> {
+    /// A non-empty needle without surrogates at its edges only matches its
+    /// exact bytes.
+    Bytes(Box<memmem::Finder<'p>>),
+    /// A needle of a single surrogate is found by scanning for the bytes
+    /// which may start it.
+    Surrogate(u16),
+    /// Otherwise, the edge surrogates may match either representation. A
+    /// long middle is searched with a skip table, and the edges are verified
+    /// at each candidate.
+    Horspool(Box<Horspool<'p>>),
+    Regex(Regex),
+}
+
 /// Derive the regex pattern from a canonicalized surrogate value
 /// (`0xa000 ..= 0xbfff`)
 fn append_regex_pattern_from_surrogate(w: &mut String, c: u16) {
@@ -212,120 +384,516 @@ fn append_regex_pattern_from_surrogate(w: &mut String, c: u16) {
     }.unwrap();
 }
 
-impl<'p, 'h> Pattern<&'h OmgWtf8> for &'p OmgWtf8 {
-    type Searcher = OmgWtf8Searcher<'h>;
+fn regex_pattern(needle: &OmgWtf8) -> String {
+    let mut pattern = String::with_capacity(needle.len() * 4);
+    let (begin, middle, end) = needle.canonicalize();
+    if begin != 0 {
+        append_regex_pattern_from_surrogate(&mut pattern, begin);
+    }
+    for byte in middle {
+        write!(&mut pattern, r"\x{:02x}", byte).unwrap();
+    }
+    if end != 0 {
+        append_regex_pattern_from_surrogate(&mut pattern, end);
+    }
+    pattern
+}
 
-    fn into_searcher(self, haystack: &'h OmgWtf8) -> OmgWtf8Searcher<'h> {
-        let mut pattern = String::with_capacity(self.len() * 4);
-        let (begin, middle, end) = self.canonicalize();
-        if begin != 0 {
-            append_regex_pattern_from_surrogate(&mut pattern, begin);
+fn build_regex(pattern: &str) -> Regex {
+    RegexBuilder::new(pattern).unicode(false).build().unwrap()
+}
+
+impl<'p> Matcher<'p> {
+    fn new(needle: &'p OmgWtf8) -> Self {
+        match needle.canonicalize() {
+            (0, middle, 0) if !middle.is_empty() => {
+                Matcher::Bytes(Box::new(memmem::Finder::new(middle)))
+            }
+            (c, middle, 0) | (0, middle, c) if middle.is_empty() && c != 0 => Matcher::Surrogate(c),
+            (begin, middle, end) if middle.len() >= MIN_MIDDLE_LEN => {
+                Matcher::Horspool(Box::new(Horspool::new(begin, middle, end)))
+            }
+            _ => Matcher::Regex(build_regex(&regex_pattern(needle))),
         }
-        for byte in middle {
-            write!(&mut pattern, r"\x{:02x}", byte).unwrap();
+    }
+
+    /// Finds the first match in `bytes`.
+    fn find(&self, bytes: &[u8]) -> Option<(usize, usize)> {
+        match *self {
+            Matcher::Bytes(ref finder) => finder
+                .find(bytes)
+                .map(|start| (start, start + finder.needle().len())),
+            Matcher::Surrogate(c) => find_surrogate(c, bytes),
+            Matcher::Horspool(ref horspool) => horspool.find(bytes),
+            Matcher::Regex(ref regex) => regex.find(bytes).map(|m| (m.start(), m.end())),
         }
-        if end != 0 {
-            append_regex_pattern_from_surrogate(&mut pattern, end);
+    }
+
+    /// Finds the last match in `bytes`.
+    ///
+    /// Matchers other than a byte or surrogate matcher walk through the
+    /// non-overlapping matches from the front. A later match can only
+    /// overlap the last of them, so only the positions inside it are checked
+    /// with the pattern anchored at the start, which is compiled into
+    /// `anchored` on the first call.
+    fn rfind(
+        &self,
+        needle: &OmgWtf8,
+        anchored: &mut Option<Regex>,
+        bytes: &[u8],
+    ) -> Option<(usize, usize)> {
+        match *self {
+            Matcher::Bytes(ref finder) => {
+                let needle = finder.needle();
+                memmem::rfind(bytes, needle).map(|start| (start, start + needle.len()))
+            }
+            Matcher::Surrogate(c) => rfind_surrogate(c, bytes),
+            _ => {
+                let mut last = None;
+                let mut pos = 0;
+                while let Some((start, end)) = self.find(&bytes[pos..]) {
+                    last = Some((pos + start, pos + end));
+                    pos += end;
+                }
+                let (start, end) = last?;
+                let anchored = anchored
+                    .get_or_insert_with(|| build_regex(&format!("^(?:{})", regex_pattern(needle))));
+                (start + 1..end)
+                    .rev()
+                    .filter_map(|pos| {
+                        let m = anchored.find(&bytes[pos..])?;
+                        Some((pos, pos + m.end()))
+                    })
+                    .next()
+                    .or(last)
+            }
+        }
+    }
+}
+
+impl<'p, 'h> OmgWtf8Searcher<'p, 'h> {
+    fn matcher(&mut self) -> &Matcher<'p> {
+        let needle = self.needle;
+        self.matcher.get_or_insert_with(|| Matcher::new(needle))
+    }
+
+    /// Matches an empty needle at the front, then steps over one code
+    /// point, like `str::match_indices("")`.
+    fn next_empty_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.finished {
+            return None;
         }
+        let pos = self.begin;
+        if pos == self.end {
+            self.finished = true;
+        } else {
+            let width = match self.haystack.0[pos] {
+                0x00..=0x7f => 1,
+                // a low surrogate in split representation at the start.
+                0x80..=0xbf => 3,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            // a high surrogate in split representation at the end is
+            // shorter than its first byte says.
+            self.begin = (pos + width).min(self.end);
+        }
+        Some((StartCursor::new(pos), EndCursor::new(pos)))
+    }
+
+    /// Matches an empty needle at the back, then steps over one code point.
+    fn next_empty_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.finished {
+            return None;
+        }
+        let pos = self.end;
+        if pos == self.begin {
+            self.finished = true;
+        } else {
+            let bytes = &self.haystack.0;
+            let mut end = pos - 1;
+            while end > self.begin && (0x80..=0xbf).contains(&bytes[end]) {
+                end -= 1;
+            }
+            self.end = end;
+        }
+        Some((StartCursor::new(pos), EndCursor::new(pos)))
+    }
+}
+
+impl<'p, 'h> Pattern<&'h OmgWtf8> for &'p OmgWtf8 {
+    type Searcher = OmgWtf8Searcher<'p, 'h>;
+
+    fn into_searcher(self, haystack: &'h OmgWtf8) -> OmgWtf8Searcher<'p, 'h> {
         OmgWtf8Searcher {
             haystack,
-            pattern: RegexBuilder::new(&pattern).unicode(false).build().unwrap(),
-            begin: Haystack::cursor_at_front(&haystack),
-            end: Haystack::cursor_at_back(&haystack),
+            needle: self,
+            matcher: None,
+            anchored: None,
+            begin: 0,
+            end: haystack.len(),
             finished: false,
         }
     }
+
+    // the needle matches at the front or back only if its code units are
+    // the first or last code units of the haystack.
+    fn is_prefix_of(self, haystack: &'h OmgWtf8) -> bool {
+        let mut units = haystack.encode_wide();
+        self.encode_wide().all(|c| units.next() == Some(c))
+    }
+    fn is_suffix_of(self, mut haystack: &'h OmgWtf8) -> bool {
+        let mut needle = self;
+        while let Some((rest, c)) = needle.split_last_code_unit() {
+            match haystack.split_last_code_unit() {
+                Some((h, d)) if c == d => haystack = h,
+                _ => return false,
+            }
+            needle = rest;
+        }
+        true
+    }
 }
 
-impl<'h> Searcher<&'h OmgWtf8> for OmgWtf8Searcher<'h> {
+impl<'p, 'h> Searcher<&'h OmgWtf8> for OmgWtf8Searcher<'p, 'h> {
     fn haystack(&self) -> &'h OmgWtf8 {
         self.haystack
     }
 
-    fn next_match(&mut self) -> Option<(*const u8, *const u8)> {
-        if self.finished {
+    /// An empty needle matches at every code point boundary.
+    fn next_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.needle.is_empty() {
+            return self.next_empty_match();
+        }
+        // every match has the same length as the needle, so a shorter
+        // region is rejected without building the matcher.
+        if self.finished || self.end - self.begin < self.needle.len() {
+            self.finished = true;
             return None;
         }
-        unsafe {
-            let slice_len = self.end as usize - self.begin as usize;
-            let slice = from_raw_parts(self.begin, slice_len);
-            match self.pattern.find(slice) {
-                None => {
-                    self.finished = true;
-                    None
+        let haystack = self.haystack;
+        let (begin, end) = (self.begin, self.end);
+        match self.matcher().find(&haystack.0[begin..end]) {
+            None => {
+                self.finished = true;
+                None
+            }
+            Some((start, end)) => {
+                let start = StartCursor::new(begin + start);
+                let end = EndCursor::new(begin + end);
+                self.begin = Haystack::end_to_start_cursor(&self.haystack, end).pos();
+                Some((start, end))
+            }
+        }
+    }
+
+    /// Returns the region between the current position and the next match
+    /// as a whole. The match itself is left for the next call. A surrogate
+    /// pair is split only when one of its halves is matched.
+    ///
+    /// An empty needle rejects the rest of the haystack at once.
+    fn next_reject(&mut self) -> Option<(StartCursor, EndCursor)> {
+        loop {
+            let begin = StartCursor::new(self.begin);
+            match self.next_match() {
+                Some((start, end)) if start == begin && end != begin => {}
+                Some((start, end)) if start != end => {
+                    self.begin = start.pos();
+                    let end = Haystack::start_to_end_cursor(&self.haystack, start);
+                    return Some((begin, end));
                 }
-                Some(m) => {
-                    let mut start = self.begin.offset(m.start() as isize);
-                    let mut end = self.begin.offset(m.end() as isize);
-                    self.begin = Haystack::end_to_start_cursor(&self.haystack, end);
-                    Some((start, end))
+                _ => {
+                    let end = EndCursor::new(self.end);
+                    self.begin = self.end;
+                    self.finished = true;
+                    return if begin < end {
+                        Some((begin, end))
+                    } else {
+                        None
+                    };
                 }
             }
         }
     }
 }
 
-impl<'h> Haystack for &'h OmgWtf8 {
-    type StartCursor = *const u8;
-    type EndCursor = *const u8;
+impl<'p, 'h> ReverseSearcher<&'h OmgWtf8> for OmgWtf8Searcher<'p, 'h> {
+    fn next_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.needle.is_empty() {
+            return self.next_empty_match_back();
+        }
+        if self.finished || self.end - self.begin < self.needle.len() {
+            self.finished = true;
+            return None;
+        }
+        let haystack = self.haystack;
+        let (begin, end) = (self.begin, self.end);
+        self.matcher();
+        // the regex only matches at positions where a valid start cursor can
+        // point.
+        let found = self.matcher.as_ref().unwrap().rfind(
+            self.needle,
+            &mut self.anchored,
+            &haystack.0[begin..end],
+        );
+        match found {
+            None => {
+                self.finished = true;
+                None
+            }
+            Some((start, end)) => {
+                let start = StartCursor::new(begin + start);
+                self.end = Haystack::start_to_end_cursor(&self.haystack, start).pos();
+                Some((start, EndCursor::new(begin + end)))
+            }
+        }
+    }
 
-    fn cursor_at_front(hs: &Self) -> Self::StartCursor {
-        hs.0.as_ptr()
+    /// Returns the region between the current position and the previous
+    /// match as a whole, leaving the match for the next call, like
+    /// `next_reject`.
+    fn next_reject_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        loop {
+            let end = EndCursor::new(self.end);
+            match self.next_match_back() {
+                Some((start, e)) if e == end && start != end => {}
+                Some((start, e)) if start != e => {
+                    self.end = e.pos();
+                    let start = Haystack::end_to_start_cursor(&self.haystack, e);
+                    return Some((start, end));
+                }
+                _ => {
+                    let start = StartCursor::new(self.begin);
+                    self.end = self.begin;
+                    self.finished = true;
+                    return if start < end {
+                        Some((start, end))
+                    } else {
+                        None
+                    };
+                }
+            }
+        }
     }
-    fn cursor_at_back(hs: &Self) -> Self::EndCursor {
-        unsafe { hs.0.as_ptr().offset(hs.0.len() as isize) }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Searcher for an OMG-WTF-8 substring in raw bytes, e.g. a memory dump or
+/// the content of a file. The bytes are not required to be well-formed.
+///
+/// A surrogate at either end of the needle matches both its canonical 3-byte
+/// sequence and the corresponding half of a 4-byte sequence, so a match may
+/// start or end in the middle of a supplementary character. Like `&OmgWtf8`,
+/// the two halves share the bytes of the 4-byte sequence, so consecutive
+/// matches may overlap, and splitting around such a match leaves the other
+/// half in split representation in the adjacent piece.
+///
+/// An empty needle matches at every byte boundary outside of complete 4-byte
+/// sequences.
+pub struct ByteSliceSearcher<'p, 'h> {
+    haystack: &'h [u8],
+    needle: &'p OmgWtf8,
+    matcher: Option<Matcher<'p>>,
+    anchored: Option<Regex>,
+    begin: usize,
+    end: usize,
+    finished: bool,
+}
+
+impl<'p, 'h> ByteSliceSearcher<'p, 'h> {
+    fn matcher(&mut self) -> &Matcher<'p> {
+        let needle = self.needle;
+        self.matcher.get_or_insert_with(|| Matcher::new(needle))
     }
 
-    unsafe fn start_to_end_cursor(hs: &Self, cur: Self::StartCursor) -> Self::EndCursor {
-        if cur != Self::cursor_at_front(hs) && 0x80 <= *cur && *cur <= 0xbf {
-            cur.offset(2)
+    /// Matches an empty needle at the front, then steps over one byte, or
+    /// over a whole 4-byte sequence.
+    fn next_empty_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.finished {
+            return None;
+        }
+        let pos = self.begin;
+        if pos == self.end {
+            self.finished = true;
+        } else if is_split_low(self.haystack, pos + 1) {
+            self.begin += 4;
         } else {
-            cur
+            self.begin += 1;
         }
+        Some((StartCursor::new(pos), EndCursor::new(pos)))
     }
 
-    unsafe fn end_to_start_cursor(hs: &Self, cur: Self::EndCursor) -> Self::StartCursor {
-        if cur != Self::cursor_at_back(hs) && 0x80 <= *cur && *cur <= 0xbf {
-            cur.offset(-2)
+    /// Matches an empty needle at the back, then steps over one byte, or
+    /// over a whole 4-byte sequence.
+    fn next_empty_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.finished {
+            return None;
+        }
+        let pos = self.end;
+        if pos == self.begin {
+            self.finished = true;
+        } else if pos >= 4 && is_split_low(self.haystack, pos - 3) {
+            self.end -= 4;
         } else {
-            cur
+            self.end -= 1;
+        }
+        Some((StartCursor::new(pos), EndCursor::new(pos)))
+    }
+}
+
+impl<'p, 'h> Pattern<&'h [u8]> for &'p OmgWtf8 {
+    type Searcher = ByteSliceSearcher<'p, 'h>;
+
+    fn into_searcher(self, haystack: &'h [u8]) -> ByteSliceSearcher<'p, 'h> {
+        ByteSliceSearcher {
+            haystack,
+            needle: self,
+            matcher: None,
+            anchored: None,
+            begin: 0,
+            end: haystack.len(),
+            finished: false,
+        }
+    }
+
+    // both representations of a surrogate take 3 bytes, so every match has
+    // the same length as the needle.
+    fn is_prefix_of(self, haystack: &'h [u8]) -> bool {
+        haystack.len() >= self.len() && Matcher::new(self).find(&haystack[..self.len()]).is_some()
+    }
+    fn is_suffix_of(self, haystack: &'h [u8]) -> bool {
+        haystack.len() >= self.len()
+            && Matcher::new(self)
+                .find(&haystack[haystack.len() - self.len()..])
+                .is_some()
+    }
+}
+
+impl<'p, 'h> Searcher<&'h [u8]> for ByteSliceSearcher<'p, 'h> {
+    fn haystack(&self) -> &'h [u8] {
+        self.haystack
+    }
+
+    fn next_match(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.needle.is_empty() {
+            return self.next_empty_match();
+        }
+        if self.finished || self.end - self.begin < self.needle.len() {
+            self.finished = true;
+            return None;
+        }
+        let haystack = self.haystack;
+        let (begin, end) = (self.begin, self.end);
+        match self.matcher().find(&haystack[begin..end]) {
+            None => {
+                self.finished = true;
+                None
+            }
+            Some((start, end)) => {
+                let start = byte_start_cursor(haystack, begin + start);
+                let end = byte_end_cursor(haystack, begin + end);
+                // overlapping matches may share a 4-byte sequence.
+                self.begin = Haystack::end_to_start_cursor(&haystack, end).pos();
+                Some((start, end))
+            }
         }
     }
 
-    unsafe fn start_cursor_to_offset(hs: &Self, cur: Self::StartCursor) -> usize {
-        let ptr = hs.0.as_ptr();
-        let mut offset = cur as usize - ptr as usize;
-        if offset != 0 && 0x80 <= *cur && *cur <= 0xbf {
-            offset += 1;
+    /// Returns the region between the current position and the next match
+    /// as a whole. The match itself is left for the next call.
+    ///
+    /// An empty needle rejects the rest of the haystack at once.
+    fn next_reject(&mut self) -> Option<(StartCursor, EndCursor)> {
+        loop {
+            let begin = byte_start_cursor(self.haystack, self.begin);
+            match self.next_match() {
+                Some((start, end)) if start == begin && end != begin => {}
+                Some((start, end)) if start != end => {
+                    self.begin = start.pos();
+                    let end = Haystack::start_to_end_cursor(&self.haystack, start);
+                    return Some((begin, end));
+                }
+                _ => {
+                    let end = byte_end_cursor(self.haystack, self.end);
+                    self.begin = self.end;
+                    self.finished = true;
+                    return if begin < end {
+                        Some((begin, end))
+                    } else {
+                        None
+                    };
+                }
+            }
         }
-        offset
     }
+}
 
-    unsafe fn end_cursor_to_offset(hs: &Self, cur: Self::EndCursor) -> usize {
-        let ptr = hs.0.as_ptr();
-        let mut offset = cur as usize - ptr as usize;
-        if offset != hs.len() && 0x80 <= *cur && *cur <= 0xbf {
-            offset -= 1;
+impl<'p, 'h> ReverseSearcher<&'h [u8]> for ByteSliceSearcher<'p, 'h> {
+    fn next_match_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        if self.needle.is_empty() {
+            return self.next_empty_match_back();
+        }
+        if self.finished || self.end - self.begin < self.needle.len() {
+            self.finished = true;
+            return None;
+        }
+        let haystack = self.haystack;
+        let (begin, end) = (self.begin, self.end);
+        self.matcher();
+        let found = self.matcher.as_ref().unwrap().rfind(
+            self.needle,
+            &mut self.anchored,
+            &haystack[begin..end],
+        );
+        match found {
+            None => {
+                self.finished = true;
+                None
+            }
+            Some((start, end)) => {
+                let start = byte_start_cursor(haystack, begin + start);
+                let end = byte_end_cursor(haystack, begin + end);
+                self.end = Haystack::start_to_end_cursor(&haystack, start).pos();
+                Some((start, end))
+            }
         }
-        offset
     }
 
-    unsafe fn range_to_self(_: Self, start: Self::StartCursor, end: Self::EndCursor) -> Self {
-        let len = end as usize - start as usize;
-        let slice = from_raw_parts(start, len);
-        &*(slice as *const [u8] as *const OmgWtf8)
+    /// Returns the region between the current position and the previous
+    /// match as a whole, leaving the match for the next call, like
+    /// `next_reject`.
+    fn next_reject_back(&mut self) -> Option<(StartCursor, EndCursor)> {
+        loop {
+            let end = byte_end_cursor(self.haystack, self.end);
+            match self.next_match_back() {
+                Some((start, e)) if e == end && start != end => {}
+                Some((start, e)) if start != e => {
+                    self.end = e.pos();
+                    let start = Haystack::end_to_start_cursor(&self.haystack, e);
+                    return Some((start, end));
+                }
+                _ => {
+                    let start = byte_start_cursor(self.haystack, self.begin);
+                    self.end = self.begin;
+                    self.finished = true;
+                    return if start < end {
+                        Some((start, end))
+                    } else {
+                        None
+                    };
+                }
+            }
+        }
     }
 }
 
 #[test]
 fn test_ow8_searcher() {
     // Tests copied from libcore.
-    fn some(hs: &OmgWtf8, start: usize, end: usize) -> Option<(*const u8, *const u8)> {
-        let ptr = hs.0.as_ptr();
-        Some((
-            ptr.wrapping_offset(start as isize),
-            ptr.wrapping_offset(end as isize),
-        ))
+    fn some(_: &OmgWtf8, start: usize, end: usize) -> Option<(StartCursor, EndCursor)> {
+        Some((StartCursor::new(start), EndCursor::new(end)))
     }
 
     let haystack = OmgWtf8::from_str("abcdeabcd");
@@ -410,3 +978,617 @@ fn test_ow8_searcher() {
     assert_eq!(searcher.next_match(), some(&haystack, 13, 16));
     assert_eq!(searcher.next_match(), None);
 }
+
+#[test]
+fn test_default_next_reject() {
+    struct MatchOnly<S>(S);
+    impl<H: Haystack, S: Searcher<H>> Searcher<H> for MatchOnly<S> {
+        fn haystack(&self) -> H {
+            self.0.haystack()
+        }
+        fn next_match(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+            self.0.next_match()
+        }
+    }
+    impl<H: Haystack, S: ReverseSearcher<H>> ReverseSearcher<H> for MatchOnly<S> {
+        fn next_match_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+            self.0.next_match_back()
+        }
+    }
+
+    let haystack = &[1, 1, 2, 3, 1, 4][..];
+    let mut searcher = MatchOnly((&1).into_searcher(haystack));
+    assert_eq!(
+        searcher.next_reject(),
+        Some((StartCursor::new(2), EndCursor::new(4)))
+    );
+    let mut searcher = MatchOnly((&1).into_searcher(&[1, 1][..]));
+    assert_eq!(searcher.next_reject(), None);
+    let mut searcher = MatchOnly((&5).into_searcher(haystack));
+    assert_eq!(
+        searcher.next_reject(),
+        Some((StartCursor::new(0), EndCursor::new(6)))
+    );
+
+    let haystack = OmgWtf8::from_str("😀a😀");
+    let needle = OmgWtf8::from_wide(&[0xde00]);
+    let mut searcher = MatchOnly((&*needle).into_searcher(haystack));
+    let (start, end) = searcher.next_reject().unwrap();
+    let reject = Haystack::range_to_self(haystack, start, end);
+    assert_eq!(*reject, *OmgWtf8::from_wide(&[0xd83d]));
+
+    let haystack = &[4, 1, 2, 3, 1, 1][..];
+    let mut searcher = MatchOnly((&1).into_searcher(haystack));
+    assert_eq!(
+        searcher.next_reject_back(),
+        Some((StartCursor::new(2), EndCursor::new(4)))
+    );
+    let mut searcher = MatchOnly((&1).into_searcher(&[1, 1][..]));
+    assert_eq!(searcher.next_reject_back(), None);
+    let mut searcher = MatchOnly((&5).into_searcher(haystack));
+    assert_eq!(
+        searcher.next_reject_back(),
+        Some((StartCursor::new(0), EndCursor::new(6)))
+    );
+
+    let haystack = OmgWtf8::from_str("😀a😀");
+    let needle = OmgWtf8::from_wide(&[0xd83d]);
+    let mut searcher = MatchOnly((&*needle).into_searcher(haystack));
+    let (start, end) = searcher.next_reject_back().unwrap();
+    let reject = Haystack::range_to_self(haystack, start, end);
+    assert_eq!(*reject, *OmgWtf8::from_wide(&[0xde00]));
+}
+
+#[test]
+fn test_default_prefix_suffix() {
+    struct SearchOnly<P>(P);
+    impl<H: Haystack, P: Pattern<H>> Pattern<H> for SearchOnly<P> {
+        type Searcher = P::Searcher;
+        fn into_searcher(self, haystack: H) -> P::Searcher {
+            self.0.into_searcher(haystack)
+        }
+    }
+
+    let haystack = &[1, 2, 1, 3][..];
+    assert!(SearchOnly(&1).is_prefix_of(haystack));
+    assert!(!SearchOnly(&2).is_prefix_of(haystack));
+    assert!(SearchOnly(&3).is_suffix_of(haystack));
+    assert!(!SearchOnly(&1).is_suffix_of(haystack));
+    assert!(!SearchOnly(&1).is_prefix_of(&[][..]));
+    assert!(!SearchOnly(&1).is_suffix_of(&[][..]));
+
+    let haystack = OmgWtf8::from_str("😀a😀");
+    let needle = OmgWtf8::from_wide(&[0xde00]);
+    assert!(SearchOnly(&*needle).is_prefix_of(&haystack[2..]));
+    assert!(SearchOnly(&*needle).is_suffix_of(haystack));
+    assert!(!SearchOnly(&*needle).is_prefix_of(haystack));
+    let needle = OmgWtf8::from_wide(&[0xd83d]);
+    assert!(SearchOnly(&*needle).is_suffix_of(&haystack[..7]));
+    assert!(!SearchOnly(&*needle).is_suffix_of(haystack));
+}
+
+#[test]
+fn test_ow8_prefix_suffix() {
+    let haystack = OmgWtf8::from_str("😀a😀");
+    let low = OmgWtf8::from_wide(&[0xde00]);
+    let high = OmgWtf8::from_wide(&[0xd83d]);
+    assert!(OmgWtf8::from_str("a😀").is_suffix_of(haystack));
+    assert!((&*low).is_suffix_of(haystack));
+    assert!((&*OmgWtf8::from_wide(&[0xde00, 0x61])).is_suffix_of(&haystack[..5]));
+    assert!((&*high).is_suffix_of(&haystack[..7]));
+    assert!(!(&*high).is_suffix_of(haystack));
+    assert!(!OmgWtf8::from_str("b😀").is_suffix_of(haystack));
+    assert!(!OmgWtf8::from_str("😀😀a😀").is_suffix_of(haystack));
+    assert!(OmgWtf8::EMPTY.is_suffix_of(OmgWtf8::EMPTY));
+    assert!((&*high).is_prefix_of(haystack));
+    assert!((&*low).is_prefix_of(&haystack[2..]));
+}
+
+#[test]
+fn test_ow8_next_reject() {
+    fn region(hs: &OmgWtf8, (start, end): (StartCursor, EndCursor)) -> &OmgWtf8 {
+        Haystack::range_to_self(hs, start, end)
+    }
+
+    let haystack = OmgWtf8::from_str("a😱b😱😱c");
+    let pattern = OmgWtf8::from_wide(&[0xd83d]);
+    let mut searcher = (&*pattern).into_searcher(haystack);
+    let rejects = ::std::iter::from_fn(|| searcher.next_reject())
+        .map(|r| region(haystack, r))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rejects,
+        vec![
+            &haystack[..1],
+            &haystack[3..6],
+            &haystack[8..10],
+            &haystack[12..],
+        ]
+    );
+
+    let mut searcher = OmgWtf8::from_str("b").into_searcher(haystack);
+    assert_eq!(
+        region(haystack, searcher.next_reject().unwrap()),
+        &haystack[..5]
+    );
+    assert_eq!(
+        region(haystack, searcher.next_reject().unwrap()),
+        &haystack[6..]
+    );
+    assert_eq!(searcher.next_reject(), None);
+    assert_eq!(searcher.next_match(), None);
+
+    let mut searcher = OmgWtf8::from_str("a").into_searcher(haystack);
+    assert_eq!(
+        searcher.next_match(),
+        Some((StartCursor::new(0), EndCursor::new(1)))
+    );
+    assert_eq!(
+        region(haystack, searcher.next_reject().unwrap()),
+        &haystack[1..]
+    );
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = OmgWtf8::from_str("a").into_searcher(&haystack[..1]);
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = OmgWtf8::from_str("a").into_searcher(OmgWtf8::from_str("xxaxxa"));
+    assert_eq!(
+        searcher.next_reject(),
+        Some((StartCursor::new(0), EndCursor::new(2)))
+    );
+    assert_eq!(
+        searcher.next_match(),
+        Some((StartCursor::new(2), EndCursor::new(3)))
+    );
+    assert_eq!(
+        searcher.next_reject(),
+        Some((StartCursor::new(3), EndCursor::new(5)))
+    );
+    assert_eq!(
+        searcher.next_match(),
+        Some((StartCursor::new(5), EndCursor::new(6)))
+    );
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = OmgWtf8::EMPTY.into_searcher(haystack);
+    assert_eq!(region(haystack, searcher.next_reject().unwrap()), haystack);
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = (&4).into_searcher(&[4, 1, 2, 4, 3][..]);
+    assert_eq!(
+        searcher.next_reject(),
+        Some((StartCursor::new(1), EndCursor::new(3)))
+    );
+    assert_eq!(
+        searcher.next_match(),
+        Some((StartCursor::new(3), EndCursor::new(4)))
+    );
+    assert_eq!(
+        searcher.next_reject(),
+        Some((StartCursor::new(4), EndCursor::new(5)))
+    );
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = (&4).into_searcher(&[4, 4][..]);
+    assert_eq!(searcher.next_reject(), None);
+    assert_eq!(searcher.next_match(), None);
+}
+
+#[test]
+fn test_ow8_next_reject_back() {
+    fn region(hs: &OmgWtf8, (start, end): (StartCursor, EndCursor)) -> &OmgWtf8 {
+        Haystack::range_to_self(hs, start, end)
+    }
+
+    let haystack = OmgWtf8::from_str("a😱b😱😱c");
+    let pattern = OmgWtf8::from_wide(&[0xd83d]);
+    let mut searcher = (&*pattern).into_searcher(haystack);
+    let rejects = ::std::iter::from_fn(|| searcher.next_reject_back())
+        .map(|r| region(haystack, r))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rejects,
+        vec![
+            &haystack[12..],
+            &haystack[8..10],
+            &haystack[3..6],
+            &haystack[..1],
+        ]
+    );
+
+    // both ends share the remaining region.
+    let mut searcher = OmgWtf8::from_str("😱").into_searcher(haystack);
+    assert_eq!(
+        region(haystack, searcher.next_reject().unwrap()),
+        &haystack[..1]
+    );
+    assert_eq!(
+        region(haystack, searcher.next_reject_back().unwrap()),
+        &haystack[14..]
+    );
+    assert_eq!(
+        region(haystack, searcher.next_reject_back().unwrap()),
+        &haystack[5..6]
+    );
+    assert_eq!(searcher.next_reject_back(), None);
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = OmgWtf8::from_str("a").into_searcher(OmgWtf8::from_str("axxaxx"));
+    assert_eq!(
+        searcher.next_reject_back(),
+        Some((StartCursor::new(4), EndCursor::new(6)))
+    );
+    assert_eq!(
+        searcher.next_match_back(),
+        Some((StartCursor::new(3), EndCursor::new(4)))
+    );
+    assert_eq!(
+        searcher.next_reject_back(),
+        Some((StartCursor::new(1), EndCursor::new(3)))
+    );
+    assert_eq!(
+        searcher.next_match_back(),
+        Some((StartCursor::new(0), EndCursor::new(1)))
+    );
+    assert_eq!(searcher.next_reject_back(), None);
+
+    let mut searcher = OmgWtf8::EMPTY.into_searcher(haystack);
+    assert_eq!(
+        region(haystack, searcher.next_reject_back().unwrap()),
+        haystack
+    );
+    assert_eq!(searcher.next_reject_back(), None);
+
+    let mut searcher = (&4).into_searcher(&[4, 1, 2, 4, 3][..]);
+    assert_eq!(
+        searcher.next_reject_back(),
+        Some((StartCursor::new(4), EndCursor::new(5)))
+    );
+    assert_eq!(
+        searcher.next_match_back(),
+        Some((StartCursor::new(3), EndCursor::new(4)))
+    );
+    assert_eq!(
+        searcher.next_reject_back(),
+        Some((StartCursor::new(1), EndCursor::new(3)))
+    );
+    assert_eq!(searcher.next_reject_back(), None);
+
+    let mut searcher = (&4).into_searcher(&[4, 4][..]);
+    assert_eq!(searcher.next_reject_back(), None);
+    assert_eq!(searcher.next_match_back(), None);
+}
+
+#[test]
+fn test_ow8_lazy_matcher() {
+    let needle = OmgWtf8::from_str("😀a");
+    let mut searcher = needle.into_searcher(OmgWtf8::from_str("a"));
+    assert_eq!(searcher.next_match(), None);
+    assert!(searcher.matcher.is_none());
+
+    let haystack = OmgWtf8::from_str("😀a😀a");
+    let mut searcher = needle.into_searcher(haystack);
+    assert!(searcher.matcher.is_none());
+    assert_eq!(
+        searcher.next_match(),
+        Some((StartCursor::new(0), EndCursor::new(5)))
+    );
+    match searcher.matcher {
+        Some(Matcher::Bytes(_)) => {}
+        _ => panic!("expected a byte matcher"),
+    }
+    assert_eq!(
+        searcher.next_match_back(),
+        Some((StartCursor::new(5), EndCursor::new(10)))
+    );
+    assert_eq!(searcher.next_match_back(), None);
+
+    let needle = &OmgWtf8::from_str("😀a")[2..];
+    let mut searcher = needle.into_searcher(haystack);
+    assert_eq!(
+        searcher.next_match(),
+        Some((StartCursor::new(1), EndCursor::new(5)))
+    );
+    match searcher.matcher {
+        Some(Matcher::Regex(_)) => {}
+        _ => panic!("expected a regex matcher"),
+    }
+}
+
+/// Finds the match starting last by trying every position.
+#[cfg(test)]
+fn rfind_by_regex(regex: &Regex, bytes: &[u8]) -> Option<(usize, usize)> {
+    (0..=bytes.len())
+        .rev()
+        .filter_map(|i| regex.find_at(bytes, i).filter(|m| m.start() == i))
+        .map(|m| (m.start(), m.end()))
+        .next()
+}
+
+#[test]
+fn test_ow8_regex_rfind() {
+    // the second needle overlaps itself inside the run of emojis.
+    let haystack = OmgWtf8::from_str("😀😀😀😀a");
+    let mut haystack = haystack.encode_wide().collect::<Vec<u16>>();
+    haystack.extend(&[0xde00, 0xd83d, 0xde00, 0xd83d, 0x62]);
+    let haystack = OmgWtf8::from_wide(&haystack);
+    for wide in &[&[0xde00, 0xd83d][..], &[0xde00, 0xd83d, 0xde00, 0xd83d]] {
+        let needle = OmgWtf8::from_wide(wide);
+        let matcher = Matcher::new(&needle);
+        assert!(matches!(matcher, Matcher::Regex(_)));
+        let regex = build_regex(&regex_pattern(&needle));
+        for region in prefixes_and_suffixes(&haystack) {
+            assert_eq!(
+                matcher.rfind(&needle, &mut None, &region.0),
+                rfind_by_regex(&regex, &region.0),
+                "{:?} {:?}",
+                needle,
+                region
+            );
+        }
+    }
+}
+
+/// Slices the haystack at every boundary, returning both halves.
+#[cfg(test)]
+fn prefixes_and_suffixes(haystack: &OmgWtf8) -> Vec<&OmgWtf8> {
+    (0..=haystack.len())
+        .filter(|&i| haystack.is_char_boundary(i))
+        .flat_map(|i| vec![&haystack[i..], &haystack[..i]])
+        .collect()
+}
+
+#[test]
+fn test_ow8_horspool() {
+    let middle = "abcdefghijklmnopqrstuvwxyz";
+    let wide = |s: &str| s.encode_utf16().collect::<Vec<u16>>();
+    let mut haystack = wide("😀abcdefghijklmnopqrstuvwxyz😀-");
+    haystack.extend(&[0xde00]);
+    haystack.extend(wide(middle));
+    haystack.extend(&[0xd83d, 0x2d, 0xd83d]);
+    haystack.extend(wide(middle));
+    haystack.extend(wide("bcdefghijklmnopqrstuvwxyz😀"));
+    let haystack = OmgWtf8::from_wide(&haystack);
+
+    let mut needles = Vec::new();
+    for &(begin, end) in &[
+        (Some(0xde00), None),
+        (None, Some(0xd83d)),
+        (Some(0xde00), Some(0xd83d)),
+    ] {
+        let mut needle = begin.into_iter().collect::<Vec<u16>>();
+        needle.extend(wide(middle));
+        needle.extend(end);
+        needles.push(OmgWtf8::from_wide(&needle));
+    }
+    let short = OmgWtf8::from_wide(&[0x61, 0x62, 0xd83d]);
+    assert!(matches!(Matcher::new(&short), Matcher::Regex(_)));
+
+    for needle in &needles {
+        let matcher = Matcher::new(needle);
+        assert!(matches!(matcher, Matcher::Horspool(_)));
+        let regex = build_regex(&regex_pattern(needle));
+        for (i, region) in prefixes_and_suffixes(&haystack).into_iter().enumerate() {
+            assert_eq!(
+                matcher.find(&region.0),
+                regex.find(&region.0).map(|m| (m.start(), m.end())),
+                "{:?} {}",
+                needle,
+                i
+            );
+            assert_eq!(
+                matcher.rfind(needle, &mut None, &region.0),
+                rfind_by_regex(&regex, &region.0),
+                "{:?} {}",
+                needle,
+                i
+            );
+        }
+        assert!(matcher.find(&haystack.0).is_some());
+    }
+}
+
+#[test]
+fn test_ow8_surrogate_matcher() {
+    let haystack = OmgWtf8::from_wide(&[
+        0xde00, 0xd83d, 0xde00, 0x61, 0xde00, 0xd83d, 0xde11, 0xd83d, 0x62, 0xd83d,
+    ]);
+    for &c in &[0xd83d, 0xde00, 0xde11, 0xd800, 0xdfff] {
+        let needle = OmgWtf8::from_wide(&[c]);
+        let c = match Matcher::new(&needle) {
+            Matcher::Surrogate(c) => c,
+            _ => panic!("expected a surrogate matcher"),
+        };
+        let regex = build_regex(&regex_pattern(&needle));
+        for region in prefixes_and_suffixes(&haystack) {
+            let bytes = &region.0;
+            let first = regex.find(bytes).map(|m| (m.start(), m.end()));
+            assert_eq!(find_surrogate(c, bytes), first);
+            assert_eq!(rfind_surrogate(c, bytes), rfind_by_regex(&regex, bytes));
+        }
+    }
+}
+
+#[test]
+fn test_byte_slice_searcher() {
+    use matching::MatchExt;
+
+    fn matches(haystack: &[u8], needle: &OmgWtf8) -> Vec<(usize, usize)> {
+        let mut searcher = needle.into_searcher(haystack);
+        let mut result = Vec::new();
+        while let Some((start, end)) = searcher.next_match() {
+            result.push((start.pos(), end.pos()));
+        }
+        result
+    }
+    fn matches_back(haystack: &[u8], needle: &OmgWtf8) -> Vec<(usize, usize)> {
+        let mut searcher = needle.into_searcher(haystack);
+        let mut result = Vec::new();
+        while let Some((start, end)) = searcher.next_match_back() {
+            result.push((start.pos(), end.pos()));
+        }
+        result.reverse();
+        result
+    }
+    fn split<'h>(haystack: &'h [u8], needle: &OmgWtf8) -> Vec<&'h [u8]> {
+        MatchExt::split(haystack, needle).collect()
+    }
+    fn rsplit<'h>(haystack: &'h [u8], needle: &OmgWtf8) -> Vec<&'h [u8]> {
+        MatchExt::rsplit(haystack, needle).collect()
+    }
+
+    // "\xff", "a", "😱", "b", "\u{d83d}", "\u{de31}", "\xff"
+    let haystack = &b"\xffa\xf0\x9f\x98\xb1b\xed\xa0\xbd\xed\xb8\xb1\xff"[..];
+    let high = omgwtf8!(r"\u{d83d}");
+    let low = omgwtf8!(r"\u{de31}");
+    for &(needle, expected) in &[
+        (omgwtf8!("b"), &[(6, 7)][..]),
+        (omgwtf8!("😱"), &[(2, 6)][..]),
+        (high, &[(2, 5), (7, 10)][..]),
+        (low, &[(3, 6), (10, 13)][..]),
+        (omgwtf8!(r"\u{de31}b\u{d83d}"), &[(3, 10)][..]),
+        (omgwtf8!("c"), &[][..]),
+    ] {
+        assert_eq!(matches(haystack, needle), expected, "{:?}", needle);
+        assert_eq!(matches_back(haystack, needle), expected, "{:?}", needle);
+    }
+
+    assert!(low.is_prefix_of(&haystack[3..]));
+    assert!(!low.is_prefix_of(&haystack[2..]));
+    assert!(high.is_suffix_of(&haystack[..5]));
+    assert!(high.is_suffix_of(&haystack[..10]));
+    assert!(!high.is_suffix_of(&haystack[..9]));
+    assert!(!high.is_suffix_of(&haystack[..1]));
+
+    // like `&OmgWtf8`, the offset of the low surrogate half is at the third
+    // byte of the 4-byte sequence.
+    assert_eq!(MatchExt::find(haystack, low), Some(4));
+    assert_eq!(MatchExt::rfind(haystack, low), Some(10));
+
+    // the pieces next to a half-surrogate match keep the other half in split
+    // representation.
+    let pieces = vec![&haystack[..2], &haystack[3..7], &haystack[10..]];
+    assert_eq!(split(haystack, high), pieces);
+    assert_eq!(
+        rsplit(haystack, high),
+        pieces.iter().rev().cloned().collect::<Vec<_>>()
+    );
+    let pieces = vec![&haystack[..5], &haystack[6..10], &haystack[13..]];
+    assert_eq!(split(haystack, low), pieces);
+    assert_eq!(
+        rsplit(haystack, low),
+        pieces.iter().rev().cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(split(&haystack[3..7], high), &[&haystack[3..7]]);
+
+    let mut searcher = high.into_searcher(haystack);
+    let reject = |start, end| {
+        Some((
+            byte_start_cursor(haystack, start),
+            byte_end_cursor(haystack, end),
+        ))
+    };
+    assert_eq!(searcher.next_reject(), reject(0, 2));
+    assert_eq!(searcher.next_reject_back(), reject(10, 14));
+    // the region after the high surrogate half starts at the low half.
+    assert_eq!(searcher.next_reject(), reject(3, 7));
+    assert_eq!(searcher.next_reject(), None);
+
+    let mut searcher = high.into_searcher(haystack);
+    assert_eq!(searcher.next_reject_back(), reject(10, 14));
+    assert_eq!(searcher.next_match_back(), reject(7, 10));
+    assert_eq!(searcher.next_reject_back(), reject(3, 7));
+    assert_eq!(searcher.next_match_back(), reject(2, 5));
+    assert_eq!(searcher.next_reject_back(), reject(0, 2));
+    assert_eq!(searcher.next_reject_back(), None);
+
+    let mut searcher = low.into_searcher(haystack);
+    assert_eq!(searcher.next_reject(), reject(0, 5));
+    assert_eq!(searcher.next_match(), reject(3, 6));
+    assert_eq!(searcher.next_reject(), reject(6, 10));
+
+    // consecutive matches sharing a 4-byte sequence.
+    let haystack = "😱😱😱".as_bytes();
+    let needle = omgwtf8!(r"\u{de31}\u{d83d}");
+    assert_eq!(matches(haystack, needle), &[(1, 7), (5, 11)]);
+    assert_eq!(matches_back(haystack, needle), &[(1, 7), (5, 11)]);
+    assert_eq!(
+        split(haystack, needle),
+        vec![&haystack[..3], &[][..], &haystack[9..]]
+    );
+    assert_eq!(
+        rsplit(haystack, needle),
+        vec![&haystack[9..], &[][..], &haystack[..3]]
+    );
+
+    // an empty needle matches at every byte boundary outside of complete
+    // 4-byte sequences.
+    let haystack = &b"a\xff\xf0\x9f"[..];
+    assert_eq!(
+        matches(haystack, OmgWtf8::EMPTY),
+        &[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]
+    );
+    assert_eq!(
+        matches_back(haystack, OmgWtf8::EMPTY),
+        matches(haystack, OmgWtf8::EMPTY)
+    );
+    assert_eq!(
+        split(haystack, OmgWtf8::EMPTY),
+        vec![&b""[..], b"a", b"\xff", b"\xf0", b"\x9f", b""]
+    );
+    assert_eq!(
+        MatchExt::match_ranges(haystack, OmgWtf8::EMPTY)
+            .map(|(range, _)| range)
+            .collect::<Vec<_>>(),
+        vec![0..0, 1..1, 2..2, 3..3, 4..4]
+    );
+    let haystack = "a😱".as_bytes();
+    assert_eq!(matches(haystack, OmgWtf8::EMPTY), &[(0, 0), (1, 1), (5, 5)]);
+    assert_eq!(
+        matches_back(haystack, OmgWtf8::EMPTY),
+        matches(haystack, OmgWtf8::EMPTY)
+    );
+    assert_eq!(
+        rsplit(haystack, OmgWtf8::EMPTY),
+        vec![&b""[..], "😱".as_bytes(), b"a", b""]
+    );
+
+    // continuation bytes after an invalid lead byte are not part of a 4-byte
+    // sequence, so they are not shared with the high surrogate half.
+    let haystack = &b"\xff\x9f\x98\xb1\xf5\x9f\x98\xb1"[..];
+    assert_eq!(matches(haystack, low), &[(1, 4), (5, 8)]);
+    assert_eq!(split(haystack, low), vec![&b"\xff"[..], b"\xf5", b""]);
+    assert_eq!(MatchExt::find(haystack, low), Some(1));
+}
+
+#[test]
+fn test_ow8_next_match_back() {
+    fn some(start: usize, end: usize) -> Option<(StartCursor, EndCursor)> {
+        Some((StartCursor::new(start), EndCursor::new(end)))
+    }
+
+    let haystack = OmgWtf8::from_str("aaa😱😱😱");
+    let mut searcher = OmgWtf8::from_str("aa").into_searcher(haystack);
+    assert_eq!(searcher.next_match_back(), some(1, 3));
+    assert_eq!(searcher.next_match_back(), None);
+
+    let pattern = OmgWtf8::from_wide(&[0xde31, 0xd83d]);
+    let mut searcher = (&*pattern).into_searcher(haystack);
+    assert_eq!(searcher.next_match_back(), some(8, 14));
+    assert_eq!(searcher.next_match_back(), some(4, 10));
+    assert_eq!(searcher.next_match_back(), None);
+
+    let pattern = OmgWtf8::from_wide(&[0xd83d]);
+    let mut searcher = (&*pattern).into_searcher(haystack);
+    assert_eq!(searcher.next_match_back(), some(11, 14));
+    assert_eq!(searcher.next_match(), some(3, 6));
+    assert_eq!(searcher.next_match_back(), some(7, 10));
+    assert_eq!(searcher.next_match(), None);
+    assert_eq!(searcher.next_match_back(), None);
+
+    let mut searcher = (&4).into_searcher(&[4, 1, 2, 4, 3][..]);
+    assert_eq!(searcher.next_match_back(), some(3, 4));
+    assert_eq!(searcher.next_match_back(), some(0, 1));
+    assert_eq!(searcher.next_match_back(), None);
+}
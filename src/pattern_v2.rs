@@ -0,0 +1,330 @@
+//! Compatibility layer with Pattern API 2.0
+//!
+//! This module reproduces the trait shapes of
+//! <https://github.com/Kimundi/rust_pattern_api_v2>, and maps the [`pattern`]
+//! module onto them, so both designs can be compared using the same
+//! OMG-WTF-8 searcher:
+//!
+//! * Every [`pattern::Haystack`] which is `Copy` is a [`PatternHaystack`],
+//!   and every [`pattern::Pattern`] is a [`Pattern`] in this module.
+//! * A [`Pattern`] of this module can be used with [`MatchExt`] by wrapping
+//!   it in [`FromV2`].
+//!
+//! The 2.0 design uses a single `Cursor` type for both ends of a match. Here
+//! a [`Cursor`] stores both the start and end cursors of the
+//! [`pattern::Haystack`] pointing at the same position. Unlike the original
+//! design, `range_to_self` is safe, since the cursors are validated.
+//!
+//! [`pattern`]: ../pattern/index.html
+//! [`pattern::Haystack`]: ../pattern/trait.Haystack.html
+//! [`pattern::Pattern`]: ../pattern/trait.Pattern.html
+//! [`PatternHaystack`]: trait.PatternHaystack.html
+//! [`Pattern`]: trait.Pattern.html
+//! [`MatchExt`]: ../trait.MatchExt.html
+//! [`FromV2`]: struct.FromV2.html
+//! [`Cursor`]: struct.Cursor.html
+
+use pattern::{self, Haystack};
+use std::cmp::Ordering;
+use std::fmt;
+
+pub trait PatternHaystack: Sized {
+    type Haystack: Copy;
+    type Cursor: Copy + PartialOrd;
+    type MatchType;
+
+    fn into_haystack(self) -> Self::Haystack;
+    fn offset_from_front(hs: Self::Haystack, cur: Self::Cursor) -> usize;
+    fn cursor_at_front(hs: Self::Haystack) -> Self::Cursor;
+    fn cursor_at_back(hs: Self::Haystack) -> Self::Cursor;
+    fn range_to_self(hs: Self::Haystack, start: Self::Cursor, end: Self::Cursor)
+        -> Self::MatchType;
+}
+
+pub trait Pattern<H: PatternHaystack>: Sized {
+    type Searcher: Searcher<H>;
+
+    fn into_searcher(self, haystack: H) -> Self::Searcher;
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_prefix_of(self, haystack: H) -> bool;
+    #[allow(clippy::wrong_self_convention)]
+    fn is_suffix_of(self, haystack: H) -> bool;
+    #[allow(clippy::wrong_self_convention)]
+    fn is_contained_in(self, haystack: H) -> bool {
+        self.into_searcher(haystack).next_match().is_some()
+    }
+}
+
+pub trait Searcher<H: PatternHaystack> {
+    fn haystack(&self) -> H::Haystack;
+    fn next_match(&mut self) -> Option<(H::Cursor, H::Cursor)>;
+    fn next_reject(&mut self) -> Option<(H::Cursor, H::Cursor)>;
+}
+
+pub trait ReverseSearcher<H: PatternHaystack>: Searcher<H> {
+    fn next_match_back(&mut self) -> Option<(H::Cursor, H::Cursor)>;
+    fn next_reject_back(&mut self) -> Option<(H::Cursor, H::Cursor)>;
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// The cursor of a haystack viewed through Pattern API 2.0.
+pub struct Cursor<H: Haystack> {
+    start: H::StartCursor,
+    end: H::EndCursor,
+    offset: usize,
+}
+
+impl<H: Haystack> Cursor<H> {
+    fn from_start(hs: &H, start: H::StartCursor) -> Self {
+        Cursor {
+            start,
+            end: H::start_to_end_cursor(hs, start),
+            offset: H::start_cursor_to_offset(hs, start),
+        }
+    }
+
+    fn from_end(hs: &H, end: H::EndCursor) -> Self {
+        Cursor {
+            start: H::end_to_start_cursor(hs, end),
+            end,
+            offset: H::end_cursor_to_offset(hs, end),
+        }
+    }
+}
+
+impl<H: Haystack> Clone for Cursor<H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: Haystack> Copy for Cursor<H> {}
+
+impl<H: Haystack> fmt::Debug for Cursor<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Cursor").field(&self.offset).finish()
+    }
+}
+
+impl<H: Haystack> PartialEq for Cursor<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+    }
+}
+
+impl<H: Haystack> PartialOrd for Cursor<H> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.offset.partial_cmp(&other.offset)
+    }
+}
+
+impl<H: Haystack + Copy> PatternHaystack for H {
+    type Haystack = H;
+    type Cursor = Cursor<H>;
+    type MatchType = H::Piece;
+
+    fn into_haystack(self) -> H {
+        self
+    }
+    fn offset_from_front(_: H, cur: Cursor<H>) -> usize {
+        cur.offset
+    }
+    fn cursor_at_front(hs: H) -> Cursor<H> {
+        Cursor::from_start(&hs, H::cursor_at_front(&hs))
+    }
+    fn cursor_at_back(hs: H) -> Cursor<H> {
+        Cursor::from_end(&hs, H::cursor_at_back(&hs))
+    }
+    fn range_to_self(hs: H, start: Cursor<H>, end: Cursor<H>) -> H::Piece {
+        H::range_to_self(hs, start.start, end.end)
+    }
+}
+
+/// A searcher of the [`pattern`](../pattern/index.html) module viewed
+/// through Pattern API 2.0.
+#[derive(Clone, Debug)]
+pub struct V2Searcher<S>(S);
+
+impl<H: Haystack + Copy, S: pattern::Searcher<H>> Searcher<H> for V2Searcher<S> {
+    fn haystack(&self) -> H {
+        self.0.haystack()
+    }
+    fn next_match(&mut self) -> Option<(Cursor<H>, Cursor<H>)> {
+        let (start, end) = self.0.next_match()?;
+        let hs = self.0.haystack();
+        Some((Cursor::from_start(&hs, start), Cursor::from_end(&hs, end)))
+    }
+    fn next_reject(&mut self) -> Option<(Cursor<H>, Cursor<H>)> {
+        let (start, end) = self.0.next_reject()?;
+        let hs = self.0.haystack();
+        Some((Cursor::from_start(&hs, start), Cursor::from_end(&hs, end)))
+    }
+}
+
+impl<H: Haystack + Copy, S: pattern::ReverseSearcher<H>> ReverseSearcher<H> for V2Searcher<S> {
+    fn next_match_back(&mut self) -> Option<(Cursor<H>, Cursor<H>)> {
+        let (start, end) = self.0.next_match_back()?;
+        let hs = self.0.haystack();
+        Some((Cursor::from_start(&hs, start), Cursor::from_end(&hs, end)))
+    }
+    fn next_reject_back(&mut self) -> Option<(Cursor<H>, Cursor<H>)> {
+        let (start, end) = self.0.next_reject_back()?;
+        let hs = self.0.haystack();
+        Some((Cursor::from_start(&hs, start), Cursor::from_end(&hs, end)))
+    }
+}
+
+impl<H: Haystack + Copy, P: pattern::Pattern<H>> Pattern<H> for P {
+    type Searcher = V2Searcher<P::Searcher>;
+
+    fn into_searcher(self, haystack: H) -> Self::Searcher {
+        V2Searcher(pattern::Pattern::into_searcher(self, haystack))
+    }
+    fn is_prefix_of(self, haystack: H) -> bool {
+        pattern::Pattern::is_prefix_of(self, haystack)
+    }
+    fn is_suffix_of(self, haystack: H) -> bool {
+        pattern::Pattern::is_suffix_of(self, haystack)
+    }
+    fn is_contained_in(self, haystack: H) -> bool {
+        pattern::Pattern::is_contained_in(self, haystack)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Wraps a Pattern API 2.0 pattern, so it can be used with the
+/// [`pattern`](../pattern/index.html) module and [`MatchExt`].
+///
+/// [`MatchExt`]: ../trait.MatchExt.html
+///
+/// ```
+/// use omgwtf8::pattern_v2::FromV2;
+/// use omgwtf8::{MatchExt, OmgWtf8};
+///
+/// let haystack = OmgWtf8::from_str("a😀b");
+/// let needle = OmgWtf8::from_wide(&[0xde00]);
+/// assert_eq!(haystack.find(FromV2(&*needle)), Some(3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FromV2<P>(pub P);
+
+/// A Pattern API 2.0 searcher viewed through the
+/// [`pattern`](../pattern/index.html) module.
+#[derive(Clone, Debug)]
+pub struct FromV2Searcher<S>(S);
+
+impl<H: Haystack + Copy, S: Searcher<H>> pattern::Searcher<H> for FromV2Searcher<S> {
+    fn haystack(&self) -> H {
+        self.0.haystack()
+    }
+    fn next_match(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        self.0
+            .next_match()
+            .map(|(start, end)| (start.start, end.end))
+    }
+    fn next_reject(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        self.0
+            .next_reject()
+            .map(|(start, end)| (start.start, end.end))
+    }
+}
+
+impl<H: Haystack + Copy, S: ReverseSearcher<H>> pattern::ReverseSearcher<H> for FromV2Searcher<S> {
+    fn next_match_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        self.0
+            .next_match_back()
+            .map(|(start, end)| (start.start, end.end))
+    }
+    fn next_reject_back(&mut self) -> Option<(H::StartCursor, H::EndCursor)> {
+        self.0
+            .next_reject_back()
+            .map(|(start, end)| (start.start, end.end))
+    }
+}
+
+impl<H: Haystack + Copy, P: Pattern<H>> pattern::Pattern<H> for FromV2<P> {
+    type Searcher = FromV2Searcher<P::Searcher>;
+
+    fn into_searcher(self, haystack: H) -> Self::Searcher {
+        FromV2Searcher(self.0.into_searcher(haystack))
+    }
+    fn is_prefix_of(self, haystack: H) -> bool {
+        self.0.is_prefix_of(haystack)
+    }
+    fn is_suffix_of(self, haystack: H) -> bool {
+        self.0.is_suffix_of(haystack)
+    }
+    fn is_contained_in(self, haystack: H) -> bool {
+        self.0.is_contained_in(haystack)
+    }
+}
+
+#[test]
+fn test_v2_searcher() {
+    use OmgWtf8;
+    type H<'a> = &'a OmgWtf8;
+
+    let haystack = OmgWtf8::from_str("😱a😱");
+    let needle = OmgWtf8::from_wide(&[0xde31]);
+    let mut searcher = Pattern::into_searcher(&*needle, haystack);
+    let (start, end) = Searcher::next_match(&mut searcher).unwrap();
+    assert_eq!(
+        <H as PatternHaystack>::offset_from_front(haystack, start),
+        2
+    );
+    assert_eq!(<H as PatternHaystack>::offset_from_front(haystack, end), 4);
+    assert_eq!(
+        <H as PatternHaystack>::range_to_self(haystack, start, end),
+        &haystack[2..4]
+    );
+    let (start, end) = Searcher::next_reject(&mut searcher).unwrap();
+    assert_eq!(
+        <H as PatternHaystack>::range_to_self(haystack, start, end),
+        &haystack[4..7]
+    );
+    let (start, _) = ReverseSearcher::next_match_back(&mut searcher).unwrap();
+    assert_eq!(
+        <H as PatternHaystack>::offset_from_front(haystack, start),
+        7
+    );
+    assert_eq!(ReverseSearcher::next_match_back(&mut searcher), None);
+
+    let mut searcher = Pattern::into_searcher(&*needle, haystack);
+    let (start, _) = ReverseSearcher::next_match_back(&mut searcher).unwrap();
+    assert_eq!(
+        <H as PatternHaystack>::offset_from_front(haystack, start),
+        7
+    );
+    let (start, _) = ReverseSearcher::next_match_back(&mut searcher).unwrap();
+    assert_eq!(
+        <H as PatternHaystack>::offset_from_front(haystack, start),
+        2
+    );
+    assert_eq!(Searcher::next_match(&mut searcher), None);
+
+    let front = <H as PatternHaystack>::cursor_at_front(&haystack[2..]);
+    let back = <H as PatternHaystack>::cursor_at_back(&haystack[2..]);
+    assert!(front < back);
+    assert!(Pattern::is_prefix_of(&*needle, &haystack[2..]));
+    assert!(Pattern::is_suffix_of(&*needle, haystack));
+    assert!(!Pattern::is_prefix_of(&*needle, haystack));
+}
+
+#[test]
+fn test_from_v2() {
+    use {MatchExt, OmgWtf8};
+
+    let haystack = OmgWtf8::from_str("😱a😱");
+    let needle = OmgWtf8::from_wide(&[0xd83d]);
+    assert_eq!(
+        haystack.split(FromV2(&*needle)).collect::<Vec<_>>(),
+        haystack.split(&*needle).collect::<Vec<_>>()
+    );
+    assert_eq!(haystack.rfind(FromV2(&*needle)), Some(5));
+    assert!(haystack.starts_with(FromV2(&*needle)));
+    assert!(!haystack.ends_with(FromV2(&*needle)));
+    assert_eq!(MatchExt::find(&[1, 2, 3][..], FromV2(&3)), Some(2));
+}
@@ -0,0 +1,162 @@
+//! Percent-encoding.
+
+use std::fmt::Write;
+use {OmgWtf8, OmgWtf8Buf};
+
+/// A set of ASCII bytes to percent-encode.
+///
+/// Non-ASCII bytes are always percent-encoded, so only ASCII bytes are
+/// configurable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AsciiSet {
+    mask: u128,
+}
+
+impl AsciiSet {
+    /// The set containing no ASCII bytes.
+    pub const EMPTY: AsciiSet = AsciiSet { mask: 0 };
+
+    /// The C0 control characters U+0000 to U+001F and U+007F.
+    pub const CONTROLS: AsciiSet = AsciiSet {
+        mask: 0xffff_ffff | 1 << 0x7f,
+    };
+
+    /// Every ASCII byte other than letters and digits.
+    pub const NON_ALPHANUMERIC: AsciiSet = AsciiSet {
+        mask: !(0x3ff << b'0' | 0x3ff_ffff << b'A' | 0x3ff_ffff << b'a'),
+    };
+
+    /// The bytes to encode in the path of a `file://` URL, i.e. the path
+    /// percent-encode set of the WHATWG URL standard plus `%`.
+    pub const PATH: AsciiSet = AsciiSet::CONTROLS
+        .add(b' ')
+        .add(b'"')
+        .add(b'#')
+        .add(b'%')
+        .add(b'<')
+        .add(b'>')
+        .add(b'?')
+        .add(b'`')
+        .add(b'{')
+        .add(b'}');
+
+    /// Returns a new set which also contains the given ASCII byte.
+    ///
+    /// Non-ASCII bytes are ignored, since they are always encoded.
+    pub const fn add(self, byte: u8) -> AsciiSet {
+        if byte >= 0x80 {
+            return self;
+        }
+        AsciiSet {
+            mask: self.mask | 1 << byte,
+        }
+    }
+
+    /// Returns a new set which does not contain the given ASCII byte.
+    ///
+    /// Non-ASCII bytes are ignored, since they are always encoded.
+    pub const fn remove(self, byte: u8) -> AsciiSet {
+        if byte >= 0x80 {
+            return self;
+        }
+        AsciiSet {
+            mask: self.mask & !(1 << byte),
+        }
+    }
+
+    /// Returns whether the byte should be percent-encoded under this set.
+    ///
+    /// This is always true for non-ASCII bytes.
+    pub fn contains(&self, byte: u8) -> bool {
+        byte >= 0x80 || self.mask & 1 << byte != 0
+    }
+}
+
+impl OmgWtf8 {
+    /// Percent-encodes this string, encoding every byte in the set and every
+    /// non-ASCII byte as `%XX`.
+    ///
+    /// The bytes are taken from the canonical representation, so unpaired
+    /// surrogates are encoded as 3-byte `ed xx xx` sequences as in WTF-8.
+    pub fn to_percent_encoded(&self, set: &AsciiSet) -> String {
+        let bytes = self.to_wtf8();
+        let mut encoded = String::with_capacity(bytes.len());
+        for &b in &*bytes {
+            if set.contains(b) {
+                write!(encoded, "%{:02X}", b).unwrap();
+            } else {
+                encoded.push(b as char);
+            }
+        }
+        encoded
+    }
+}
+
+impl OmgWtf8Buf {
+    /// Decodes a percent-encoded string, returning `None` if the decoded
+    /// bytes are not well-formed OMG-WTF-8.
+    ///
+    /// A `%` not followed by two hex digits is kept as is.
+    pub fn from_percent_encoded(s: &str) -> Option<Self> {
+        fn hex(b: u8) -> Option<u8> {
+            (b as char).to_digit(16).map(|d| d as u8)
+        }
+
+        let bytes = s.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if let [b'%', hi, lo, ..] = bytes[i..] {
+                if let (Some(hi), Some(lo)) = (hex(hi), hex(lo)) {
+                    decoded.push(hi << 4 | lo);
+                    i += 3;
+                    continue;
+                }
+            }
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+        OmgWtf8::from_bytes(&decoded).ok().map(OmgWtf8Buf::from)
+    }
+}
+
+#[test]
+fn test_ascii_set() {
+    assert!(AsciiSet::CONTROLS.contains(0));
+    assert!(AsciiSet::CONTROLS.contains(0x7f));
+    assert!(!AsciiSet::CONTROLS.contains(b' '));
+    assert!(AsciiSet::NON_ALPHANUMERIC.contains(b'-'));
+    assert!(!AsciiSet::NON_ALPHANUMERIC.contains(b'z'));
+    assert!(!AsciiSet::NON_ALPHANUMERIC.contains(b'0'));
+    assert!(AsciiSet::PATH.contains(b'%'));
+    assert!(!AsciiSet::PATH.contains(b'/'));
+    assert!(AsciiSet::EMPTY.contains(0x80));
+    assert!(AsciiSet::EMPTY.add(b'a').contains(b'a'));
+    assert!(!AsciiSet::PATH.remove(b' ').contains(b' '));
+    assert_eq!(AsciiSet::PATH.add(0x80), AsciiSet::PATH);
+    assert_eq!(AsciiSet::PATH.add(0xff), AsciiSet::PATH);
+    assert_eq!(AsciiSet::PATH.remove(0xe9), AsciiSet::PATH);
+    assert!(AsciiSet::EMPTY.remove(0xe9).contains(0xe9));
+}
+
+#[test]
+fn test_percent_encoding() {
+    let s = OmgWtf8::from_str("C:/a b/%😀");
+    assert_eq!(
+        s.to_percent_encoded(&AsciiSet::PATH),
+        "C:/a%20b/%25%F0%9F%98%80"
+    );
+    assert_eq!(
+        s[..10].to_percent_encoded(&AsciiSet::EMPTY),
+        "C:/a b/%%ED%A0%BD"
+    );
+    let lone = OmgWtf8::from_wide(&[0x2f, 0xdc00]);
+    let encoded = lone.to_percent_encoded(&AsciiSet::PATH);
+    assert_eq!(encoded, "/%ED%B0%80");
+    assert_eq!(*OmgWtf8Buf::from_percent_encoded(&encoded).unwrap(), *lone);
+
+    let decoded = OmgWtf8Buf::from_percent_encoded("%f0%9F%98%80%2x%").unwrap();
+    assert_eq!(decoded, "😀%2x%");
+    assert_eq!(OmgWtf8Buf::from_percent_encoded("%FF"), None);
+    assert_eq!(OmgWtf8Buf::from_percent_encoded("%ED%A0%BD%ED%B8%80"), None);
+}
@@ -0,0 +1,115 @@
+//! Portable binary serialization.
+//!
+//! The portable format is a 2-byte header followed by the canonical (WTF-8)
+//! bytes of the string:
+//!
+//! * The first byte is the format version, currently `1`.
+//! * The second byte is a set of flags. Bit 0 is set if the string starts
+//!   with an unpaired surrogate, and bit 1 is set if it ends with one. The
+//!   other bits are reserved and must be zero.
+//!
+//! The flags are redundant with the content, and are checked when decoding.
+//! They let readers tell whether a string may join with its neighbors
+//! without decoding it.
+
+use code_point::CodePoint;
+use OmgWtf8;
+
+/// The version of the portable format.
+const VERSION: u8 = 1;
+/// Flag of a string starting with an unpaired surrogate.
+const STARTS_WITH_SURROGATE: u8 = 1;
+/// Flag of a string ending with an unpaired surrogate.
+const ENDS_WITH_SURROGATE: u8 = 2;
+
+impl OmgWtf8 {
+    fn edge_surrogate_flags(&self) -> u8 {
+        let is_surrogate =
+            |c: Option<(CodePoint, usize)>| matches!(c, Some((c, _)) if c.is_surrogate());
+        let mut flags = 0;
+        if is_surrogate(self.first_code_point()) {
+            flags |= STARTS_WITH_SURROGATE;
+        }
+        if is_surrogate(self.last_code_point()) {
+            flags |= ENDS_WITH_SURROGATE;
+        }
+        flags
+    }
+
+    /// Serializes this string in the portable format.
+    ///
+    /// The output is independent of the representation of the surrogates at
+    /// the edges, so equal strings always produce the same bytes.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("😀a");
+    /// let bytes = s[2..].to_portable_bytes();
+    /// assert_eq!(bytes, b"\x01\x01\xed\xb8\x80a");
+    /// assert_eq!(OmgWtf8::from_portable_bytes(&bytes), Some(&s[2..]));
+    /// ```
+    pub fn to_portable_bytes(&self) -> Vec<u8> {
+        let wtf8 = self.to_wtf8();
+        let mut bytes = Vec::with_capacity(wtf8.len() + 2);
+        bytes.push(VERSION);
+        bytes.push(self.edge_surrogate_flags());
+        bytes.extend_from_slice(&wtf8);
+        bytes
+    }
+
+    /// Deserializes a string in the portable format, returning `None` if the
+    /// header is unknown, the content is not well-formed WTF-8, or the flags
+    /// do not match the content.
+    ///
+    /// The result borrows the content from the input.
+    pub fn from_portable_bytes(bytes: &[u8]) -> Option<&Self> {
+        match *bytes {
+            [VERSION, flags, ref content @ ..] => {
+                let s = Self::from_wtf8(content)?;
+                if s.edge_surrogate_flags() == flags {
+                    Some(s)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_portable_bytes() {
+    let s = OmgWtf8::from_str("😀a😑");
+    for (s, flags) in &[
+        (s, 0),
+        (&s[2..], 1),
+        (&s[..7], 2),
+        (&s[2..7], 3),
+        (OmgWtf8::EMPTY, 0),
+    ] {
+        let bytes = s.to_portable_bytes();
+        assert_eq!(bytes[..2], [VERSION, *flags]);
+        assert_eq!(bytes[2..], *s.to_wtf8());
+        assert_eq!(OmgWtf8::from_portable_bytes(&bytes), Some(*s));
+    }
+
+    let split = &s[2..7];
+    let canonical = split.to_owned();
+    assert_eq!(split.to_portable_bytes(), canonical.to_portable_bytes());
+}
+
+#[test]
+fn test_portable_bytes_invalid() {
+    assert_eq!(OmgWtf8::from_portable_bytes(b""), None);
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x01"), None);
+    assert!(OmgWtf8::from_portable_bytes(b"\x01\x00").is_some());
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x02\x00a"), None);
+    // flags not matching the content.
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x01\x01a"), None);
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x01\x04a"), None);
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x01\x00\xed\xa0\x80"), None);
+    // split surrogates are not canonical.
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x01\x01\x98\x80a"), None);
+    assert_eq!(OmgWtf8::from_portable_bytes(b"\x01\x00\xff"), None);
+}
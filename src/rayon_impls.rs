@@ -0,0 +1,190 @@
+//! Parallel searching with `rayon`.
+
+use pattern::{EndCursor, Haystack, Pattern, Searcher, StartCursor};
+use rayon::prelude::*;
+use rayon::vec::IntoIter;
+use validate::is_continuation_byte;
+use OmgWtf8;
+
+/// Haystacks shorter than this are not split further.
+const MIN_CHUNK_LEN: usize = 64 * 1024;
+
+/// Moves `pos` forward to the next byte which starts a character, so the
+/// haystack is never partitioned inside a 4-byte sequence.
+fn safe_boundary(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && is_continuation_byte(bytes[pos]) {
+        pos += 1;
+    }
+    pos.min(bytes.len())
+}
+
+/// Finds the non-overlapping matches in `haystack.0[from..to]` whose start is
+/// before `limit`, as byte positions relative to the whole haystack.
+///
+/// `from` and `to` must be valid byte positions to slice the haystack.
+fn search(
+    haystack: &OmgWtf8,
+    needle: &OmgWtf8,
+    from: usize,
+    to: usize,
+    limit: usize,
+) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    if from >= limit {
+        return matches;
+    }
+    let chunk = unsafe { OmgWtf8::from_bytes_unchecked(&haystack.0[from..to]) };
+    let mut searcher = needle.into_searcher(chunk);
+    while let Some((start, end)) = searcher.next_match() {
+        let start = from + start.pos();
+        if start >= limit {
+            break;
+        }
+        matches.push((start, from + end.pos()));
+    }
+    matches
+}
+
+/// Finds all non-overlapping matches of the needle, as byte positions of the
+/// start and end cursors. The result is the same as searching sequentially.
+fn find_ranges(haystack: &OmgWtf8, needle: &OmgWtf8, chunk_len: usize) -> Vec<(usize, usize)> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let bytes = &haystack.0;
+    let mut bounds = vec![0];
+    while let Some(&last) = bounds.last() {
+        if last == bytes.len() {
+            break;
+        }
+        bounds.push(safe_boundary(bytes, last + chunk_len));
+    }
+    // a match starting in a chunk can extend past its end by at most the
+    // needle's length.
+    let extend = |end: usize| safe_boundary(bytes, end + needle.len());
+
+    let chunks = bounds
+        .par_windows(2)
+        .map(|w| search(haystack, needle, w[0], extend(w[1]), w[1]))
+        .collect::<Vec<_>>();
+
+    // each chunk was searched from its own start, so its matches may differ
+    // from a sequential search if the previous match runs into it. In that
+    // case the chunk is searched again from where the previous match ended.
+    let mut result = Vec::new();
+    let mut begin = 0;
+    for (w, matches) in bounds.windows(2).zip(chunks) {
+        let matches = if matches.first().is_none_or(|m| m.0 >= begin) {
+            matches
+        } else {
+            search(haystack, needle, begin, extend(w[1]), w[1])
+        };
+        if let Some(&(_, end)) = matches.last() {
+            begin = Haystack::end_to_start_cursor(&haystack, EndCursor::new(end)).pos();
+        }
+        result.extend(matches);
+    }
+    result
+}
+
+fn chunk_len(haystack: &OmgWtf8) -> usize {
+    let threads = rayon::current_num_threads();
+    haystack.len().div_ceil(threads).max(MIN_CHUNK_LEN)
+}
+
+fn par_find(haystack: &OmgWtf8, needle: &OmgWtf8, chunk_len: usize) -> Vec<usize> {
+    find_ranges(haystack, needle, chunk_len)
+        .into_iter()
+        .map(|(start, _)| Haystack::start_cursor_to_offset(&haystack, StartCursor::new(start)))
+        .collect()
+}
+
+fn par_split<'a>(haystack: &'a OmgWtf8, separator: &OmgWtf8, chunk_len: usize) -> Vec<&'a OmgWtf8> {
+    let mut start = Haystack::cursor_at_front(&haystack);
+    let mut pieces = Vec::new();
+    for (a, b) in find_ranges(haystack, separator, chunk_len) {
+        let a = Haystack::start_to_end_cursor(&haystack, StartCursor::new(a));
+        pieces.push(Haystack::range_to_self(haystack, start, a));
+        start = Haystack::end_to_start_cursor(&haystack, EndCursor::new(b));
+    }
+    let end = Haystack::cursor_at_back(&haystack);
+    pieces.push(Haystack::range_to_self(haystack, start, end));
+    pieces
+}
+
+impl OmgWtf8 {
+    /// Returns the byte offsets of all non-overlapping matches of the needle,
+    /// searching large haystacks in parallel.
+    ///
+    /// The haystack is partitioned at character boundaries, and the chunks
+    /// are searched with an overlap so matches spanning two chunks are found.
+    /// The offsets are the same as repeatedly calling
+    /// [`find`](trait.MatchExt.html#method.find) would give, in ascending
+    /// order. An empty needle never matches.
+    pub fn par_find_iter(&self, needle: &OmgWtf8) -> IntoIter<usize> {
+        par_find(self, needle, chunk_len(self)).into_par_iter()
+    }
+
+    /// Splits this string by the separator, searching large haystacks in
+    /// parallel.
+    ///
+    /// The pieces are the same as [`split`](trait.MatchExt.html#method.split)
+    /// would give, in order. An empty separator never matches, so the whole
+    /// string is returned as a single piece.
+    pub fn par_split<'a>(&'a self, separator: &OmgWtf8) -> IntoIter<&'a OmgWtf8> {
+        par_split(self, separator, chunk_len(self)).into_par_iter()
+    }
+}
+
+#[test]
+fn test_par_find_iter() {
+    fn sequential(haystack: &OmgWtf8, needle: &OmgWtf8) -> Vec<usize> {
+        let mut searcher = needle.into_searcher(haystack);
+        let mut offsets = Vec::new();
+        while let Some((start, _)) = searcher.next_match() {
+            offsets.push(Haystack::start_cursor_to_offset(&haystack, start));
+        }
+        offsets
+    }
+
+    let s = OmgWtf8::from_str("aaaa😀a😀😀ba😀aaa");
+    let needles = [
+        omgwtf8!("aa"),
+        omgwtf8!("😀"),
+        omgwtf8!("a😀"),
+        omgwtf8!(r"\u{d83d}"),
+        omgwtf8!(r"\u{de00}a"),
+        omgwtf8!(r"\u{de00}\u{d83d}"),
+    ];
+    for haystack in &[s, &s[6..], &s[..15], &s[6..21]] {
+        for needle in &needles {
+            let expected = sequential(haystack, needle);
+            for chunk_len in 1..=haystack.len() {
+                assert_eq!(par_find(haystack, needle, chunk_len), expected);
+            }
+            assert_eq!(haystack.par_find_iter(needle).collect::<Vec<_>>(), expected);
+        }
+    }
+    assert_eq!(s.par_find_iter(OmgWtf8::EMPTY).count(), 0);
+    assert_eq!(
+        s.par_find_iter(OmgWtf8::from_str("aa")).collect::<Vec<_>>(),
+        vec![0, 2, 23]
+    );
+}
+
+#[test]
+fn test_par_split() {
+    use matching::MatchExt;
+
+    let s = OmgWtf8::from_str("aaaa😀a😀😀ba😀aaa");
+    for haystack in &[s, &s[6..], &s[..15]] {
+        for separator in &[omgwtf8!("aa"), omgwtf8!("😀"), omgwtf8!(r"\u{d83d}")] {
+            let expected = haystack.split(*separator).collect::<Vec<_>>();
+            for chunk_len in 1..=haystack.len() {
+                assert_eq!(par_split(haystack, separator, chunk_len), expected);
+            }
+            assert_eq!(haystack.par_split(separator).collect::<Vec<_>>(), expected);
+        }
+    }
+    assert_eq!(s.par_split(OmgWtf8::EMPTY).collect::<Vec<_>>(), vec![s]);
+}
@@ -0,0 +1,117 @@
+//! Lossy conversion to UTF-8.
+
+use std::fmt;
+use validate::find_surrogate;
+use {OmgWtf8, OmgWtf8Buf};
+
+impl OmgWtf8 {
+    /// Converts this string to UTF-8, replacing every unpaired surrogate with
+    /// `replacement`, and returns the result with the number of replacements.
+    pub fn sanitize(&self, replacement: char) -> (String, usize) {
+        let mut s = String::with_capacity(self.len());
+        let mut count = 0;
+        for c in self.chars() {
+            s.push(c.to_char().unwrap_or_else(|| {
+                count += 1;
+                replacement
+            }));
+        }
+        (s, count)
+    }
+}
+
+impl OmgWtf8Buf {
+    /// Replaces every unpaired surrogate in this buffer with `replacement`,
+    /// and returns the number of replacements. The buffer is valid UTF-8
+    /// afterwards.
+    ///
+    /// If the replacement is encoded in 3 bytes like U+FFFD, the surrogates
+    /// are overwritten without reallocating.
+    pub fn sanitize_in_place(&mut self, replacement: char) -> usize {
+        let mut encoded = [0; 4];
+        let encoded = replacement.encode_utf8(&mut encoded).as_bytes();
+        if encoded.len() != 3 {
+            let (s, count) = self.sanitize(replacement);
+            if count != 0 {
+                self.0 = s.into_bytes();
+            }
+            return count;
+        }
+
+        // the buffer is canonical, so every surrogate is a 3-byte
+        // `ed a0..bf xx` sequence.
+        let mut count = 0;
+        let mut i = 0;
+        while let Some(pos) = find_surrogate(&self.0[i..]) {
+            i += pos;
+            self.0[i..i + 3].copy_from_slice(encoded);
+            count += 1;
+            i += 3;
+        }
+        count
+    }
+}
+
+/// Displays the string with every unpaired surrogate replaced by U+FFFD
+/// REPLACEMENT CHARACTER.
+///
+/// The width, fill, alignment and precision flags are honored like `str`,
+/// counting each replaced surrogate as one character.
+impl fmt::Display for OmgWtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_str() {
+            Some(s) => f.pad(s),
+            None => f.pad(&self.sanitize('\u{fffd}').0),
+        }
+    }
+}
+
+impl fmt::Display for OmgWtf8Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[test]
+fn test_sanitize() {
+    let s = OmgWtf8::from_str("😀a😑");
+    assert_eq!(s.sanitize('?'), ("😀a😑".to_owned(), 0));
+    assert_eq!(s[2..7].sanitize('?'), ("?a?".to_owned(), 2));
+    assert_eq!(
+        OmgWtf8::from_wide(&[0xdc00, 0xd800]).sanitize('\u{fffd}'),
+        ("\u{fffd}\u{fffd}".to_owned(), 2)
+    );
+}
+
+#[test]
+fn test_sanitize_in_place() {
+    let s = OmgWtf8::from_str("😀a\u{d7ff}😑");
+
+    let mut buf = s[2..10].to_owned();
+    let capacity = buf.capacity();
+    assert_eq!(buf.sanitize_in_place('\u{fffd}'), 2);
+    assert_eq!(buf, "\u{fffd}a\u{d7ff}\u{fffd}");
+    assert_eq!(buf.capacity(), capacity);
+
+    let mut buf = s[2..10].to_owned();
+    assert_eq!(buf.sanitize_in_place('?'), 2);
+    assert_eq!(buf, "?a\u{d7ff}?");
+
+    let mut buf = s.to_owned();
+    assert_eq!(buf.sanitize_in_place('?'), 0);
+    assert_eq!(buf, "😀a\u{d7ff}😑");
+}
+
+#[test]
+fn test_display() {
+    let s = OmgWtf8::from_str("😀a😑");
+    assert_eq!(s.to_string(), "😀a😑");
+    assert_eq!(s[2..7].to_string(), "\u{fffd}a\u{fffd}");
+    assert_eq!(s.to_owned().to_string(), "😀a😑");
+
+    assert_eq!(format!("[{:>5}]", s), "[  😀a😑]");
+    assert_eq!(format!("[{:*<5}]", &s[2..7]), "[\u{fffd}a\u{fffd}**]");
+    assert_eq!(format!("[{:^7.2}]", &s[2..7]), "[  \u{fffd}a   ]");
+    assert_eq!(format!("[{:.1}]", s), "[😀]");
+    assert_eq!(format!("[{:2}]", s), "[😀a😑]");
+}
@@ -0,0 +1,133 @@
+//! Lossless human-readable serde representation.
+//!
+//! Use this module with `#[serde(with = "omgwtf8::serde_escaped")]` on a
+//! field of type `OmgWtf8Buf` or `Box<OmgWtf8>`. The string is always
+//! serialized as a string, where every unpaired surrogate is written as a
+//! `\uD8xx`-style escape, and a literal backslash is written as `\\`.
+//!
+//! ```
+//! # extern crate omgwtf8;
+//! # extern crate serde_json;
+//! # fn main() {
+//! # use omgwtf8::{serde_escaped, OmgWtf8, OmgWtf8Buf};
+//! let s = OmgWtf8::from_wide(&[0x5c, 0x41, 0xd800]);
+//! let mut json = Vec::new();
+//! serde_escaped::serialize(&*s, &mut serde_json::Serializer::new(&mut json)).unwrap();
+//! assert_eq!(json, br#""\\\\A\\uD800""#);
+//!
+//! let mut de = serde_json::Deserializer::from_slice(&json);
+//! let t: OmgWtf8Buf = serde_escaped::deserialize(&mut de).unwrap();
+//! assert_eq!(*t, *s);
+//! # }
+//! ```
+
+use serde::de::{Error, Unexpected, Visitor};
+use serde::{Deserializer, Serializer};
+use std::fmt::{self, Write};
+use {OmgWtf8, OmgWtf8Buf};
+
+/// Serializes the string with unpaired surrogates escaped.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<OmgWtf8> + ?Sized,
+    S: Serializer,
+{
+    let value = value.as_ref();
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c.to_char() {
+            Some('\\') => escaped.push_str("\\\\"),
+            Some(c) => escaped.push(c),
+            None => write!(escaped, "\\u{:04X}", c.to_u32()).unwrap(),
+        }
+    }
+    serializer.serialize_str(&escaped)
+}
+
+/// Deserializes a string with unpaired surrogates escaped.
+///
+/// Escaped surrogates forming a pair are merged into a supplementary
+/// character.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: From<OmgWtf8Buf>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(EscapedVisitor).map(T::from)
+}
+
+struct EscapedVisitor;
+
+impl<'de> Visitor<'de> for EscapedVisitor {
+    type Value = OmgWtf8Buf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string with surrogates escaped as \\uXXXX")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<OmgWtf8Buf, E> {
+        let invalid = || E::invalid_value(Unexpected::Str(v), &self);
+        let mut buf = OmgWtf8Buf::with_capacity(v.len());
+        let mut rest = v;
+        while let Some(pos) = rest.find('\\') {
+            buf.push_omgwtf8(OmgWtf8::from_str(&rest[..pos]));
+            rest = &rest[pos + 1..];
+            if rest.starts_with('\\') {
+                buf.push_code_unit(0x5c);
+                rest = &rest[1..];
+            } else if rest.starts_with('u') {
+                let hex = rest.get(1..5).ok_or_else(invalid)?;
+                if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(invalid());
+                }
+                let c = u16::from_str_radix(hex, 16).unwrap();
+                buf.push_code_unit(c);
+                rest = &rest[5..];
+            } else {
+                return Err(invalid());
+            }
+        }
+        buf.push_omgwtf8(OmgWtf8::from_str(rest));
+        Ok(buf)
+    }
+}
+
+#[test]
+fn test_serde_escaped() {
+    use serde_json;
+
+    #[derive(Debug, PartialEq)]
+    struct Wrapper(OmgWtf8Buf);
+
+    impl ::serde::Serialize for Wrapper {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> ::serde::Deserialize<'de> for Wrapper {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer).map(Wrapper)
+        }
+    }
+
+    let s = OmgWtf8::from_str("a\\u😀b");
+    for (t, json) in &[
+        (s, r#""a\\\\u😀b""#),
+        (&s[..5], r#""a\\\\u\\uD83D""#),
+        (&s[5..], r#""\\uDE00b""#),
+    ] {
+        let serialized = serde_json::to_string(&Wrapper(OmgWtf8Buf::from(*t))).unwrap();
+        assert_eq!(serialized, *json);
+        let deserialized: Wrapper = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(&*deserialized.0, *t);
+    }
+
+    let merged: Wrapper = serde_json::from_str(r#""\\ud83d\\uDE00""#).unwrap();
+    assert_eq!(merged.0, "😀");
+    let unescaped: Wrapper = serde_json::from_str(r#""😀""#).unwrap();
+    assert_eq!(unescaped.0, "😀");
+    assert!(serde_json::from_str::<Wrapper>(r#""\\n""#).is_err());
+    assert!(serde_json::from_str::<Wrapper>(r#""\\u12""#).is_err());
+    assert!(serde_json::from_str::<Wrapper>(r#""\\u+123""#).is_err());
+}
@@ -0,0 +1,145 @@
+//! Serde support.
+//!
+//! An OMG-WTF-8 string is serialized as a string if it is valid UTF-8.
+//! Otherwise, it is serialized as bytes in canonical representation, i.e.
+//! all unpaired surrogates are encoded as 3-byte `ed xx xx` sequences.
+//!
+//! Deserialization accepts either form. Bytes are validated, and split
+//! surrogates are accepted at both ends.
+
+use conv::canonicalize_in_place;
+use serde::de::{Error, SeqAccess, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use {OmgWtf8, OmgWtf8Buf};
+
+impl Serialize for OmgWtf8 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(s) = self.to_str() {
+            serializer.serialize_str(s)
+        } else if self.is_canonical() {
+            serializer.serialize_bytes(&self.0)
+        } else {
+            serializer.serialize_bytes(&self.to_owned().0)
+        }
+    }
+}
+
+impl Serialize for OmgWtf8Buf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+struct BorrowedVisitor;
+
+impl<'de> Visitor<'de> for BorrowedVisitor {
+    type Value = &'de OmgWtf8;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a borrowed OMG-WTF-8 string")
+    }
+
+    fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<&'de OmgWtf8, E> {
+        Ok(OmgWtf8::from_str(v))
+    }
+
+    fn visit_borrowed_bytes<E: Error>(self, v: &'de [u8]) -> Result<&'de OmgWtf8, E> {
+        OmgWtf8::from_bytes(v).map_err(|_| E::invalid_value(Unexpected::Bytes(v), &self))
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for &'a OmgWtf8 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(BorrowedVisitor)
+    }
+}
+
+struct OwnedVisitor;
+
+impl<'de> Visitor<'de> for OwnedVisitor {
+    type Value = OmgWtf8Buf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an OMG-WTF-8 string")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<OmgWtf8Buf, E> {
+        Ok(OmgWtf8Buf::from(v))
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<OmgWtf8Buf, E> {
+        Ok(OmgWtf8Buf::from(v))
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<OmgWtf8Buf, E> {
+        match OmgWtf8::from_bytes(v) {
+            Ok(s) => Ok(OmgWtf8Buf::from(s)),
+            Err(_) => Err(E::invalid_value(Unexpected::Bytes(v), &self)),
+        }
+    }
+
+    fn visit_byte_buf<E: Error>(self, mut v: Vec<u8>) -> Result<OmgWtf8Buf, E> {
+        if OmgWtf8::from_bytes(&v).is_err() {
+            return Err(E::invalid_value(Unexpected::Bytes(&v), &self));
+        }
+        canonicalize_in_place(&mut v);
+        Ok(OmgWtf8Buf(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<OmgWtf8Buf, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        self.visit_byte_buf(bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for OmgWtf8Buf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(OwnedVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<OmgWtf8> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        OmgWtf8Buf::deserialize(deserializer).map(OmgWtf8Buf::into_boxed_omgwtf8)
+    }
+}
+
+#[test]
+fn test_serialize() {
+    use serde_json::to_string;
+
+    let s = OmgWtf8::from_str("a😀");
+    assert_eq!(to_string(s).unwrap(), r#""a😀""#);
+    assert_eq!(to_string(&s.to_owned()).unwrap(), r#""a😀""#);
+    assert_eq!(to_string(&s[..3]).unwrap(), "[97,237,160,189]");
+    assert_eq!(
+        to_string(&OmgWtf8::from_wide(&[0xdc00])).unwrap(),
+        "[237,176,128]"
+    );
+}
+
+#[test]
+fn test_deserialize() {
+    use serde_json::{from_str, to_string};
+
+    let s = OmgWtf8::from_str("a😀b");
+    for t in &[s, &s[..3], &s[3..], &s[3..5]] {
+        let json = to_string(t).unwrap();
+        let buf: OmgWtf8Buf = from_str(&json).unwrap();
+        assert_eq!(&*buf, *t);
+        let boxed: Box<OmgWtf8> = from_str(&json).unwrap();
+        assert_eq!(&*boxed, *t);
+    }
+
+    let borrowed: &OmgWtf8 = from_str(r#""a😀b""#).unwrap();
+    assert_eq!(borrowed, s);
+    let split: OmgWtf8Buf = from_str("[159,152,128,98]").unwrap();
+    assert_eq!(&*split, &s[3..]);
+    assert!(split.is_canonical());
+    assert!(from_str::<OmgWtf8Buf>("[255]").is_err());
+    assert!(from_str::<OmgWtf8Buf>("[237,160,189,237,184,128]").is_err());
+}
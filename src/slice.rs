@@ -1,8 +1,16 @@
 use OmgWtf8;
-use std::ops::{Index, Range, RangeFrom, RangeFull, RangeTo};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Bound, Index, Range, RangeBounds, RangeFrom, RangeFull, RangeTo};
 
 /// Type of an index in an OMG-WTF-8 string.
-pub(crate) enum IndexType {
+///
+/// This is returned by [`OmgWtf8::classify_index`] to explain whether, and
+/// why, an index can or cannot be used for slicing.
+///
+/// [`OmgWtf8::classify_index`]: struct.OmgWtf8.html#method.classify_index
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IndexType {
     /// Boundary of a WTF-8 character sequence.
     CharBoundary,
     /// Byte 1 in a 4-byte sequence.
@@ -17,6 +25,69 @@ pub(crate) enum IndexType {
     OutOfBounds,
 }
 
+/// The error returned by [`OmgWtf8::slice_checked`] when a range cannot be
+/// used to slice a string.
+///
+/// [`OmgWtf8::slice_checked`]: struct.OmgWtf8.html#method.slice_checked
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SliceError {
+    index: usize,
+    index_type: IndexType,
+    len: usize,
+    reversed_start: Option<usize>,
+}
+
+impl SliceError {
+    /// The offending index.
+    ///
+    /// If the range is reversed, this is the end index.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The classification of the offending index.
+    pub fn index_type(&self) -> IndexType {
+        self.index_type
+    }
+
+    /// The length of the string being sliced.
+    pub fn string_len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the error is caused by the start index being after the
+    /// end index, rather than by an invalid index.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed_start.is_some()
+    }
+}
+
+impl fmt::Display for SliceError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(start) = self.reversed_start {
+            return write!(
+                fmt,
+                "slice index starts at {} but ends at {}",
+                start, self.index
+            );
+        }
+        match self.index_type {
+            IndexType::OutOfBounds => write!(
+                fmt,
+                "byte index {} is out of bounds of string of length {}",
+                self.index, self.len
+            ),
+            index_type => write!(
+                fmt,
+                "byte index {} is not a char boundary ({:?}) of string of length {}",
+                self.index, index_type, self.len
+            ),
+        }
+    }
+}
+
+impl Error for SliceError {}
+
 impl OmgWtf8 {
     /// Obtains the length of this string.
     pub fn len(&self) -> usize {
@@ -27,19 +98,32 @@ impl OmgWtf8 {
         self.0.is_empty()
     }
 
+    /// Returns whether `index` can be used as an endpoint when slicing this
+    /// string.
+    ///
+    /// Besides the usual boundaries between WTF-8 character sequences, the
+    /// middle of a 4-byte sequence (between the high and low surrogate
+    /// halves) is also considered a boundary.
+    pub fn is_char_boundary(&self, index: usize) -> bool {
+        matches!(
+            self.classify_index(index),
+            IndexType::CharBoundary | IndexType::FourByteSeq2
+        )
+    }
+
     /// Classifies the kind of index in this string.
-    pub(crate) fn classify_index(&self, index: usize) -> IndexType {
+    pub fn classify_index(&self, index: usize) -> IndexType {
         let len = self.0.len();
         if index == 0 || index == len {
             return IndexType::CharBoundary;
         }
         match self.0.get(index) {
-            Some(&0x80...0xbf) => {
-                if 1 <= index && index <= len - 3 && self.0[index - 1] >= 0xf0 {
+            Some(&(0x80..=0xbf)) => {
+                if index + 3 <= len && self.0[index - 1] >= 0xf0 {
                     IndexType::FourByteSeq1
-                } else if 2 <= index && index <= len - 2 && self.0[index - 2] >= 0xf0 {
+                } else if index >= 2 && index + 2 <= len && self.0[index - 2] >= 0xf0 {
                     IndexType::FourByteSeq2
-                } else if 3 <= index && index <= len - 1 && self.0[index - 3] >= 0xf0 {
+                } else if index >= 3 && self.0[index - 3] >= 0xf0 {
                     IndexType::FourByteSeq3
                 } else {
                     IndexType::Interior
@@ -49,6 +133,152 @@ impl OmgWtf8 {
             None => IndexType::OutOfBounds,
         }
     }
+
+    /// Converts an offset counted in UTF-16 code units into a byte index
+    /// usable for slicing, or returns `None` if it is out of bounds.
+    ///
+    /// An offset between the two surrogates of a 4-byte sequence is
+    /// converted to the `FourByteSeq2` index.
+    pub(crate) fn byte_index_of_wide(&self, wide_index: usize) -> Option<usize> {
+        let len = self.0.len();
+        let mut index = 0;
+        let mut wide = 0;
+        while wide < wide_index {
+            let lead = *self.0.get(index)?;
+            if lead < 0xf0 || index + 3 == len {
+                index += match lead {
+                    0x00..=0x7f => 1,
+                    0x80..=0xdf => 2,
+                    _ => 3,
+                };
+                if lead & 0xc0 == 0x80 {
+                    // a low surrogate in split representation.
+                    index += 1;
+                }
+                wide += 1;
+            } else if wide + 1 == wide_index {
+                return Some(index + 2);
+            } else {
+                index += 4;
+                wide += 2;
+            }
+        }
+        Some(index)
+    }
+
+    /// Converts a UTF-16 offset into a byte index, panicking if out of
+    /// bounds.
+    pub(crate) fn byte_index_of_wide_or_panic(&self, wide_index: usize) -> usize {
+        match self.byte_index_of_wide(wide_index) {
+            Some(index) => index,
+            None => panic!(
+                "wide index {} is out of bounds of string of wide length {}",
+                wide_index,
+                self.encode_wide().count()
+            ),
+        }
+    }
+
+    /// Divides this string into two at an offset counted in UTF-16 code
+    /// units.
+    ///
+    /// If the offset lands between the two surrogates of a supplementary
+    /// character, the character is cut in half, so the first part ends with
+    /// the high surrogate and the second part starts with the low surrogate.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("a😀b");
+    /// let (head, tail) = s.split_at_wide(2);
+    /// assert_eq!(head.encode_wide().collect::<Vec<_>>(), [0x61, 0xd83d]);
+    /// assert_eq!(tail.encode_wide().collect::<Vec<_>>(), [0xde00, 0x62]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is beyond the length of this string in UTF-16
+    /// code units.
+    pub fn split_at_wide(&self, wide_index: usize) -> (&Self, &Self) {
+        let index = self.byte_index_of_wide_or_panic(wide_index);
+        (&self[..index], &self[index..])
+    }
+
+    /// Cuts the supplementary characters at either end of this string in half
+    /// when requested, producing the split representation.
+    #[cfg(any(feature = "arbitrary", feature = "proptest"))]
+    pub(crate) fn split_ends(&self, split_start: bool, split_end: bool) -> Box<Self> {
+        let len = self.len();
+        let start = if split_start && self.classify_index(2) == IndexType::FourByteSeq2 {
+            2
+        } else {
+            0
+        };
+        let end =
+            if split_end && len >= 2 && self.classify_index(len - 2) == IndexType::FourByteSeq2 {
+                len - 2
+            } else {
+                len
+            };
+        let s = &self[start..end];
+        unsafe { OmgWtf8::from_boxed_bytes_unchecked(Box::from(s.as_bytes())) }
+    }
+
+    /// Slices this string, returning an error instead of panicking if either
+    /// end of the range is not a valid index.
+    ///
+    /// An endpoint pointing at the middle of a 4-byte sequence is valid, and
+    /// the result will include the corresponding surrogate half, so the
+    /// returned string may be longer than the range.
+    pub fn slice_checked<R: RangeBounds<usize>>(&self, range: R) -> Result<&Self, SliceError> {
+        let len = self.0.len();
+        let mut start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let mut end = match range.end_bound() {
+            Bound::Included(&i) => i.saturating_add(1),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => len,
+        };
+        let error = |index, index_type| SliceError {
+            index,
+            index_type,
+            len,
+            reversed_start: None,
+        };
+        match self.classify_index(start) {
+            // an empty range in the middle of a 4-byte sequence is still empty.
+            IndexType::FourByteSeq2 | IndexType::CharBoundary if start == end => {
+                return Ok(Self::EMPTY);
+            }
+            IndexType::FourByteSeq2 => start -= 1,
+            IndexType::CharBoundary => {}
+            index_type => return Err(error(start, index_type)),
+        }
+        match self.classify_index(end) {
+            IndexType::FourByteSeq2 => end += 1,
+            IndexType::CharBoundary => {}
+            index_type => return Err(error(end, index_type)),
+        }
+        if start > end {
+            return Err(SliceError {
+                index: end,
+                index_type: IndexType::CharBoundary,
+                len,
+                reversed_start: Some(start),
+            });
+        }
+        unsafe { Ok(Self::from_bytes_unchecked(&self.0[start..end])) }
+    }
+
+    fn slice_or_panic<R: RangeBounds<usize>>(&self, range: R) -> &Self {
+        match self.slice_checked(range) {
+            Ok(s) => s,
+            Err(e) => panic!("{}", e),
+        }
+    }
 }
 
 /// Allows OMG-WTF-8 strings be sliced using `s[..]`.
@@ -62,47 +292,24 @@ impl Index<RangeFull> for OmgWtf8 {
 /// Allows OMG-WTF-8 strings be sliced using `s[..j]`.
 impl Index<RangeTo<usize>> for OmgWtf8 {
     type Output = Self;
-    fn index(&self, mut range: RangeTo<usize>) -> &Self {
-        match self.classify_index(range.end) {
-            IndexType::FourByteSeq2 => range.end += 1,
-            IndexType::CharBoundary => {}
-            _ => panic!("Invalid end index {}", range.end),
-        };
-        unsafe { Self::from_bytes_unchecked(&self.0[range]) }
+    fn index(&self, range: RangeTo<usize>) -> &Self {
+        self.slice_or_panic(range)
     }
 }
 
 /// Allows OMG-WTF-8 strings be sliced using `s[i..]`.
 impl Index<RangeFrom<usize>> for OmgWtf8 {
     type Output = Self;
-    fn index(&self, mut range: RangeFrom<usize>) -> &Self {
-        match self.classify_index(range.start) {
-            IndexType::FourByteSeq2 => range.start -= 1,
-            IndexType::CharBoundary => {}
-            _ => panic!("Invalid start index {}", range.start),
-        };
-        unsafe { Self::from_bytes_unchecked(&self.0[range]) }
+    fn index(&self, range: RangeFrom<usize>) -> &Self {
+        self.slice_or_panic(range)
     }
 }
 
 /// Allows OMG-WTF-8 strings be sliced using `s[i..j]`.
 impl Index<Range<usize>> for OmgWtf8 {
     type Output = Self;
-    fn index(&self, mut range: Range<usize>) -> &Self {
-        if range.start == range.end {
-            return Self::from_str("");
-        }
-        match self.classify_index(range.start) {
-            IndexType::FourByteSeq2 => range.start -= 1,
-            IndexType::CharBoundary => {}
-            _ => panic!("Invalid start index {}", range.start),
-        };
-        match self.classify_index(range.end) {
-            IndexType::FourByteSeq2 => range.end += 1,
-            IndexType::CharBoundary => {}
-            _ => panic!("Invalid end index {}", range.end),
-        };
-        unsafe { Self::from_bytes_unchecked(&self.0[range]) }
+    fn index(&self, range: Range<usize>) -> &Self {
+        self.slice_or_panic(range)
     }
 }
 
@@ -112,6 +319,87 @@ fn test_ow8_len() {
     assert_eq!(s.len(), 3);
     assert_eq!(s.as_bytes(), b"foo");
 }
+#[test]
+fn test_classify_index() {
+    let s = OmgWtf8::from_str("a\u{e9}\u{10000}");
+    assert_eq!(s.classify_index(0), IndexType::CharBoundary);
+    assert_eq!(s.classify_index(1), IndexType::CharBoundary);
+    assert_eq!(s.classify_index(2), IndexType::Interior);
+    assert_eq!(s.classify_index(3), IndexType::CharBoundary);
+    assert_eq!(s.classify_index(4), IndexType::FourByteSeq1);
+    assert_eq!(s.classify_index(5), IndexType::FourByteSeq2);
+    assert_eq!(s.classify_index(6), IndexType::FourByteSeq3);
+    assert_eq!(s.classify_index(7), IndexType::CharBoundary);
+    assert_eq!(s.classify_index(8), IndexType::OutOfBounds);
+
+    // short strings must not underflow.
+    let s = OmgWtf8::from_str("\u{e9}");
+    assert_eq!(s.classify_index(1), IndexType::Interior);
+
+    // the edges of split surrogates can't be split further.
+    let s = unsafe { OmgWtf8::from_bytes_unchecked(b"\x90\x80\x80\xf0\x90\x80") };
+    assert_eq!(s.classify_index(1), IndexType::Interior);
+    assert_eq!(s.classify_index(2), IndexType::Interior);
+    assert_eq!(s.classify_index(3), IndexType::CharBoundary);
+    assert_eq!(s.classify_index(4), IndexType::Interior);
+    assert_eq!(s.classify_index(5), IndexType::Interior);
+}
+#[test]
+fn test_is_char_boundary() {
+    let s = OmgWtf8::from_str("a\u{e9}\u{10000}");
+    let boundaries = (0..10)
+        .filter(|&i| s.is_char_boundary(i))
+        .collect::<Vec<_>>();
+    assert_eq!(boundaries, vec![0, 1, 3, 5, 7]);
+}
+#[test]
+fn test_byte_index_of_wide() {
+    let s = OmgWtf8::from_str("😀A\u{e9}\u{6e2c}😑");
+    let indices: Vec<_> = (0..9).map(|i| s.byte_index_of_wide(i)).collect();
+    assert_eq!(
+        indices,
+        vec![
+            Some(0),
+            Some(2),
+            Some(4),
+            Some(5),
+            Some(7),
+            Some(10),
+            Some(12),
+            Some(14),
+            None
+        ]
+    );
+    assert_eq!(s[2..12].byte_index_of_wide(1), Some(3));
+    assert_eq!(s[2..12].byte_index_of_wide(4), Some(9));
+    assert_eq!(s[2..12].byte_index_of_wide(5), Some(12));
+    assert_eq!(s[2..12].byte_index_of_wide(6), None);
+}
+
+#[test]
+fn test_split_at_wide() {
+    let s = OmgWtf8::from_str("😀A😑");
+    let wide = s.encode_wide().collect::<Vec<_>>();
+    for i in 0..=wide.len() {
+        let (head, tail) = s.split_at_wide(i);
+        assert_eq!(head.encode_wide().collect::<Vec<_>>(), &wide[..i]);
+        assert_eq!(tail.encode_wide().collect::<Vec<_>>(), &wide[i..]);
+    }
+    assert_eq!(s.split_at_wide(1), (&s[..2], &s[2..]));
+    assert_eq!(s.split_at_wide(4), (&s[..7], &s[7..]));
+
+    let (head, tail) = s[2..7].split_at_wide(1);
+    assert_eq!(head.as_bytes(), b"\x9f\x98\x80");
+    assert_eq!(tail.as_bytes(), b"A\xf0\x9f\x98");
+    assert_eq!(s[2..7].split_at_wide(3), (&s[2..7], OmgWtf8::EMPTY));
+}
+
+#[test]
+#[should_panic(expected = "wide index 6 is out of bounds of string of wide length 5")]
+fn test_split_at_wide_out_of_bounds() {
+    OmgWtf8::from_str("😀A😑").split_at_wide(6);
+}
+
 #[test]
 fn test_ow8_slices_str() {
     let s = OmgWtf8::from_str("foo");
@@ -209,7 +497,50 @@ fn test_slice_into_invalid_index_canonical_2() {
 }
 #[test]
 #[should_panic]
+#[allow(clippy::reversed_empty_ranges)]
 fn test_slice_into_invalid_index_wrong_order() {
     let s = OmgWtf8::from_str("12345");
     let _ = s[3..1];
 }
+
+#[test]
+#[allow(clippy::reversed_empty_ranges)]
+fn test_slice_checked() {
+    let s = OmgWtf8::from_str("a\u{e9}\u{10000}");
+    assert_eq!(s.slice_checked(..).unwrap().as_bytes(), s.as_bytes());
+    assert_eq!(s.slice_checked(1..3).unwrap().as_bytes(), b"\xc3\xa9");
+    assert_eq!(s.slice_checked(1..=2).unwrap().as_bytes(), b"\xc3\xa9");
+    assert_eq!(s.slice_checked(5..).unwrap().as_bytes(), b"\x90\x80\x80");
+    assert_eq!(
+        s.slice_checked(..5).unwrap().as_bytes(),
+        b"a\xc3\xa9\xf0\x90\x80"
+    );
+    assert_eq!(s.slice_checked(5..5).unwrap().as_bytes(), b"");
+    assert_eq!(s.slice_checked(7..7).unwrap().as_bytes(), b"");
+
+    // an empty range must still start at a valid index.
+    let e = s.slice_checked(2..2).unwrap_err();
+    assert_eq!((e.index(), e.index_type()), (2, IndexType::Interior));
+    let e = s.slice_checked(6..6).unwrap_err();
+    assert_eq!((e.index(), e.index_type()), (6, IndexType::FourByteSeq3));
+    let e = s.slice_checked(8..8).unwrap_err();
+    assert_eq!((e.index(), e.index_type()), (8, IndexType::OutOfBounds));
+
+    let e = s.slice_checked(2..).unwrap_err();
+    assert_eq!(
+        (e.index(), e.index_type(), e.string_len()),
+        (2, IndexType::Interior, 7)
+    );
+    assert!(!e.is_reversed());
+    let e = s.slice_checked(..6).unwrap_err();
+    assert_eq!((e.index(), e.index_type()), (6, IndexType::FourByteSeq3));
+    let e = s.slice_checked(1..8).unwrap_err();
+    assert_eq!((e.index(), e.index_type()), (8, IndexType::OutOfBounds));
+    assert_eq!(
+        e.to_string(),
+        "byte index 8 is out of bounds of string of length 7"
+    );
+    let e = s.slice_checked(3..1).unwrap_err();
+    assert!(e.is_reversed());
+    assert_eq!(e.to_string(), "slice index starts at 3 but ends at 1");
+}
@@ -0,0 +1,103 @@
+//! Proptest strategies.
+//!
+//! These strategies generate well-formed OMG-WTF-8 strings for property
+//! testing code built on top of this crate, e.g. custom patterns.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::prop_oneof;
+use proptest::sample::select;
+use {IndexType, OmgWtf8, OmgWtf8Buf};
+
+/// Generates UTF-16 code units, biased towards ASCII characters and
+/// surrogates.
+fn code_units() -> impl Strategy<Value = Vec<u16>> {
+    vec(
+        prop_oneof![
+            0u16..0x80,
+            0xd800u16..0xdc00,
+            0xdc00u16..0xe000,
+            any::<u16>(),
+        ],
+        0..16,
+    )
+}
+
+/// Generates a well-formed OMG-WTF-8 string.
+///
+/// The string may contain unpaired surrogates, and a supplementary character
+/// at either end may be cut in half to produce the split representation.
+pub fn omgwtf8() -> impl Strategy<Value = Box<OmgWtf8>> {
+    (code_units(), any::<bool>(), any::<bool>()).prop_map(|(wide, split_start, split_end)| {
+        OmgWtf8::from_wide(&wide).split_ends(split_start, split_end)
+    })
+}
+
+/// Generates a well-formed OMG-WTF-8 string together with a valid index into
+/// it.
+///
+/// The index can be used to slice the string at either end, and it may point
+/// to the middle of a 4-byte sequence.
+pub fn omgwtf8_with_index() -> impl Strategy<Value = (Box<OmgWtf8>, usize)> {
+    omgwtf8().prop_flat_map(|s| {
+        let indices = (0..=s.len())
+            .filter(|&i| s.is_char_boundary(i))
+            .collect::<Vec<_>>();
+        (Just(s), select(indices))
+    })
+}
+
+/// Generates a needle, a haystack and the index where the needle is found in
+/// the haystack, as `(needle, haystack, index)`.
+///
+/// The haystack starts with the needle at the index, i.e. `&haystack[index..]`
+/// begins with the needle. The needle may also occur earlier in the haystack.
+/// If the start of the needle is merged with a high surrogate before it, the
+/// index points to the middle of the 4-byte sequence.
+pub fn needle_and_haystack() -> impl Strategy<Value = (Box<OmgWtf8>, Box<OmgWtf8>, usize)> {
+    (omgwtf8(), code_units(), code_units()).prop_map(|(needle, prefix, suffix)| {
+        let mut haystack = OmgWtf8Buf::from(&*OmgWtf8::from_wide(&prefix));
+        let prefix_len = haystack.len();
+        haystack.push_omgwtf8(&needle);
+        haystack.push_omgwtf8(&OmgWtf8::from_wide(&suffix));
+        // the end of the prefix may become the middle of a 4-byte sequence
+        // after merging with the surrogate after it.
+        let index = if haystack.classify_index(prefix_len) == IndexType::FourByteSeq3 {
+            prefix_len - 1
+        } else {
+            prefix_len
+        };
+        (needle, haystack.into_boxed_omgwtf8(), index)
+    })
+}
+
+#[test]
+fn test_omgwtf8() {
+    use proptest::test_runner::TestRunner;
+
+    let mut runner = TestRunner::deterministic();
+    runner
+        .run(&omgwtf8_with_index(), |(s, index)| {
+            prop_assert!(OmgWtf8::validate(s.as_bytes()).is_well_formed());
+            prop_assert!(s.slice_checked(..index).is_ok());
+            prop_assert!(s.slice_checked(index..).is_ok());
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_needle_and_haystack() {
+    use matching::MatchExt;
+    use proptest::test_runner::TestRunner;
+
+    let mut runner = TestRunner::deterministic();
+    runner
+        .run(&needle_and_haystack(), |(needle, haystack, index)| {
+            let found = haystack.find(&*needle);
+            prop_assert!(found.is_some_and(|i| i <= index));
+            prop_assert_eq!(haystack[index..].find(&*needle), Some(0));
+            Ok(())
+        })
+        .unwrap();
+}
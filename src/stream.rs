@@ -0,0 +1,150 @@
+//! Searching in a stream of chunks.
+
+use std::ops::Range;
+use std::vec;
+use OmgWtf8;
+
+/// A searcher finding a needle in a haystack which arrives in chunks.
+///
+/// The search is done on UTF-16 code units with the Knuth-Morris-Pratt
+/// algorithm, so matches spanning chunk boundaries are found, including when
+/// a boundary falls between the two halves of a surrogate pair. Matches are
+/// reported as ranges of code unit offsets from the start of the stream, and
+/// do not overlap.
+///
+/// ```
+/// use omgwtf8::{OmgWtf8, StreamSearcher};
+///
+/// let haystack = OmgWtf8::from_str("a😀b😀");
+/// let mut searcher = StreamSearcher::new(OmgWtf8::from_str("😀"));
+/// assert_eq!(searcher.push(&haystack[..3]).collect::<Vec<_>>(), vec![]);
+/// assert_eq!(searcher.push(&haystack[3..8]).collect::<Vec<_>>(), vec![1..3]);
+/// assert_eq!(searcher.push(&haystack[8..]).collect::<Vec<_>>(), vec![4..6]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamSearcher {
+    needle: Vec<u16>,
+    /// `failure[i]` is the length of the longest proper border of
+    /// `needle[..=i]`.
+    failure: Vec<usize>,
+    /// The number of code units of the needle currently matched.
+    state: usize,
+    position: u64,
+}
+
+impl StreamSearcher {
+    /// Creates a searcher for the needle.
+    ///
+    /// An empty needle never matches.
+    pub fn new(needle: &OmgWtf8) -> Self {
+        let needle = needle.encode_wide().collect::<Vec<u16>>();
+        let mut failure = vec![0; needle.len()];
+        let mut k = 0;
+        for i in 1..needle.len() {
+            while k > 0 && needle[i] != needle[k] {
+                k = failure[k - 1];
+            }
+            if needle[i] == needle[k] {
+                k += 1;
+            }
+            failure[i] = k;
+        }
+        StreamSearcher {
+            needle,
+            failure,
+            state: 0,
+            position: 0,
+        }
+    }
+
+    /// Returns the number of code units pushed so far.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Feeds the next chunk of the haystack, and returns the matches ending
+    /// in this chunk.
+    pub fn push(&mut self, chunk: &OmgWtf8) -> StreamMatches {
+        let mut matches = Vec::new();
+        if self.needle.is_empty() {
+            self.position += chunk.encode_wide().count() as u64;
+        } else {
+            for c in chunk.encode_wide() {
+                self.position += 1;
+                while self.state > 0 && c != self.needle[self.state] {
+                    self.state = self.failure[self.state - 1];
+                }
+                if c == self.needle[self.state] {
+                    self.state += 1;
+                }
+                if self.state == self.needle.len() {
+                    matches.push(self.position - self.state as u64..self.position);
+                    self.state = 0;
+                }
+            }
+        }
+        StreamMatches {
+            inner: matches.into_iter(),
+        }
+    }
+}
+
+/// An iterator over the matches found in a chunk.
+///
+/// This is returned by [`StreamSearcher::push`].
+///
+/// [`StreamSearcher::push`]: struct.StreamSearcher.html#method.push
+#[derive(Clone, Debug)]
+pub struct StreamMatches {
+    inner: vec::IntoIter<Range<u64>>,
+}
+
+impl Iterator for StreamMatches {
+    type Item = Range<u64>;
+    fn next(&mut self) -> Option<Range<u64>> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for StreamMatches {}
+
+#[test]
+fn test_stream_searcher() {
+    let haystack = OmgWtf8::from_str("abab😀abac😀😀");
+    let expected_wide = haystack.encode_wide().collect::<Vec<u16>>();
+    for needle in &["aba", "😀", "c😀", "😀😀", "abac"] {
+        let needle_wide = OmgWtf8::from_str(needle)
+            .encode_wide()
+            .collect::<Vec<u16>>();
+        let mut expected = Vec::new();
+        let mut i = 0;
+        while i + needle_wide.len() <= expected_wide.len() {
+            if expected_wide[i..].starts_with(&needle_wide) {
+                expected.push(i as u64..(i + needle_wide.len()) as u64);
+                i += needle_wide.len();
+            } else {
+                i += 1;
+            }
+        }
+
+        for split in (0..=haystack.len()).filter(|&i| haystack.is_char_boundary(i)) {
+            let mut searcher = StreamSearcher::new(OmgWtf8::from_str(needle));
+            let mut found = searcher.push(&haystack[..split]).collect::<Vec<_>>();
+            found.extend(searcher.push(&haystack[split..]));
+            assert_eq!(found, expected, "{:?} {}", needle, split);
+            assert_eq!(searcher.position(), expected_wide.len() as u64);
+        }
+    }
+
+    let mut searcher = StreamSearcher::new(&OmgWtf8::from_wide(&[0xd83d]));
+    assert_eq!(
+        searcher.push(&haystack[..6]).collect::<Vec<_>>(),
+        vec![4..5]
+    );
+    let mut searcher = StreamSearcher::new(OmgWtf8::EMPTY);
+    assert_eq!(searcher.push(haystack).len(), 0);
+}
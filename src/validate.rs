@@ -0,0 +1,444 @@
+use conv::debug_check_well_formed;
+use errors::FromBytesError;
+use std::cmp;
+use std::slice;
+use OmgWtf8;
+
+/// Kind of a rule violation found by [`OmgWtf8::validate`].
+///
+/// [`OmgWtf8::validate`]: struct.OmgWtf8.html#method.validate
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// A byte which can never appear in OMG-WTF-8 (`f8`–`ff`).
+    InvalidByte,
+    /// A run of continuation bytes not belonging to any sequence.
+    UnexpectedContinuation,
+    /// A sequence missing some of its continuation bytes.
+    Truncated,
+    /// A sequence encoding a code point using more bytes than necessary.
+    Overlong,
+    /// A sequence encoding a value above U+10FFFF.
+    OutOfRange,
+    /// A surrogate in split representation which is not at the edge of the
+    /// string.
+    InteriorSplitSurrogate,
+    /// A high surrogate followed by a low surrogate, which should have been
+    /// encoded as a single 4-byte sequence.
+    SurrogatePair,
+}
+
+/// A single rule violation found by [`OmgWtf8::validate`].
+///
+/// [`OmgWtf8::validate`]: struct.OmgWtf8.html#method.validate
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Violation {
+    offset: usize,
+    kind: ViolationKind,
+}
+
+impl Violation {
+    /// The byte offset where the offending sequence starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The kind of rule being violated.
+    pub fn kind(&self) -> ViolationKind {
+        self.kind
+    }
+}
+
+/// The well-formedness report returned by [`OmgWtf8::validate`].
+///
+/// [`OmgWtf8::validate`]: struct.OmgWtf8.html#method.validate
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Returns true if no violations are found.
+    pub fn is_well_formed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// All violations found, ordered by their offsets.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    fn push(&mut self, offset: usize, kind: ViolationKind) {
+        self.violations.push(Violation { offset, kind });
+    }
+}
+
+/// Checks whether the byte is a UTF-8 continuation byte (`80`–`bf`).
+pub(crate) fn is_continuation_byte(b: u8) -> bool {
+    b & 0xc0 == 0x80
+}
+
+/// Finds the first surrogate in the bytes. In WTF-8, a surrogate is always
+/// encoded as `ed [a0-bf] xx`, so this only looks for its first two bytes.
+pub(crate) fn find_surrogate(bytes: &[u8]) -> Option<usize> {
+    bytes.windows(2).position(|w| w[0] == 0xed && w[1] >= 0xa0)
+}
+
+/// Returns the length of the longest well-formed prefix of the bytes, given
+/// the offset of the first violation.
+///
+/// Everything before the violation is well-formed. The offending sequence
+/// may still be well-formed at the end of a prefix, e.g. a high surrogate
+/// followed by a low surrogate, or a high surrogate in split representation
+/// in the middle, so the prefixes ending within 3 bytes after it are checked
+/// too.
+fn valid_up_to(bytes: &[u8], offset: usize) -> usize {
+    (offset + 1..=cmp::min(offset + 3, bytes.len()))
+        .rev()
+        .find(|&n| OmgWtf8::validate(&bytes[..n]).is_well_formed())
+        .unwrap_or(offset)
+}
+
+impl OmgWtf8 {
+    /// Scans the bytes and reports every violation of the OMG-WTF-8
+    /// well-formedness rules.
+    pub fn validate(bytes: &[u8]) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let len = bytes.len();
+        let continuations_from = |i: usize, max: usize| {
+            bytes[i..]
+                .iter()
+                .take(max)
+                .take_while(|&&b| is_continuation_byte(b))
+                .count()
+        };
+
+        // a low surrogate in split representation is allowed at the beginning.
+        let mut i = if continuations_from(0, 3) == 3 { 3 } else { 0 };
+        let mut prev_high_surrogate = false;
+
+        while i < len {
+            let lead = bytes[i];
+            let (need, min, max) = match lead {
+                0x00..=0x7f => {
+                    i += 1;
+                    prev_high_surrogate = false;
+                    continue;
+                }
+                0x80..=0xbf => {
+                    let run = continuations_from(i, usize::MAX);
+                    report.push(
+                        i,
+                        if run == 3 {
+                            ViolationKind::InteriorSplitSurrogate
+                        } else {
+                            ViolationKind::UnexpectedContinuation
+                        },
+                    );
+                    i += run;
+                    prev_high_surrogate = false;
+                    continue;
+                }
+                0xc0 | 0xc1 => {
+                    report.push(i, ViolationKind::Overlong);
+                    i += 1 + continuations_from(i + 1, 1);
+                    prev_high_surrogate = false;
+                    continue;
+                }
+                0xf5..=0xf7 => {
+                    report.push(i, ViolationKind::OutOfRange);
+                    i += 1 + continuations_from(i + 1, 3);
+                    prev_high_surrogate = false;
+                    continue;
+                }
+                0xf8..=0xff => {
+                    report.push(i, ViolationKind::InvalidByte);
+                    i += 1;
+                    prev_high_surrogate = false;
+                    continue;
+                }
+                0xc2..=0xdf => (1, 0x80, 0xbf),
+                0xe0 => (2, 0xa0, 0xbf),
+                0xe1..=0xef => (2, 0x80, 0xbf),
+                0xf0 => (3, 0x90, 0xbf),
+                0xf1..=0xf3 => (3, 0x80, 0xbf),
+                0xf4 => (3, 0x80, 0x8f),
+            };
+
+            let found = continuations_from(i + 1, need);
+            let second = bytes.get(i + 1).cloned().unwrap_or(0);
+            let second_in_range = min <= second && second <= max;
+            if found < need {
+                if need == 3 && found == 2 && second_in_range {
+                    // a high surrogate in split representation is allowed at
+                    // the end.
+                    if i + 3 != len {
+                        report.push(i, ViolationKind::InteriorSplitSurrogate);
+                    }
+                } else {
+                    report.push(i, ViolationKind::Truncated);
+                }
+            } else if second < min {
+                report.push(i, ViolationKind::Overlong);
+            } else if second > max {
+                report.push(i, ViolationKind::OutOfRange);
+            } else if lead == 0xed && second >= 0xb0 && prev_high_surrogate {
+                report.push(i - 3, ViolationKind::SurrogatePair);
+            }
+            prev_high_surrogate = found == need && lead == 0xed && (0xa0..=0xaf).contains(&second);
+            i += 1 + found;
+        }
+
+        report
+    }
+
+    /// Converts a byte slice into an OMG-WTF-8 string, returning an error
+    /// with the first violation if the bytes are not well-formed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<&Self, FromBytesError> {
+        match Self::validate(bytes).violations().first() {
+            None => Ok(unsafe { Self::from_bytes_unchecked(bytes) }),
+            Some(&violation) => Err(FromBytesError::new(
+                violation,
+                valid_up_to(bytes, violation.offset()),
+            )),
+        }
+    }
+
+    /// Counts the unpaired surrogates in one scan over the bytes, stopping as
+    /// soon as `limit` of them are found.
+    fn count_surrogates(&self, limit: usize) -> usize {
+        let (begin, middle, end) = self.canonicalize();
+        let mut count = (begin != 0) as usize + (end != 0) as usize;
+        let mut rest = middle;
+        while count < limit {
+            match find_surrogate(rest) {
+                Some(pos) => {
+                    count += 1;
+                    rest = &rest[pos + 3..];
+                }
+                None => break,
+            }
+        }
+        count
+    }
+
+    /// Creates a string viewing `len` bytes starting at `ptr`, e.g. in a
+    /// buffer owned by foreign code or a memory-mapped file.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of `std::slice::from_raw_parts` apply: `ptr` must be
+    /// non-null and valid for reads of `len` bytes, which must not be mutated
+    /// during the lifetime `'a`. In addition, the bytes must be a well-formed
+    /// OMG-WTF-8 string. This is verified in debug builds or with the
+    /// `strict` feature, panicking if the bytes are not well-formed.
+    pub unsafe fn from_raw_parts<'a>(ptr: *const u8, len: usize) -> &'a Self {
+        let bytes = slice::from_raw_parts(ptr, len);
+        debug_check_well_formed(bytes);
+        Self::from_bytes_unchecked(bytes)
+    }
+
+    /// Same as [`from_raw_parts`], which now also panics in debug builds if
+    /// the bytes are not well-formed. Kept for compatibility.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`from_raw_parts`]. The validation is skipped in release
+    /// builds without the `strict` feature, so the bytes must still be
+    /// well-formed.
+    ///
+    /// [`from_raw_parts`]: #method.from_raw_parts
+    pub unsafe fn from_raw_parts_debug_checked<'a>(ptr: *const u8, len: usize) -> &'a Self {
+        Self::from_raw_parts(ptr, len)
+    }
+
+    /// Returns the number of unpaired surrogates in this string, whether in
+    /// split or canonical representation.
+    pub fn surrogate_count(&self) -> usize {
+        self.count_surrogates(usize::MAX)
+    }
+
+    /// Returns whether this string contains any unpaired surrogate. The scan
+    /// stops at the first one.
+    pub fn has_unpaired_surrogates(&self) -> bool {
+        self.count_surrogates(1) != 0
+    }
+
+    /// Returns whether `encode_wide` produces well-formed UTF-16, i.e. this
+    /// string contains no unpaired surrogates.
+    ///
+    /// Note that concatenating two strings which are not well-formed may
+    /// still produce a well-formed string, when a high surrogate at the end
+    /// of one pairs with a low surrogate at the start of the other.
+    pub fn is_valid_utf16_when_widened(&self) -> bool {
+        !self.has_unpaired_surrogates()
+    }
+}
+
+#[test]
+fn test_validate_well_formed() {
+    for bytes in &[
+        &b""[..],
+        b"abc",
+        "測試😊".as_bytes(),
+        b"\xed\xa0\x80",
+        b"\xed\xb0\x80\xed\xa0\x80",
+        b"\x90\x80\x80",
+        b"\xf0\x90\x80",
+        b"\x90\x80\x80\xf0\x90\x80",
+        b"\xa9\xa8\x83a\xf4\x8f\xbf",
+    ] {
+        assert!(OmgWtf8::validate(bytes).is_well_formed(), "{:?}", bytes);
+        assert!(OmgWtf8::from_bytes(bytes).is_ok());
+    }
+}
+
+#[test]
+fn test_validate_violations() {
+    fn violations(bytes: &[u8]) -> Vec<(usize, ViolationKind)> {
+        OmgWtf8::validate(bytes)
+            .violations()
+            .iter()
+            .map(|v| (v.offset(), v.kind()))
+            .collect()
+    }
+
+    assert_eq!(violations(b"a\xff"), vec![(1, ViolationKind::InvalidByte)]);
+    assert_eq!(
+        violations(b"a\x80\x80b"),
+        vec![(1, ViolationKind::UnexpectedContinuation)]
+    );
+    assert_eq!(
+        violations(b"\xe6\xb8 \xe6"),
+        vec![(0, ViolationKind::Truncated), (3, ViolationKind::Truncated)]
+    );
+    assert_eq!(
+        violations(b"\xc0\x80\xe0\x80\x80\xf0\x80\x80\x80"),
+        vec![
+            (0, ViolationKind::Overlong),
+            (2, ViolationKind::Overlong),
+            (5, ViolationKind::Overlong),
+        ]
+    );
+    assert_eq!(
+        violations(b"\xf4\x90\x80\x80\xf5\x80\x80\x80"),
+        vec![
+            (0, ViolationKind::OutOfRange),
+            (4, ViolationKind::OutOfRange)
+        ]
+    );
+    assert_eq!(
+        violations(b"a\x90\x80\x80\xf0\x90\x80a"),
+        vec![
+            (1, ViolationKind::InteriorSplitSurrogate),
+            (4, ViolationKind::InteriorSplitSurrogate),
+        ]
+    );
+    assert_eq!(
+        violations(b"a\xed\xa0\x80\xed\xb0\x80"),
+        vec![(1, ViolationKind::SurrogatePair)]
+    );
+    assert!(OmgWtf8::from_bytes(b"\xed\xa0\x80\xed\xb0\x80").is_err());
+
+    let err = OmgWtf8::from_bytes(b"a\xed\xa0\x80\xed\xb0\x80").unwrap_err();
+    assert_eq!(err.valid_up_to(), 4);
+    assert_eq!(err.violation().offset(), 1);
+    assert_eq!(err.violation().kind(), ViolationKind::SurrogatePair);
+    assert_eq!(
+        err.to_string(),
+        "invalid OMG-WTF-8 sequence (SurrogatePair) at byte index 1"
+    );
+}
+
+#[test]
+fn test_from_bytes_valid_up_to() {
+    for &(bytes, valid_up_to) in &[
+        (&b"a\xed\xa0\x80\xed\xb0\x80"[..], 4),
+        (b"a\xf0\x9f\x98b", 4),
+        (b"a\xe2\x82b", 1),
+        (b"\xe2\x82", 0),
+        (b"a\x80", 1),
+        (b"\x80\x80\x80\x80", 3),
+        (b"ab\xff", 2),
+    ] {
+        let err = OmgWtf8::from_bytes(bytes).unwrap_err();
+        assert_eq!(err.valid_up_to(), valid_up_to, "{:x?}", bytes);
+        assert!(OmgWtf8::from_bytes(&bytes[..valid_up_to]).is_ok());
+    }
+}
+
+#[test]
+fn test_surrogate_stats() {
+    let s = OmgWtf8::from_str("😀\u{d7ff}😑");
+    for (t, count) in &[
+        (s, 0),
+        (&s[2..9], 2),
+        (&s[2..], 1),
+        (&s[..9], 1),
+        (&*OmgWtf8::from_wide(&[0xdc00, 0x61, 0xd800]), 2),
+        (OmgWtf8::EMPTY, 0),
+    ] {
+        assert_eq!(t.surrogate_count(), *count, "{:?}", t);
+        assert_eq!(t.has_unpaired_surrogates(), *count != 0);
+        assert_eq!(
+            t.is_valid_utf16_when_widened(),
+            ::std::char::decode_utf16(t.encode_wide()).all(|c| c.is_ok())
+        );
+    }
+}
+
+#[cfg(feature = "zerocopy")]
+#[test]
+fn test_zerocopy_round_trip() {
+    use zerocopy::IntoBytes;
+
+    let s = &OmgWtf8::from_str("a😀b")[..3];
+    let archive = IntoBytes::as_bytes(s).to_vec();
+    assert_eq!(archive, b"a\xf0\x9f\x98");
+    assert_eq!(OmgWtf8::from_bytes(&archive), Ok(s));
+    assert!(OmgWtf8::from_bytes(&archive[2..]).is_err());
+}
+
+#[test]
+fn test_from_raw_parts() {
+    let bytes = "😀a".as_bytes().to_vec();
+    unsafe {
+        let s = OmgWtf8::from_raw_parts(bytes[1..].as_ptr(), 4);
+        assert_eq!(s, &OmgWtf8::from_str("😀a")[2..]);
+        let s = OmgWtf8::from_raw_parts(bytes.as_ptr(), bytes.len());
+        assert_eq!(s, "😀a");
+        let s = OmgWtf8::from_raw_parts_debug_checked(bytes.as_ptr(), bytes.len());
+        assert_eq!(s, "😀a");
+    }
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "strict"))]
+#[should_panic(expected = "bytes are not well-formed OMG-WTF-8")]
+fn test_from_raw_parts_invalid() {
+    let bytes = b"a\xf0\x9f";
+    unsafe {
+        OmgWtf8::from_raw_parts(bytes.as_ptr(), bytes.len());
+    }
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "strict"))]
+#[should_panic(expected = "bytes are not well-formed OMG-WTF-8")]
+fn test_from_raw_parts_invalid_middle() {
+    // the edges are well-formed, but the surrogate pair must be encoded as a
+    // 4-byte sequence.
+    let bytes = b"a\xed\xa0\x80\xed\xb0\x80a";
+    unsafe {
+        OmgWtf8::from_raw_parts(bytes.as_ptr(), bytes.len());
+    }
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "strict"))]
+#[should_panic(expected = "bytes are not well-formed OMG-WTF-8")]
+fn test_from_raw_parts_debug_checked_invalid() {
+    let bytes = b"\xed\xa0\x80\xed\xb0\x80";
+    unsafe {
+        OmgWtf8::from_raw_parts_debug_checked(bytes.as_ptr(), bytes.len());
+    }
+}
@@ -0,0 +1,22 @@
+//! Interoperability with JavaScript strings.
+//!
+//! A JavaScript string is a sequence of UTF-16 code units which may contain
+//! unpaired surrogates, so the conversions here are lossless, unlike going
+//! through `String`.
+
+use js_sys::JsString;
+use OmgWtf8;
+
+impl<'a> From<&'a JsString> for Box<OmgWtf8> {
+    fn from(s: &'a JsString) -> Box<OmgWtf8> {
+        let wide = s.iter().collect::<Vec<u16>>();
+        OmgWtf8::from_wide(&wide)
+    }
+}
+
+impl<'a> From<&'a OmgWtf8> for JsString {
+    fn from(s: &'a OmgWtf8) -> JsString {
+        let wide = s.encode_wide().collect::<Vec<u16>>();
+        JsString::from_char_code(&wide)
+    }
+}
@@ -0,0 +1,78 @@
+//! Display width in terminal columns.
+
+use code_point::CodePoint;
+use unicode_width::UnicodeWidthChar;
+use OmgWtf8;
+
+/// Returns the number of columns taken by a code point. An unpaired
+/// surrogate is rendered as U+FFFD, which takes 1 column.
+fn code_point_width(c: CodePoint) -> usize {
+    match c.to_char() {
+        Some(c) => c.width().unwrap_or(0),
+        None => 1,
+    }
+}
+
+impl OmgWtf8 {
+    /// Returns the number of columns this string takes when displayed in a
+    /// terminal.
+    ///
+    /// Valid characters are measured like `unicode_width`, where control
+    /// characters take no columns. Every unpaired surrogate takes 1 column,
+    /// as it is displayed as U+FFFD REPLACEMENT CHARACTER.
+    pub fn width(&self) -> usize {
+        self.chars().map(code_point_width).sum()
+    }
+
+    /// Returns the longest prefix of this string which fits in `cols`
+    /// columns.
+    ///
+    /// A supplementary character is never split into surrogates.
+    ///
+    /// ```
+    /// use omgwtf8::OmgWtf8;
+    ///
+    /// let s = OmgWtf8::from_str("日本語.txt");
+    /// assert_eq!(s.truncate_to_width(5), "日本");
+    /// assert_eq!(s.truncate_to_width(5).width(), 4);
+    /// ```
+    pub fn truncate_to_width(&self, cols: usize) -> &Self {
+        let mut width = 0;
+        for (i, _, c) in self.wide_indices() {
+            width += code_point_width(c);
+            if width > cols {
+                return &self[..i];
+            }
+        }
+        self
+    }
+}
+
+#[test]
+fn test_width() {
+    let s = OmgWtf8::from_str("a日😀\u{301}\t");
+    assert_eq!(s.width(), 5);
+    assert_eq!(s[..6].width(), 4);
+    assert_eq!(s[6..].width(), 1);
+    assert_eq!(OmgWtf8::from_wide(&[0xdc00, 0x41, 0xd800]).width(), 3);
+    assert_eq!(OmgWtf8::EMPTY.width(), 0);
+}
+
+#[test]
+fn test_truncate_to_width() {
+    let s = OmgWtf8::from_str("a日😀\u{301}b");
+    assert_eq!(s.truncate_to_width(0), "");
+    assert_eq!(s.truncate_to_width(1), "a");
+    assert_eq!(s.truncate_to_width(2), "a");
+    assert_eq!(s.truncate_to_width(4), "a日");
+    assert_eq!(s.truncate_to_width(5), "a日😀\u{301}");
+    assert_eq!(s.truncate_to_width(6), s);
+    assert_eq!(s.truncate_to_width(100), s);
+
+    // a low surrogate, U+0301 and "b".
+    let s = &s[6..];
+    assert_eq!(s.truncate_to_width(0), "");
+    assert_eq!(s.truncate_to_width(1), &s[..5]);
+    assert_eq!(s.truncate_to_width(1).width(), 1);
+    assert_eq!(s.truncate_to_width(2), s);
+}